@@ -0,0 +1,76 @@
+use std::io::{Read, Write};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use crate::error::Result;
+use crate::limits::{Limit, ParseLimits};
+
+/// The compression that is applied to an ODS file (or a compressed object).
+///
+/// This matches the `Compression` enum of the Java version of ODS, so files can be shared
+/// between the two versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// The data is not compressed.
+    None,
+    /// The data is compressed using GZIP.
+    Gzip,
+    /// The data is compressed using ZLIB.
+    Zlib
+}
+
+impl Compression {
+    /// Compress data.
+    ///
+    /// # Params
+    /// data: `&[u8]` -> The data to compress.
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Compression::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    /// Decompress data.
+    ///
+    /// # Params
+    /// data: `&[u8]` -> The compressed data.
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.decompress_limited(data, &ParseLimits::default())
+    }
+
+    /// Decompress data, without decompressing more than the total size limit.
+    ///
+    /// # Params
+    /// data: `&[u8]` -> The compressed data.
+    /// limits: `&ParseLimits` -> The limits to check the decompressed size against.
+    ///
+    /// # Returns
+    /// `OdsError::LimitExceeded` if the decompressed data is larger than `max_total_size`.
+    pub fn decompress_limited(&self, data: &[u8], limits: &ParseLimits) -> Result<Vec<u8>> {
+        // One byte more than the limit is read, so data that is too large can be detected.
+        let max = (limits.max_total_size as u64).saturating_add(1);
+        let mut output = Vec::new();
+        match self {
+            Compression::None => output.extend_from_slice(data),
+            Compression::Gzip => {
+                GzDecoder::new(data).take(max).read_to_end(&mut output)?;
+            }
+            Compression::Zlib => {
+                ZlibDecoder::new(data).take(max).read_to_end(&mut output)?;
+            }
+        }
+        limits.check(Limit::TotalSize, output.len())?;
+        Ok(output)
+    }
+}
\ No newline at end of file
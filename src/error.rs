@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::limits::Limit;
+
+/// The error type returned by every fallible operation in ODS.
+#[derive(Debug)]
+pub enum OdsError {
+    /// An error occurred while reading from or writing to the underlying storage.
+    Io(io::Error),
+    /// The data ended before a complete tag could be read.
+    Truncated,
+    /// A tag id was read that does not belong to any known tag type.
+    UnknownTag(u8),
+    /// A tag was found, but it is not of the type that was requested.
+    TypeMismatch {
+        expected: u8,
+        found: u8
+    },
+    /// No tag exists with the given key.
+    KeyNotFound(String),
+    /// The key is not valid. (Ex: It is empty or contains an empty section.)
+    InvalidKey(String),
+    /// A value could not be serialized to or deserialized from ODS. (Only used by the `serde` feature.)
+    Serde(String),
+    /// The lock on the file could not be taken in time, because another process is using the file.
+    LockTimeout(String),
+    /// The data is larger (or nested deeper) than the `ParseLimits` that it is read with allow.
+    LimitExceeded {
+        limit: Limit,
+        max: usize
+    },
+    /// A char is above `U+00FF`, so it does not fit in the one byte of a Char Tag.
+    InvalidChar(char),
+    /// The operation is not supported for the type of tag. (Ex: `as_tag` on an AnyTag.)
+    Unsupported(String)
+}
+
+/// A `Result` with the error type set to `OdsError`.
+pub type Result<T> = std::result::Result<T, OdsError>;
+
+impl fmt::Display for OdsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OdsError::Io(err) => write!(f, "I/O error: {}", err),
+            OdsError::Truncated => write!(f, "unexpected end of data while reading a tag"),
+            OdsError::UnknownTag(id) => write!(f, "unknown tag id: {}", id),
+            OdsError::TypeMismatch { expected, found } => write!(f, "expected a tag with id {}, found id {}", expected, found),
+            OdsError::KeyNotFound(key) => write!(f, "no tag found with the key `{}`", key),
+            OdsError::InvalidKey(key) => write!(f, "invalid key `{}`", key),
+            OdsError::Serde(msg) => write!(f, "{}", msg),
+            OdsError::LockTimeout(file) => write!(f, "timed out waiting for the lock on `{}`", file),
+            OdsError::LimitExceeded { limit, max } => write!(f, "the {} limit of {} was exceeded", limit, max),
+            OdsError::InvalidChar(character) => write!(f, "{:?} is too large to be stored in a Char Tag", character),
+            OdsError::Unsupported(msg) => write!(f, "{}", msg)
+        }
+    }
+}
+
+impl Error for OdsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            OdsError::Io(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+/// Running out of bytes is reported as `OdsError::Truncated`, everything else is kept as an I/O error.
+impl From<io::Error> for OdsError {
+    fn from(err: io::Error) -> Self {
+        if err.kind() == io::ErrorKind::UnexpectedEof {
+            return OdsError::Truncated;
+        }
+        OdsError::Io(err)
+    }
+}
\ No newline at end of file
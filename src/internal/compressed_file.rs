@@ -0,0 +1,179 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::compression::Compression;
+use crate::error::Result;
+use crate::io::files::write_atomic;
+use crate::io::lock::{lock_file, FileLock, LockMode};
+use crate::internal::memory::ODSMemory;
+use crate::internal::ODSInternal;
+use crate::internal::tag_builder::TagBuilder;
+use crate::tags::general::{AnyTag, Tag, Taggable};
+use crate::tags::value::OdsValue;
+use crate::key_path::KeyPath;
+use crate::limits::{Limit, ParseLimits};
+use crate::validate::{validate, ValidationReport};
+
+/// An ODS file where the whole file is compressed.
+///
+/// The file is decompressed every time it is read and compressed again on every change.
+pub struct ODSCompressedFile {
+    file: PathBuf,
+    compression: Compression,
+    keep_backup: bool,
+    lock_mode: LockMode,
+    limits: ParseLimits
+}
+
+impl ODSInternal for ODSCompressedFile {
+    fn get<T: Taggable<T>>(&mut self, key: KeyPath) -> Result<Tag<T>> {
+        let _lock = self.lock(false)?;
+        self.load()?.get::<T>(key)
+    }
+
+    fn get_many(&mut self, keys: &[KeyPath]) -> Result<Vec<Result<TagBuilder>>> {
+        let _lock = self.lock(false)?;
+        self.load()?.get_many(keys)
+    }
+
+    fn get_all(&mut self) -> Result<Vec<AnyTag>> {
+        let _lock = self.lock(false)?;
+        self.load()?.get_all()
+    }
+
+    fn load_value(&mut self) -> Result<OdsValue> {
+        let _lock = self.lock(false)?;
+        self.load()?.load_value()
+    }
+
+    fn save(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let mut memory = ODSMemory::new(Vec::new());
+        memory.save(tags)?;
+        self.export(memory)
+    }
+
+    fn save_value(&mut self, value: &OdsValue) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let mut memory = ODSMemory::new(Vec::new());
+        memory.save_value(value)?;
+        self.export(memory)
+    }
+
+    fn append<T: Taggable<T>>(&mut self, tag: Tag<T>) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let mut memory = self.load()?;
+        memory.append(tag)?;
+        self.export(memory)
+    }
+
+    fn append_all(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let mut memory = self.load()?;
+        memory.append_all(tags)?;
+        self.export(memory)
+    }
+
+    fn find(&mut self, key: KeyPath) -> Result<bool> {
+        let _lock = self.lock(false)?;
+        self.load()?.find(key)
+    }
+
+    fn delete(&mut self, key: KeyPath) -> Result<bool> {
+        let _lock = self.lock(true)?;
+        let mut memory = self.load()?;
+        if !memory.delete(key)? {
+            return Ok(false);
+        }
+        self.export(memory)?;
+        Ok(true)
+    }
+
+    fn replace_data<T: Taggable<T>>(&mut self, key: KeyPath, replacement: Tag<T>) -> Result<bool> {
+        let _lock = self.lock(true)?;
+        let mut memory = self.load()?;
+        if !memory.replace_data(key, replacement)? {
+            return Ok(false);
+        }
+        self.export(memory)?;
+        Ok(true)
+    }
+
+    fn set<T: Taggable<T>>(&mut self, key: KeyPath, value: Option<Tag<T>>) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let mut memory = self.load()?;
+        memory.set(key, value)?;
+        self.export(memory)
+    }
+
+    fn validate(&mut self) -> Result<ValidationReport> {
+        let _lock = self.lock(false)?;
+        let data = self.load()?.into_bytes();
+        Ok(validate(&data))
+    }
+
+    fn set_parse_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+    }
+}
+
+impl ODSCompressedFile {
+    pub fn new(file: PathBuf, compression: Compression) -> Self {
+        ODSCompressedFile {
+            file,
+            compression,
+            keep_backup: false,
+            lock_mode: LockMode::default(),
+            limits: ParseLimits::default()
+        }
+    }
+
+    /// Set if a backup of the previous version of the file (`<file>.bak`) is kept every time the file is written.
+    pub fn set_keep_backup(&mut self, keep_backup: bool) {
+        self.keep_backup = keep_backup;
+    }
+
+    pub fn get_keep_backup(&self) -> bool {
+        self.keep_backup
+    }
+
+    /// Set how the file waits for other processes that are using it. (See `LockMode`.)
+    ///
+    /// Reads take a shared lock and changes take an exclusive lock, so a change is never lost to another process
+    /// that changes the file at the same time.
+    pub fn set_lock_mode(&mut self, lock_mode: LockMode) {
+        self.lock_mode = lock_mode;
+    }
+
+    pub fn get_lock_mode(&self) -> LockMode {
+        self.lock_mode
+    }
+
+    /// Lock the file for reading (shared) or for changing it (exclusive).
+    fn lock(&self, exclusive: bool) -> Result<Option<FileLock>> {
+        lock_file(&self.file, exclusive, self.lock_mode)
+    }
+
+    /// Get the compression that is used for the file.
+    pub fn get_compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Read and decompress the file. A file that does not exist is treated as an empty document.
+    fn load(&self) -> Result<ODSMemory> {
+        if !self.file.exists() {
+            return Ok(ODSMemory::new(Vec::new()));
+        }
+        self.limits.check(Limit::TotalSize, fs::metadata(&self.file)?.len() as usize)?;
+        let data = fs::read(&self.file)?;
+        ODSMemory::new_with_limits(self.compression.decompress_limited(&data, &self.limits)?, self.limits)
+    }
+
+    /// Compress a document and write it to the file.
+    /// **Note:** This will overwrite the existing data in the file. The file is replaced atomically,
+    /// so a crash leaves either the old or the new document.
+    fn export(&self, memory: ODSMemory) -> Result<()> {
+        let data = self.compression.compress(memory.get_bytes())?;
+        write_atomic(&self.file, &data, self.keep_backup)
+    }
+}
\ No newline at end of file
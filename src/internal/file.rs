@@ -1,145 +1,648 @@
-use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use crate::internal::internal_utils::{delete_sub_object_data, find_sub_object_data, get_list_data, get_sub_object_data, replace_sub_object_data, scout_object_data};
-use crate::internal::keyscout::KeyScout;
+use crate::error::{OdsError, Result};
+use crate::io::files::{append_to_file, write_atomic};
+use crate::io::lock::{lock_file, FileLock, LockMode};
+use crate::io::streams::{IORead, SeekStream, StandardIO};
+use crate::internal::footer::{document_len, Footer};
+use crate::internal::index::{IndexMode, Located, TagIndex};
+use crate::internal::internal_utils::{find_path_data, get_many_data, get_path_data, scout_path_data};
+use crate::internal::journal::{append_record, journal_path, read_journal, read_records, remove_journal, BaseVersion, JournalRecord};
+use crate::internal::memory::{encode_set_tag, encode_tag, ODSMemory};
 use crate::internal::ODSInternal;
-use crate::io::streams::{ReadStream, WriteStream, IOWrite, StandardIO, Stream};
+use crate::internal::tag_builder::TagBuilder;
 use crate::tags::general::{AnyTag, Tag, Taggable};
+use crate::tags::value::OdsValue;
+use crate::key_path::{KeyPath, KeySegment};
+use crate::limits::{Limit, ParseLimits};
+use crate::salvage::{salvage, SalvageReport};
+use crate::validate::{validate, ValidationReport};
+use crate::util::write_any_tags;
 
 pub struct ODSFile {
-    file: PathBuf
+    file: PathBuf,
+    keep_backup: bool,
+    lock_mode: LockMode,
+    limits: ParseLimits,
+    journaled: bool,
+    index_mode: IndexMode,
+    index: Option<FileIndex>,
+    footer_index: bool,
+    sync_in_place: bool,
+    journal: Option<JournalCache>
+}
+
+/// The index of a file, and the version of the file that it was built from.
+struct FileIndex {
+    len: u64,
+    modified: SystemTime,
+    index: TagIndex
+}
+
+/// The document of a file with the changes in its journal, so a change only has to append its record and a read does
+/// not replay the journal.
+struct JournalCache {
+    /// The length and the modified time of the file when the document was read.
+    file: Option<(u64, SystemTime)>,
+    /// The length and the modified time of the journal after the last record was appended.
+    journal: Option<(u64, SystemTime)>,
+    /// The version of the file that the journal applies to.
+    base: BaseVersion,
+    /// The end of the last record of the journal.
+    end: u64,
+    memory: ODSMemory
+}
+
+/// Get the length and the modified time of a file, or None if it does not exist.
+fn file_version(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// Encode the name of a tag with its length, the way it is stored after the id and size of the tag.
+fn header_name(name: &str) -> Vec<u8> {
+    let mut output = (name.len() as i16).to_be_bytes().to_vec();
+    output.extend_from_slice(name.as_bytes());
+    output
+}
+
+/// Where a tag is read from after it was looked up.
+enum Lookup {
+    /// The tag is read from the file, starting at the position of the stream.
+    Stream(KeyPath),
+    /// The tag is read from the bytes of the top level tag that holds it.
+    Tag(Vec<u8>, Located),
+    /// The tag does not exist.
+    Missing
 }
 
 impl ODSInternal for ODSFile {
-    fn get<T: Taggable<T>>(&mut self, key: String) -> Option<Tag<T>> {
-        if !self.file.exists() {
-            return Option::None;
+    fn get<T: Taggable<T>>(&mut self, key: KeyPath) -> Result<Tag<T>> {
+        let _lock = self.lock(false)?;
+        let mut read_stream = match self.open_lazy()? {
+            Some(read_stream) => read_stream,
+            None => return self.read_memory(|memory| memory.get::<T>(key))
+        };
+        match self.lookup(&mut read_stream, &key)? {
+            Lookup::Stream(stream_key) => get_path_data(&mut read_stream, &stream_key, self.limits),
+            Lookup::Tag(tag, located) => get_path_data(&mut located.read_stream(&tag)?, &located.key, located.limits),
+            Lookup::Missing => Err(OdsError::KeyNotFound(key.to_string()))
         }
-        let mut read_stream = Stream::new_from_file(&self.file);
+    }
 
-        get_sub_object_data::<T>(read_stream, key)
+    fn get_many(&mut self, keys: &[KeyPath]) -> Result<Vec<Result<TagBuilder>>> {
+        let _lock = self.lock(false)?;
+        let mut read_stream = match self.open_lazy()? {
+            Some(read_stream) => read_stream,
+            None => return self.read_memory(|memory| memory.get_many(keys))
+        };
+        if let Some(footer) = Footer::read(&mut read_stream)? {
+            read_stream.limit(footer.offset());
+        }
+        read_stream.set_position(0);
+        get_many_data(&mut read_stream, keys, self.limits)
     }
 
-    fn get_all(&mut self) -> Option<Vec<AnyTag>> {
-        if !self.file.exists() {
-            return Option::None;
+    fn get_all(&mut self) -> Result<Vec<AnyTag>> {
+        let _lock = self.lock(false)?;
+        self.read_memory(|memory| memory.get_all())
+    }
+
+    fn load_value(&mut self) -> Result<OdsValue> {
+        let _lock = self.lock(false)?;
+        self.read_memory(|memory| memory.load_value())
+    }
+
+    fn save(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let mut memory = ODSMemory::new(Vec::new());
+        memory.save(tags)?;
+        self.export(memory)
+    }
+
+    fn save_value(&mut self, value: &OdsValue) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let mut memory = ODSMemory::new(Vec::new());
+        memory.save_value(value)?;
+        self.export(memory)
+    }
+
+    fn append<T: Taggable<T>>(&mut self, tag: Tag<T>) -> Result<()> {
+        let _lock = self.lock(true)?;
+        self.append_encoded(encode_tag(tag)?)
+    }
+
+    fn append_all(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        let _lock = self.lock(true)?;
+        self.append_encoded(write_any_tags(&tags)?)
+    }
+
+    fn find(&mut self, key: KeyPath) -> Result<bool> {
+        let _lock = self.lock(false)?;
+        let mut read_stream = match self.open_lazy()? {
+            Some(read_stream) => read_stream,
+            None => return self.read_memory(|memory| memory.find(key))
+        };
+        match self.lookup(&mut read_stream, &key)? {
+            Lookup::Stream(stream_key) => find_path_data(&mut read_stream, &stream_key, self.limits),
+            Lookup::Tag(tag, located) => find_path_data(&mut located.read_stream(&tag)?, &located.key, located.limits),
+            Lookup::Missing => Ok(false)
         }
+    }
 
-        let mut read_stream = Stream::new_from_file(&self.file);
+    fn delete(&mut self, key: KeyPath) -> Result<bool> {
+        let _lock = self.lock(true)?;
+        self.change(JournalRecord::Delete(key))
+    }
 
-        Some(get_list_data(read_stream.clone(), read_stream.size() as i32))
+    fn replace_data<T: Taggable<T>>(&mut self, key: KeyPath, replacement: Tag<T>) -> Result<bool> {
+        let _lock = self.lock(true)?;
+        let data = encode_tag(replacement)?;
+        if self.patch_in_place(&key, &data)? {
+            return Ok(true);
+        }
+        self.change(JournalRecord::Replace(key, data))
     }
 
-    fn append<T: Taggable<T>>(&mut self, tag: Tag<T>) {
-        if !self.file.exists() {
-            File::create(self.file.clone());
+    fn set<T: Taggable<T>>(&mut self, key: KeyPath, value: Option<Tag<T>>) -> Result<()> {
+        let _lock = self.lock(true)?;
+        let data = match value {
+            Some(value) => Some(encode_set_tag(&key, value)?),
+            None => Option::None
+        };
+        if let Some(data) = &data {
+            if self.patch_in_place(&key, data)? {
+                return Ok(());
+            }
         }
-        let mut file = File::open(self.file.clone()).unwrap();
+        self.change(JournalRecord::Set(key, data))?;
+        Ok(())
+    }
 
-        let mut data: Vec<u8> = Vec::new();
-        file.read_to_end(&mut data);
-        let mut stream = Stream::new_with_data(data);
-        T::write_data(tag, &mut stream);
-        stream.export_to_file(self.file.clone());
+    fn validate(&mut self) -> Result<ValidationReport> {
+        let _lock = self.lock(false)?;
+        Ok(validate(&self.read_base()?))
     }
 
-    fn find(&mut self, key: String) -> bool {
-        if !self.file.exists() {
-            return false;
+    fn set_parse_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+        self.index = Option::None;
+        self.journal = Option::None;
+    }
+}
+
+impl ODSFile {
+    pub fn new(file: PathBuf) -> Self {
+        ODSFile {
+            file,
+            keep_backup: false,
+            lock_mode: LockMode::default(),
+            limits: ParseLimits::default(),
+            journaled: false,
+            index_mode: IndexMode::Disabled,
+            index: Option::None,
+            footer_index: false,
+            sync_in_place: true,
+            journal: Option::None
         }
-        let mut read_stream = Stream::new_from_file(&self.file);
+    }
 
-        find_sub_object_data(read_stream, key)
+    /// Create an ODSFile that stores its changes in a journal. (See `set_journaled`.)
+    /// Any changes that are left in the journal are folded into the file.
+    pub fn new_journaled(file: PathBuf) -> Result<Self> {
+        let mut ods_file = ODSFile::new(file);
+        ods_file.set_journaled(true);
+        ods_file.checkpoint()?;
+        Ok(ods_file)
     }
 
-    fn delete(&mut self, key: String) -> bool {
-        if !self.file.exists() {
-            return false;
+    /// Set if a backup of the previous version of the file (`<file>.bak`) is kept every time the file is written.
+    pub fn set_keep_backup(&mut self, keep_backup: bool) {
+        self.keep_backup = keep_backup;
+    }
+
+    pub fn get_keep_backup(&self) -> bool {
+        self.keep_backup
+    }
+
+    /// Set how the file waits for other processes that are using it. (See `LockMode`.)
+    ///
+    /// Reads take a shared lock and changes take an exclusive lock, so a change is never lost to another process
+    /// that changes the file at the same time.
+    pub fn set_lock_mode(&mut self, lock_mode: LockMode) {
+        self.lock_mode = lock_mode;
+    }
+
+    pub fn get_lock_mode(&self) -> LockMode {
+        self.lock_mode
+    }
+
+    /// Lock the file for reading (shared) or for changing it (exclusive).
+    fn lock(&self, exclusive: bool) -> Result<Option<FileLock>> {
+        lock_file(&self.file, exclusive, self.lock_mode)
+    }
+
+    /// Set if changes are appended to a journal (`<file>.odsj`) instead of rewriting the file.
+    ///
+    /// Appending, deleting, replacing and setting tags only appends a small record to the journal.
+    /// The journal is folded into the file by `checkpoint`, or by any change that rewrites the whole file (Ex: `save`).
+    /// Reads always include the changes in the journal.
+    ///
+    /// The document is kept in memory between changes and reads, and is only read again when the file or the journal
+    /// was changed by something else.
+    pub fn set_journaled(&mut self, journaled: bool) {
+        self.journaled = journaled;
+        self.journal = Option::None;
+    }
+
+    pub fn is_journaled(&self) -> bool {
+        self.journaled
+    }
+
+    /// Set which tags are indexed to find them without reading the tags before them. (See `IndexMode`.)
+    ///
+    /// The index is kept in memory and is built again when the file changes. While the file has changes in its
+    /// journal the tags are read from memory instead.
+    pub fn set_index_mode(&mut self, index_mode: IndexMode) {
+        self.index_mode = index_mode;
+        self.index = Option::None;
+    }
+
+    pub fn get_index_mode(&self) -> IndexMode {
+        self.index_mode
+    }
+
+    /// Set if a footer index is written at the end of the file every time it is written. (See `Footer`.)
+    ///
+    /// The footer index is a tag named `$ods_index` that maps the names of the top level tags to their positions, so
+    /// a tag can be found without reading the tags before it. Files with a footer index are read the same way whether
+    /// this is set or not, the tag is never returned by the ObjectDataStructure.
+    pub fn set_footer_index(&mut self, footer_index: bool) {
+        self.footer_index = footer_index;
+    }
+
+    pub fn get_footer_index(&self) -> bool {
+        self.footer_index
+    }
+
+    /// Set if tags that are written in place (appended, or replaced by a tag of the same size) are synced to disk
+    /// before returning.
+    ///
+    /// Without it the writes are faster, but they can be lost to a power loss. (Not to a crash of the process.)
+    pub fn set_sync_in_place(&mut self, sync_in_place: bool) {
+        self.sync_in_place = sync_in_place;
+    }
+
+    pub fn get_sync_in_place(&self) -> bool {
+        self.sync_in_place
+    }
+
+    /// Fold the changes in the journal into the file and remove the journal.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        let _lock = self.lock(true)?;
+        if !journal_path(&self.file).exists() {
+            return Ok(());
+        }
+        let memory = self.load()?;
+        self.export(memory)
+    }
+
+    /// Recover the tags that can still be read from a damaged file. (See `salvage::salvage`.)
+    ///
+    /// # Params
+    /// repair: bool -> If the file should be replaced with the recovered tags when it is damaged.
+    /// The damaged file is kept as a backup (`<file>.bak`) and the journal is removed.
+    pub fn salvage(&mut self, repair: bool) -> Result<SalvageReport> {
+        let _lock = self.lock(repair)?;
+        let report = salvage(&self.read_base()?);
+        if repair && !report.is_intact() {
+            self.index = Option::None;
+            self.journal = Option::None;
+            write_atomic(&self.file, &report.to_bytes()?, true)?;
+            remove_journal(&journal_path(&self.file))?;
+        }
+        Ok(report)
+    }
+
+    /// Open the file to read tags from it lazily, without loading the whole file into memory.
+    /// The stream includes the footer index of the file. (See `lookup`.)
+    ///
+    /// # Returns
+    /// None if the file does not exist or if it has changes in its journal, since those have to be applied in memory.
+    fn open_lazy(&self) -> Result<Option<SeekStream<File>>> {
+        if !self.file.exists() || journal_path(&self.file).exists() {
+            return Ok(Option::None);
+        }
+        let mut read_stream = SeekStream::new(File::open(&self.file)?)?;
+        read_stream.set_limits(self.limits);
+        Ok(Some(read_stream))
+    }
+
+    /// Find where to read a tag from a file that was opened with `open_lazy`.
+    ///
+    /// The in memory index is used if it is enabled, otherwise the footer index of the file is used if the file has
+    /// one and the key starts with a name. The stream is ended before the footer index either way.
+    fn lookup(&mut self, read_stream: &mut SeekStream<File>, key: &KeyPath) -> Result<Lookup> {
+        let footer = Footer::read(read_stream)?;
+        read_stream.set_position(0);
+        if self.index_mode != IndexMode::Disabled {
+            if let Some(footer) = &footer {
+                read_stream.limit(footer.offset());
+            }
+            return Ok(match self.locate(read_stream, key)? {
+                Some((tag, located)) => Lookup::Tag(tag, located),
+                None => Lookup::Missing
+            });
         }
-        let mut read_stream = Stream::new_from_file(&self.file);
-        let mut my_counter = KeyScout::new();
-        scout_object_data(&mut read_stream, key, &mut my_counter);
 
-        if my_counter.get_end().is_none() {
-            return false;
+        let footer = match footer {
+            Some(footer) => footer,
+            None => return Ok(Lookup::Stream(key.clone()))
+        };
+        let mut stream_key = key.clone();
+        if let Some(KeySegment::Name(name)) = key.segments().first() {
+            match footer.find(read_stream, name, &self.limits)? {
+                Some(offset) => read_stream.set_position(offset),
+                None => return Ok(Lookup::Missing)
+            }
+            let mut segments = vec![KeySegment::Index(0)];
+            segments.extend_from_slice(&key.segments()[1..]);
+            stream_key = KeyPath::from_segments(segments);
         }
-        let mut data = read_stream.bytes();
-        delete_sub_object_data(&mut data, &mut my_counter);
+        read_stream.limit(footer.offset());
+        Ok(Lookup::Stream(stream_key))
+    }
+
+    /// Find a tag in the file using its index, and read the top level tag that holds it.
+    /// The file is indexed first if it was changed since it was last indexed.
+    ///
+    /// # Returns
+    /// The bytes of the top level tag and where the tag is inside of them, or None if the tag could not be found.
+    fn locate(&mut self, read_stream: &mut SeekStream<File>, key: &KeyPath) -> Result<Option<(Vec<u8>, Located)>> {
+        let (first, rest) = key.segments().split_first().ok_or_else(|| OdsError::InvalidKey(String::new()))?;
+        let metadata = read_stream.get_ref().metadata()?;
+        let (len, modified) = (metadata.len(), metadata.modified()?);
+        let end = read_stream.size() as u64;
+        let mut rebuilt = false;
+        loop {
+            let file_index = match self.index.take() {
+                Some(file_index) if file_index.len == len && file_index.modified == modified && !rebuilt => file_index,
+                _ => {
+                    read_stream.set_position(0);
+                    FileIndex { len, modified, index: TagIndex::scan(read_stream, end, self.index_mode, &self.limits)? }
+                }
+            };
+            let index = &mut self.index.insert(file_index).index;
+
+            let position = match index.position(first) {
+                Some(position) => position,
+                None => return Ok(Option::None)
+            };
+            let entry = &index.entries()[position];
+            let mut tag = vec![0; entry.size as usize];
+            if entry.offset + entry.size <= end {
+                read_stream.set_position(entry.offset);
+                read_stream.read_vec(&mut tag)?;
+            }
+            // The version of the file is only known to the precision of its modification time, so an index that does
+            // not match the file is built again.
+            if !entry.matches(&tag) && !rebuilt {
+                rebuilt = true;
+                continue;
+            }
+            let located = index.locate_in(position, &tag, 0, rest, self.limits)?;
+            return Ok(located.map(|located| (tag, located)));
+        }
+    }
+
+    /// Read from the document in memory, for a file that cannot be read lazily. (See `open_lazy`.)
+    /// A file with changes in its journal is read from the `JournalCache`, so the journal is only replayed when the
+    /// file or the journal changed.
+    fn read_memory<R>(&mut self, read: impl FnOnce(&mut ODSMemory) -> Result<R>) -> Result<R> {
+        if !journal_path(&self.file).exists() {
+            return read(&mut self.load()?);
+        }
+        let mut cache = self.journal_cache()?;
+        let output = read(&mut cache.memory);
+        self.journal = Some(cache);
+        output
+    }
 
-        let mut write_stream = WriteStream::new();
-        write_stream.write_vec(data);
-        write_stream.export_to_file(self.file.clone());
-        true
+    /// Read the file into memory, including the changes in the journal.
+    /// A file that does not exist is treated as an empty document.
+    fn load(&self) -> Result<ODSMemory> {
+        self.replay(&self.read_base()?)
     }
 
-    fn replace_data<T: Taggable<T>>(&mut self, key: String, replacement: Tag<T>) -> bool {
+    /// Read the file without the changes in the journal and without its footer index.
+    /// A file that is larger than the total size limit is not read at all.
+    fn read_base(&self) -> Result<Vec<u8>> {
         if !self.file.exists() {
-            return false;
+            return Ok(Vec::new());
         }
-        let mut read_stream = Stream::new_from_file(&self.file);
-        let mut my_counter = KeyScout::new();
-        scout_object_data(&mut read_stream, key, &mut my_counter);
+        self.limits.check(Limit::TotalSize, fs::metadata(&self.file)?.len() as usize)?;
+        let mut data = fs::read(&self.file)?;
+        data.truncate(document_len(&data)?);
+        Ok(data)
+    }
 
-        if my_counter.get_end().is_none() {
-            return false;
+    /// Apply the changes in the journal to the bytes of the file.
+    fn replay(&self, base: &[u8]) -> Result<ODSMemory> {
+        let mut memory = ODSMemory::new_with_limits(base.to_vec(), self.limits)?;
+        for record in read_journal(&journal_path(&self.file), base)?.iter() {
+            record.apply(&mut memory)?;
         }
+        Ok(memory)
+    }
 
-        let mut write_tag = Stream::new_empty();
-        T::write_data(replacement, &mut write_tag);
+    /// Append encoded tags to the document.
+    ///
+    /// The tags are added to the end of the file without reading it, unless the whole file has to be written anyway:
+    /// when it has changes in its journal, when a backup is kept, or when it has (or should have) a footer index.
+    fn append_encoded(&mut self, data: Vec<u8>) -> Result<()> {
+        if self.journaled {
+            self.change(JournalRecord::Append(data))?;
+            return Ok(());
+        }
+        if !self.can_append_in_place()? {
+            let mut memory = self.load()?;
+            memory.append_encoded(&data);
+            return self.export(memory);
+        }
 
-        let mut data = read_stream.bytes();
-        let replacement_data = write_tag.bytes();
+        let index_current = self.index_current();
+        // The length and modified time of the file can stay the same, so the cache is not trusted after the file is
+        // written in place.
+        self.journal = Option::None;
+        let offset = append_to_file(&self.file, &data, self.sync_in_place)?;
+        self.index = match self.index.take() {
+            Some(file_index) if index_current => self.index_appended(file_index, offset, &data),
+            _ => Option::None
+        };
+        Ok(())
+    }
 
-        replace_sub_object_data(&mut data, &mut my_counter, &replacement_data);
+    /// Overwrite the tag with the specified key in place, if the new tag has the same size and name.
+    /// Then no other tag moves or changes size, so only the bytes of the tag are written.
+    ///
+    /// # Returns
+    /// If the tag was overwritten. It is not if the file is journaled or has to be written another way
+    /// (See `can_append_in_place`), the key is empty, the tag is not found or is inside of a Compressed Object Tag, or
+    /// the new tag is different.
+    fn patch_in_place(&mut self, key: &KeyPath, data: &[u8]) -> Result<bool> {
+        if self.journaled || key.is_empty() || self.keep_backup || !self.file.exists()
+            || journal_path(&self.file).exists() {
+            return Ok(false);
+        }
+        let mut read_stream = SeekStream::new(OpenOptions::new().read(true).write(true).open(&self.file)?)?;
+        read_stream.set_limits(self.limits);
+        // The footer stays valid, but writing the file adds or removes it when it does not match the setting.
+        match Footer::read(&mut read_stream)? {
+            Some(footer) if self.footer_index => read_stream.limit(footer.offset()),
+            Option::None if !self.footer_index => {}
+            _ => return Ok(false)
+        }
+        read_stream.set_position(0);
+        let current_builder = match scout_path_data(&mut read_stream, key, self.limits)? {
+            Some(current_builder) => current_builder,
+            None => return Ok(false)
+        };
 
-        let mut write_stream = WriteStream::new();
-        write_stream.write_vec(data);
+        // The footer index stores the names of the tags, so the name has to stay the same.
+        let name = current_builder.get_name();
+        let offset = current_builder.starting_index as u64 - 5;
+        if data.len() as u64 != current_builder.data_size as u64 + 5 || data.get(5..7 + name.len()) != Some(&header_name(&name)[..]) {
+            return Ok(false);
+        }
 
-        write_stream.export_to_file(self.file.clone())
+        let index_current = self.index_current();
+        // See `append_encoded`, the cache is not trusted after the file is written in place.
+        self.journal = Option::None;
+        let mut file = read_stream.into_inner();
+        file.seek(SeekFrom::Start(offset))?;
+        file.write_all(data)?;
+        if self.sync_in_place {
+            file.sync_data()?;
+        }
+        // The index only stays the same if the tag has the same type and no children that could have been indexed.
+        let keep_index = index_current && data[0] as i32 == current_builder.data_type && !(9..=12).contains(&data[0]);
+        self.index = match self.index.take() {
+            Some(file_index) if keep_index => self.index_appended(file_index, 0, &[]),
+            _ => Option::None
+        };
+        Ok(true)
     }
 
-    fn set<T: Taggable<T>>(&mut self, key: String, value: Option<Tag<T>>) {
-        if value.is_none() {
-            let output = self.delete(key);
-            if !output {
-                // TODO handle errors
-            }
-            return;
+    /// Check if tags can be appended to the end of the file, without writing the whole file.
+    fn can_append_in_place(&self) -> Result<bool> {
+        if self.keep_backup || self.footer_index || journal_path(&self.file).exists() {
+            return Ok(false);
+        }
+        if !self.file.exists() {
+            return Ok(true);
         }
-        if key == "" {
-            //save(vec![value]);
-            // Hacky method until save works.
-            let mut stream = WriteStream::new();
-            stream.export_to_file(self.file.clone());
+        Ok(Footer::read(&mut SeekStream::new(File::open(&self.file)?)?)?.is_none())
+    }
 
-            self.append(value.unwrap());
-            return;
+    /// Check if the in memory index was built from the file as it is now.
+    fn index_current(&self) -> bool {
+        match (&self.index, fs::metadata(&self.file)) {
+            (Some(file_index), Ok(metadata)) => {
+                metadata.len() == file_index.len && metadata.modified().ok() == Some(file_index.modified)
+            }
+            _ => false
         }
-        let mut read_stream = Stream::new_from_file(&self.file);
-        let mut counter = KeyScout::new();
-        scout_object_data(&mut read_stream, key, &mut counter);
+    }
 
-        if counter.get_end().is_none() {
-            if counter.get_children().len() < 1 {
-                self.append(value.unwrap());
-                return;
+    /// Add tags that were appended to the file to its in memory index.
+    ///
+    /// # Returns
+    /// The index, or None if it has to be built again.
+    fn index_appended(&self, mut file_index: FileIndex, offset: u64, data: &[u8]) -> Option<FileIndex> {
+        let metadata = fs::metadata(&self.file).ok()?;
+        file_index.index.extend(TagIndex::build(data, self.index_mode, &self.limits).ok()?, offset);
+        file_index.len = metadata.len();
+        file_index.modified = metadata.modified().ok()?;
+        Some(file_index)
+    }
+
+    /// Make a change to the document and store it, either as a record in the journal or by writing the changed document.
+    ///
+    /// # Returns
+    /// If the document was changed. (See `JournalRecord::apply`.) Nothing is stored if it was not.
+    fn change(&mut self, record: JournalRecord) -> Result<bool> {
+        if !self.journaled {
+            let mut memory = self.load()?;
+            if !record.apply(&mut memory)? {
+                return Ok(false);
             }
-            let mut existing_key = String::new();
-            for child in counter.get_children().iter() {
-                if existing_key.len() != 0 {
+            self.export(memory)?;
+            return Ok(true);
+        }
 
-                }
+        // The cache is only put back once the change is stored, so a failed change does not leave it in memory.
+        let mut cache = self.journal_cache()?;
+        if !record.apply(&mut cache.memory)? {
+            self.journal = Some(cache);
+            return Ok(false);
+        }
+        let journal = journal_path(&self.file);
+        cache.end = append_record(&journal, cache.base, cache.end, &record)?;
+        cache.journal = file_version(&journal);
+        self.journal = Some(cache);
+        Ok(true)
+    }
+
+    /// Take the document of a journaled file with the changes in its journal. (See `JournalCache`.)
+    /// It is only read and replayed if the file or the journal changed since the last change.
+    fn journal_cache(&mut self) -> Result<JournalCache> {
+        let journal = journal_path(&self.file);
+        if let Some(cache) = self.journal.take() {
+            if cache.file == file_version(&self.file) && cache.journal == file_version(&journal) {
+                return Ok(cache);
             }
         }
+
+        let file = file_version(&self.file);
+        let base = self.read_base()?;
+        let version = BaseVersion::of(&base);
+        let (records, end) = read_records(&journal, version)?;
+        let mut memory = ODSMemory::new_with_limits(base, self.limits)?;
+        for record in records.iter() {
+            record.apply(&mut memory)?;
+        }
+        Ok(JournalCache {
+            file,
+            journal: file_version(&journal),
+            base: version,
+            end,
+            memory
+        })
     }
-}
 
-impl ODSFile {
-    pub fn new(file: PathBuf) -> Self {
-        ODSFile {
-            file
+    /// Write a document to the file and remove the journal, since the document includes its changes.
+    /// **Note:** This will overwrite the existing data in the file. The file is replaced atomically,
+    /// so a crash leaves either the old or the new document.
+    fn export(&mut self, memory: ODSMemory) -> Result<()> {
+        self.index = Option::None;
+        self.journal = Option::None;
+        let journal = journal_path(&self.file);
+        // A journal only applies to the bytes it was started on, so once the new document is written the journal
+        // is ignored, even if the process stops before it is removed. If the file already holds the document only
+        // the journal has to be removed.
+        if !journal.exists() || self.read_base()? != memory.get_bytes() {
+            if self.footer_index {
+                let mut data = memory.into_bytes();
+                data.extend(Footer::encode(&data)?);
+                write_atomic(&self.file, &data, self.keep_backup)?;
+            } else {
+                write_atomic(&self.file, memory.get_bytes(), self.keep_backup)?;
+            }
         }
+        remove_journal(&journal)
     }
 }
\ No newline at end of file
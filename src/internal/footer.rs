@@ -0,0 +1,185 @@
+use std::io::Cursor;
+
+use crate::error::{OdsError, Result};
+use crate::internal::index::{IndexMode, TagIndex};
+use crate::internal::internal_utils::read_header;
+use crate::internal::journal::hash;
+use crate::io::streams::{IORead, SeekStream, StandardIO};
+use crate::limits::{Limit, ParseLimits};
+
+/// The name of the tag that holds the footer index. A tag with this name at the end of a file is reserved.
+pub const FOOTER_NAME: &str = "$ods_index";
+
+const VEC_TAG_ID: u8 = 9;
+const LONG_TAG_ID: u8 = 6;
+/// The header of a Long Tag without a name.
+const LONG_HEADER: [u8; 7] = [LONG_TAG_ID, 0, 0, 0, 10, 0, 0];
+/// The size of a Long Tag without a name.
+const LONG_SIZE: u64 = 15;
+/// The size of an entry: the hash of the name of a tag and the position of the tag, as two Long Tags.
+const ENTRY_SIZE: u64 = 2 * LONG_SIZE;
+
+/// An index of the tags at the top of a file, that is stored as the last tag of the file.
+///
+/// The index is a Vec Tag named `$ods_index` that holds Long Tags without names: the hash of the name and the position
+/// of every other tag (sorted by the hash), and then the position of the index itself. A reader that knows about it
+/// reads the last Long Tag to find the index, and then finds a tag with a binary search of the entries.
+/// Since the index is a normal tag, readers that do not know about it still read a valid document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Footer {
+    /// The position of the footer tag. (The size of the document without it.)
+    offset: u64,
+    /// The position of the first entry.
+    entries_start: u64,
+    /// The number of entries.
+    entries: u64
+}
+
+impl Footer {
+    /// Encode the footer index of a document.
+    ///
+    /// # Params
+    /// data: `&[u8]` -> The encoded tags of the document. The footer has to be written right after them.
+    pub(crate) fn encode(data: &[u8]) -> Result<Vec<u8>> {
+        let index = TagIndex::build(data, IndexMode::TopLevel, &ParseLimits::default())?;
+        let mut entries: Vec<(u64, u64)> = index.entries().iter()
+            .map(|entry| (name_hash(&entry.name), entry.offset))
+            .collect();
+        entries.sort_unstable();
+
+        let data_size = 2 + FOOTER_NAME.len() as u64 + entries.len() as u64 * ENTRY_SIZE + LONG_SIZE;
+        if data_size > i32::MAX as u64 {
+            return Err(OdsError::LimitExceeded {
+                limit: Limit::TagSize,
+                max: i32::MAX as usize
+            });
+        }
+        let mut output = Vec::with_capacity(5 + data_size as usize);
+        output.push(VEC_TAG_ID);
+        output.extend_from_slice(&(data_size as i32).to_be_bytes());
+        output.extend_from_slice(&(FOOTER_NAME.len() as i16).to_be_bytes());
+        output.extend_from_slice(FOOTER_NAME.as_bytes());
+        for (name_hash, offset) in entries {
+            write_long(&mut output, name_hash);
+            write_long(&mut output, offset);
+        }
+        write_long(&mut output, data.len() as u64);
+        Ok(output)
+    }
+
+    /// Read the footer index at the end of any stream.
+    ///
+    /// # Returns
+    /// The footer, or None if the stream does not end with a footer index.
+    pub(crate) fn read<S: IORead + StandardIO>(read_stream: &mut S) -> Result<Option<Footer>> {
+        let size = read_stream.size() as u64;
+        if size < LONG_SIZE {
+            return Ok(Option::None);
+        }
+        read_stream.set_position(size - LONG_SIZE);
+        let mut trailer = [0; LONG_SIZE as usize];
+        read_stream.read_vec(&mut trailer)?;
+        if trailer[..LONG_HEADER.len()] != LONG_HEADER {
+            return Ok(Option::None);
+        }
+        let offset = read_long(&trailer[LONG_HEADER.len()..]);
+        if offset > size - LONG_SIZE {
+            return Ok(Option::None);
+        }
+        let entries_start = offset + 7 + FOOTER_NAME.len() as u64;
+        if entries_start > size - LONG_SIZE || !(size - LONG_SIZE - entries_start).is_multiple_of(ENTRY_SIZE) {
+            return Ok(Option::None);
+        }
+
+        let mut header = vec![0; entries_start as usize - offset as usize];
+        read_stream.set_position(offset);
+        read_stream.read_vec(&mut header)?;
+        let mut expected = vec![VEC_TAG_ID];
+        expected.extend_from_slice(&((size - offset - 5) as i32).to_be_bytes());
+        expected.extend_from_slice(&(FOOTER_NAME.len() as i16).to_be_bytes());
+        expected.extend_from_slice(FOOTER_NAME.as_bytes());
+        if header != expected {
+            return Ok(Option::None);
+        }
+        Ok(Some(Footer {
+            offset,
+            entries_start,
+            entries: (size - LONG_SIZE - entries_start) / ENTRY_SIZE
+        }))
+    }
+
+    /// Get the position of the footer tag, which is the size of the document without it.
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Find the position of the first tag at the top of the document with a name.
+    ///
+    /// # Params
+    /// read_stream: `&mut S` -> The stream that the footer was read from.
+    /// name: `&str` -> The name of the tag.
+    /// limits: `&ParseLimits` -> The limits of the data.
+    ///
+    /// # Returns
+    /// The position of the tag, or None if no tag has the name.
+    pub(crate) fn find<S: IORead + StandardIO>(&self, read_stream: &mut S, name: &str, limits: &ParseLimits) -> Result<Option<u64>> {
+        let target = name_hash(name);
+        let (mut low, mut high) = (0, self.entries);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.read_entry(read_stream, middle)?.0 < target {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        // Entries with the same hash are sorted by their position, so the first tag with the name is found first.
+        for entry in low..self.entries {
+            let (entry_hash, offset) = self.read_entry(read_stream, entry)?;
+            if entry_hash != target {
+                break;
+            }
+            if offset >= self.offset {
+                return Err(OdsError::Truncated);
+            }
+            read_stream.set_position(offset);
+            let current_builder = read_header(read_stream, self.offset, limits)?;
+            if read_stream.read_string(current_builder.name_size as u64)? == name {
+                return Ok(Some(offset));
+            }
+        }
+        Ok(Option::None)
+    }
+
+    /// Read the hash and the position of an entry.
+    fn read_entry<S: IORead + StandardIO>(&self, read_stream: &mut S, entry: u64) -> Result<(u64, u64)> {
+        read_stream.set_position(self.entries_start + entry * ENTRY_SIZE);
+        let mut bytes = [0; ENTRY_SIZE as usize];
+        read_stream.read_vec(&mut bytes)?;
+        let (name_hash, offset) = bytes.split_at(LONG_SIZE as usize);
+        Ok((read_long(&name_hash[LONG_HEADER.len()..]), read_long(&offset[LONG_HEADER.len()..])))
+    }
+}
+
+/// Get the size of encoded tags without the footer index at their end. (See `Footer`.)
+pub(crate) fn document_len(data: &[u8]) -> Result<usize> {
+    let mut read_stream = SeekStream::new(Cursor::new(data))?;
+    Ok(Footer::read(&mut read_stream)?.map_or(data.len(), |footer| footer.offset as usize))
+}
+
+/// The hash of the name of a tag that is stored in the footer index.
+fn name_hash(name: &str) -> u64 {
+    hash(name.as_bytes())
+}
+
+fn write_long(output: &mut Vec<u8>, value: u64) {
+    output.extend_from_slice(&LONG_HEADER);
+    output.extend_from_slice(&value.to_be_bytes());
+}
+
+fn read_long(bytes: &[u8]) -> u64 {
+    let mut value = [0; 8];
+    value.copy_from_slice(&bytes[..8]);
+    u64::from_be_bytes(value)
+}
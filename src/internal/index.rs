@@ -0,0 +1,217 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::ops::Range;
+
+use crate::error::{OdsError, Result};
+use crate::internal::internal_utils::read_header;
+use crate::io::streams::{IORead, SeekStream, StandardIO};
+use crate::key_path::{KeyPath, KeySegment};
+use crate::limits::{Limit, ParseLimits};
+
+/// The size of the id and the data size of a tag.
+const SIZE_PREFIX: u64 = 5;
+
+/// Which tags of a document are indexed, so they can be found without reading the tags before them.
+///
+/// An index is built the first time a key is looked up, and is kept until the document changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexMode {
+    /// Tags are found by reading the document from the start. (The default.)
+    #[default]
+    Disabled,
+    /// The tags at the top of the document are indexed.
+    TopLevel,
+    /// The tags at the top of the document are indexed, and the children of a Vec, Map or Object Tag are indexed
+    /// the first time a key goes through it.
+    Nested
+}
+
+/// The position of an indexed tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub name: String,
+    /// The id of the tag type.
+    pub id: u8,
+    /// The position of the start of the tag.
+    pub offset: u64,
+    /// The size of the whole encoded tag.
+    pub size: u64
+}
+
+impl IndexEntry {
+    /// Get the position of the value of the tag, relative to the start of the tag.
+    fn value_start(&self) -> usize {
+        SIZE_PREFIX as usize + 2 + self.name.len()
+    }
+
+    /// Check that encoded bytes hold the tag of the entry.
+    pub(crate) fn matches(&self, tag: &[u8]) -> bool {
+        tag.len() as u64 == self.size
+            && tag.first() == Some(&self.id)
+            && tag.get(SIZE_PREFIX as usize + 2..self.value_start()) == Some(self.name.as_bytes())
+    }
+}
+
+/// An index of encoded tags, that maps their names to their positions.
+///
+/// When several tags have the same name the first one is found, the same as when the tags are read in order.
+#[derive(Debug, Clone, Default)]
+pub struct TagIndex {
+    entries: Vec<IndexEntry>,
+    names: HashMap<String, usize>,
+    nested: bool,
+    children: HashMap<usize, TagIndex>
+}
+
+/// A tag that was found with an index.
+pub(crate) struct Located {
+    /// The bytes of the tag that holds the key.
+    pub range: Range<usize>,
+    /// The key inside of those bytes. (It starts at the first tag.)
+    pub key: KeyPath,
+    /// The limits of the data inside of the range.
+    pub limits: ParseLimits
+}
+
+impl Located {
+    /// Get a Stream that reads the range from the bytes that the tag was found in.
+    pub fn read_stream<'a>(&self, data: &'a [u8]) -> Result<SeekStream<Cursor<&'a [u8]>>> {
+        let mut read_stream = SeekStream::new(Cursor::new(&data[self.range.clone()]))?;
+        read_stream.set_limits(self.limits);
+        Ok(read_stream)
+    }
+}
+
+impl TagIndex {
+    /// Index the tags in any stream, reading only their headers and names.
+    ///
+    /// # Params
+    /// read_stream: `&mut S` -> The stream to read from. The tags start at the current position.
+    /// end: u64 -> The position where the tags end.
+    /// mode: `IndexMode` -> If the children of the tags are indexed when they are looked up.
+    /// limits: `&ParseLimits` -> The limits of the data.
+    pub(crate) fn scan<S: IORead + StandardIO>(read_stream: &mut S, end: u64, mode: IndexMode, limits: &ParseLimits) -> Result<TagIndex> {
+        limits.check(Limit::TotalSize, read_stream.size())?;
+        let mut index = TagIndex {
+            nested: mode == IndexMode::Nested,
+            ..TagIndex::default()
+        };
+        while read_stream.position() < end {
+            limits.check(Limit::Elements, index.entries.len() + 1)?;
+            let current_builder = read_header(read_stream, end, limits)?;
+            let name = read_stream.read_string(current_builder.name_size as u64)?;
+            index.push(IndexEntry {
+                name,
+                id: current_builder.data_type as u8,
+                offset: current_builder.starting_index as u64 - SIZE_PREFIX,
+                size: current_builder.data_size as u64 + SIZE_PREFIX
+            });
+            read_stream.set_position((current_builder.starting_index + current_builder.data_size as i64) as u64);
+        }
+        Ok(index)
+    }
+
+    /// Index encoded tags. (See `scan`.)
+    pub(crate) fn build(data: &[u8], mode: IndexMode, limits: &ParseLimits) -> Result<TagIndex> {
+        let mut read_stream = SeekStream::new(Cursor::new(data))?;
+        TagIndex::scan(&mut read_stream, data.len() as u64, mode, limits)
+    }
+
+    /// Add the index of tags that were appended to the indexed bytes.
+    ///
+    /// # Params
+    /// appended: `TagIndex` -> The index of the appended tags.
+    /// offset: u64 -> The position that the tags were appended at.
+    pub(crate) fn extend(&mut self, appended: TagIndex, offset: u64) {
+        for mut entry in appended.entries {
+            entry.offset += offset;
+            self.push(entry);
+        }
+    }
+
+    /// Get the indexed tags, in order.
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// Get the position in the index of the tag that a segment of a key points to.
+    pub(crate) fn position(&self, segment: &KeySegment) -> Option<usize> {
+        match segment {
+            KeySegment::Name(name) => self.names.get(name).copied(),
+            KeySegment::Index(index) => Some(*index).filter(|index| *index < self.entries.len())
+        }
+    }
+
+    /// Find the tag with a key in the indexed bytes.
+    ///
+    /// # Returns
+    /// The tag, or None if the tag could not be found.
+    pub(crate) fn locate(&mut self, data: &[u8], key: &[KeySegment], limits: ParseLimits) -> Result<Option<Located>> {
+        let (first, rest) = key.split_first().ok_or_else(|| OdsError::InvalidKey(String::new()))?;
+        let position = match self.position(first) {
+            Some(position) => position,
+            None => return Ok(Option::None)
+        };
+        let entry = &self.entries[position];
+        let range = entry.offset as usize..(entry.offset + entry.size) as usize;
+        let tag = data.get(range.clone()).ok_or(OdsError::Truncated)?;
+        self.locate_in(position, tag, range.start, rest, limits)
+    }
+
+    /// Find the rest of a key inside of an indexed tag.
+    ///
+    /// # Params
+    /// position: usize -> The position of the tag in the index.
+    /// tag: `&[u8]` -> The bytes of the tag.
+    /// start: usize -> The position of the tag in the bytes that the range of the result is relative to.
+    /// rest: `&[KeySegment]` -> The key inside of the tag.
+    /// limits: `ParseLimits` -> The limits of the data that holds the tag.
+    pub(crate) fn locate_in(&mut self, position: usize, tag: &[u8], start: usize, rest: &[KeySegment], limits: ParseLimits) -> Result<Option<Located>> {
+        let entry = &self.entries[position];
+        if rest.is_empty() || !self.nested || !(9..=11).contains(&entry.id) {
+            let mut segments = vec![KeySegment::Index(0)];
+            segments.extend_from_slice(rest);
+            return Ok(Some(Located {
+                range: start..start + tag.len(),
+                key: KeyPath::from_segments(segments),
+                limits
+            }));
+        }
+
+        let value_start = entry.value_start();
+        let value = &tag[value_start..];
+        let limits = limits.descend()?;
+        let child = match self.children.entry(position) {
+            Entry::Occupied(child) => child.into_mut(),
+            Entry::Vacant(child) => child.insert(TagIndex::build(value, IndexMode::Nested, &limits)?)
+        };
+        let offset = start + value_start;
+        Ok(child.locate(value, rest, limits)?.map(|mut located| {
+            located.range = located.range.start + offset..located.range.end + offset;
+            located
+        }))
+    }
+
+    /// Add a tag to the end of the index.
+    fn push(&mut self, entry: IndexEntry) {
+        self.names.entry(entry.name.clone()).or_insert(self.entries.len());
+        self.entries.push(entry);
+    }
+}
+
+/// Find a tag in encoded bytes using their index. The bytes are indexed first if they do not have an index yet.
+///
+/// # Params
+/// index: `&mut Option<TagIndex>` -> The index of the bytes, if they have been indexed.
+/// data: `&[u8]` -> The encoded tags.
+/// key: `&KeyPath` -> The key of the tag.
+/// mode: `IndexMode` -> How the bytes are indexed.
+/// limits: `ParseLimits` -> The limits of the data.
+pub(crate) fn locate(index: &mut Option<TagIndex>, data: &[u8], key: &KeyPath, mode: IndexMode, limits: ParseLimits) -> Result<Option<Located>> {
+    let built = match index.take() {
+        Some(built) => built,
+        None => TagIndex::build(data, mode, &limits)?
+    };
+    index.insert(built).locate(data, key.segments(), limits)
+}
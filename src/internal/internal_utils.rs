@@ -1,378 +1,794 @@
 use std::any::Any;
+use std::convert::TryFrom;
 
+use crate::compression::Compression;
+use crate::error::{OdsError, Result};
 use crate::internal::keyscout::{KeyScout, KeyScoutChild};
 use crate::internal::tag_builder::TagBuilder;
-use crate::io::streams::{ReadStream, StandardIO, IORead, Stream};
-use crate::tags::general::{AnyTag, StringTag, Tag, Taggable};
+use crate::io::streams::{StandardIO, IORead, Stream};
+use crate::key_path::{KeyPath, KeySegment};
+use crate::limits::{Limit, ParseLimits};
+use crate::tags::general::{AnyTag, CompressedObjectContainer, CompressedObjectTag, Container, ObjectContainer, ObjectTag, Tag, Taggable};
+
+/// The id of the Vec Tag.
+const VEC_TAG_ID: i32 = 9;
+/// The id of the Map Tag.
+const MAP_TAG_ID: i32 = 10;
+/// The id of the Object Tag.
+const OBJECT_TAG_ID: i32 = 11;
+/// The id of the Compressed Object Tag.
+const COMPRESSED_OBJECT_TAG_ID: i32 = 12;
+/// The compression that is used for the value of a Compressed Object Tag.
+pub(crate) const OBJECT_COMPRESSION: Compression = Compression::Gzip;
+
+/// Check if a key path can continue inside of a tag. (Ex: An Object Tag or a Vec Tag.)
+fn is_container(data_type: i32) -> bool {
+    data_type == VEC_TAG_ID || data_type == MAP_TAG_ID || data_type == OBJECT_TAG_ID || data_type == COMPRESSED_OBJECT_TAG_ID
+}
 
 /// Get Sub Object data from a Stream using a key.
 ///
 /// # Params
 /// mut read_stream: `Stream` -> The Stream to read from. (Ensure the position is where you want it to be).
-/// key: String -> The key of the object to get.
+/// key: `&KeyPath` -> The key of the object to get.
 ///
 /// # Type Params
 /// T: `Taggable<T>` ->  The data stored by a Tag that you want to get. Ex: If you want to get a
 /// StringTag, then you put String as the type parameter.
 ///
 /// # Returns
-/// `Option<Tag<T>>` -> The obtained Tag. This is None if the key is not found or if the tag
-/// is not of type described in the type parameter.
-pub fn get_sub_object_data<T: Taggable<T>>(mut read_stream: Stream, key: String) -> Option<Tag<T>> {
-    let name_list: Vec<&str> = key.as_str().split('.').collect();
-    let name = name_list[0].to_string();
-    let other_key = get_key(key.as_str().split('.').collect());
+/// `Result<Tag<T>>` -> The obtained Tag. This is `OdsError::KeyNotFound` if the key is not found or
+/// `OdsError::TypeMismatch` if the tag is not of type described in the type parameter.
+pub fn get_sub_object_data<T: Taggable<T>>(read_stream: &mut Stream, key: &KeyPath) -> Result<Tag<T>> {
+    let limits = read_stream.get_limits();
+    get_path_data::<T, Stream>(read_stream, key, limits)
+}
 
-    let mut current_builder = TagBuilder::new();
-    while read_stream.can_read_more() {
-        current_builder.set_data_type(read_stream.read() as i32);
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
-
-        if current_builder.get_name_size() != name.len() as i32 {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
-        }
+/// Get the tag with the specified key from any stream, reading only the tags on the path of the key. (See `find_path_in`.)
+///
+/// # Params
+/// read_stream: `&mut S` -> The stream to read from. The search starts at the current position.
+/// key: `&KeyPath` -> The key of the tag.
+/// limits: `ParseLimits` -> The limits of the data.
+///
+/// # Returns
+/// The tag, `OdsError::KeyNotFound` if the key is not found or `OdsError::TypeMismatch` if the tag is not of type `T`.
+pub(crate) fn get_path_data<T: Taggable<T>, S: IORead + StandardIO>(read_stream: &mut S, key: &KeyPath, limits: ParseLimits) -> Result<Tag<T>> {
+    limits.check(Limit::TotalSize, read_stream.size())?;
+    let end = read_stream.size() as u64;
+    match find_path_in(read_stream, end, key, limits, true)? {
+        Some(current_builder) => current_builder.process::<T>(),
+        None => Err(OdsError::KeyNotFound(key.to_string()))
+    }
+}
 
-        let tag_name = read_stream.read_string(current_builder.get_name_size() as u64);
-        current_builder.set_name(tag_name.clone());
+/// Check if a tag with the specified key exists in any stream, reading only the tags on the path of the key.
+///
+/// # Returns
+/// If the tag exists. A key that continues inside of a tag that cannot hold other tags does not exist.
+pub(crate) fn find_path_data<S: IORead + StandardIO>(read_stream: &mut S, key: &KeyPath, limits: ParseLimits) -> Result<bool> {
+    limits.check(Limit::TotalSize, read_stream.size())?;
+    let end = read_stream.size() as u64;
+    match find_path_in(read_stream, end, key, limits, false) {
+        Ok(current_builder) => Ok(current_builder.is_some()),
+        Err(OdsError::TypeMismatch { .. }) => Ok(false),
+        Err(err) => Err(err)
+    }
+}
 
-        if name != tag_name {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
+/// Get the tags with the specified keys from any stream in a single pass. (See `get_many_in`.)
+///
+/// # Params
+/// read_stream: `&mut S` -> The stream to read from. The search starts at the current position.
+/// keys: `&[KeyPath]` -> The keys of the tags.
+/// limits: `ParseLimits` -> The limits of the data.
+///
+/// # Returns
+/// The builder of every tag (with the value bytes set), in the order of the keys. A key gets the error that
+/// `get_path_data` would return for it (Ex: `OdsError::KeyNotFound`), while an error that stops the stream from
+/// being read is returned for the whole call.
+pub(crate) fn get_many_data<S: IORead + StandardIO>(read_stream: &mut S, keys: &[KeyPath], limits: ParseLimits) -> Result<Vec<Result<TagBuilder>>> {
+    limits.check(Limit::TotalSize, read_stream.size())?;
+    let mut output: Vec<Option<Result<TagBuilder>>> = keys.iter().map(|_| Option::None).collect();
+    let mut pending = Vec::new();
+    for (i, key) in keys.iter().enumerate() {
+        if key.is_empty() {
+            output[i] = Some(Err(OdsError::InvalidKey(String::new())));
+        } else if let Err(err) = limits.check(Limit::Depth, key.len() - 1) {
+            output[i] = Some(Err(err));
+        } else {
+            pending.push((i, key.segments()));
         }
+    }
 
-        let starting_index = current_builder.get_starting_index();
-        let data_size = current_builder.get_data_size();
+    let end = read_stream.size() as u64;
+    get_many_in(read_stream, end, pending, limits, &mut output)?;
+    Ok(output.into_iter().zip(keys)
+        .map(|(found, key)| found.unwrap_or_else(|| Err(OdsError::KeyNotFound(key.to_string()))))
+        .collect())
+}
 
-        current_builder.set_value_length(((starting_index - read_stream.position() as i64) + data_size as i64) as i32);
-        current_builder.set_value_bytes(read_stream);
-        if other_key.is_some() {
-            // TODO Validate not compressed
-            return get_sub_object_data(current_builder.get_value_bytes().unwrap(), other_key.unwrap());
+/// Follow many key paths through any stream at once, reading every tag at most one time.
+///
+/// The tags of a level are read in order, and a tag is only searched (in place, the same as `find_path_in`) when
+/// the rest of a key continues inside of it. A level is not read any further once every key on it has been found.
+///
+/// # Params
+/// read_stream: `&mut S` -> The stream to read from. The tags start at the current position.
+/// end: u64 -> The position where the tags end.
+/// pending: `Vec<(usize, &[KeySegment])>` -> The position of every key in the output, and the rest of the key. (Which is never empty.)
+/// limits: `ParseLimits` -> The limits of the data.
+/// output: `&mut [Option<Result<TagBuilder>>]` -> Where the tags that are found are stored.
+fn get_many_in<S: IORead + StandardIO>(read_stream: &mut S, end: u64, mut pending: Vec<(usize, &[KeySegment])>, limits: ParseLimits,
+                                       output: &mut [Option<Result<TagBuilder>>]) -> Result<()> {
+    let mut count = 0;
+    while !pending.is_empty() && read_stream.position() < end {
+        count += 1;
+        limits.check(Limit::Elements, count)?;
+        let mut current_builder = read_header(read_stream, end, &limits)?;
+        let value_end = (current_builder.starting_index + current_builder.data_size as i64) as u64;
+
+        let candidate = pending.iter().any(|(_, rest)| match &rest[0] {
+            KeySegment::Name(name) => current_builder.name_size == name.len() as i32,
+            KeySegment::Index(index) => count - 1 == *index
+        });
+        if !candidate {
+            read_stream.set_position(value_end);
+            continue;
         }
-        return current_builder.process::<T>();
+        current_builder.set_name(read_stream.read_string(current_builder.name_size as u64)?);
+
+        // Only the first tag with a name is found, so the keys that match are not searched for again.
+        let (matched, rest): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(_, rest)| match &rest[0] {
+            KeySegment::Name(name) => current_builder.name == *name,
+            KeySegment::Index(index) => count - 1 == *index
+        });
+        pending = rest;
+        let (found, mut inner): (Vec<_>, Vec<_>) = matched.into_iter().partition(|(_, rest)| rest.len() == 1);
+        if !inner.is_empty() && !is_container(current_builder.data_type) {
+            for (i, _) in inner.drain(..) {
+                output[i] = Some(Err(OdsError::TypeMismatch {
+                    expected: OBJECT_TAG_ID as u8,
+                    found: current_builder.data_type as u8
+                }));
+            }
+        }
+        let inner: Vec<_> = inner.into_iter().map(|(i, rest)| (i, &rest[1..])).collect();
+
+        if !found.is_empty() || (!inner.is_empty() && current_builder.data_type == COMPRESSED_OBJECT_TAG_ID) {
+            // The value is read anyway, so the keys inside of it are followed in memory.
+            finish_tag(read_stream, &mut current_builder, limits)?;
+            if !inner.is_empty() {
+                let mut value_stream = read_container_value(&current_builder)?;
+                let (value_size, value_limits) = (value_stream.size() as u64, value_stream.get_limits());
+                get_many_in(&mut value_stream, value_size, inner, value_limits, output)?;
+            }
+            for (i, _) in found {
+                output[i] = Some(Ok(current_builder.clone()));
+            }
+        } else if !inner.is_empty() {
+            get_many_in(read_stream, value_end, inner, limits.descend()?, output)?;
+        }
+        read_stream.set_position(value_end);
     }
+    Ok(())
+}
 
-    Option::None
+/// Follow a key path through the stream. (See `find_path_in`.)
+///
+/// # Returns
+/// The builder of the tag, or None if the tag could not be found.
+fn find_path(read_stream: &mut Stream, key: &KeyPath, read_value: bool) -> Result<Option<TagBuilder>> {
+    let limits = read_stream.get_limits();
+    limits.check(Limit::TotalSize, read_stream.size())?;
+    let end = read_stream.size() as u64;
+    find_path_in(read_stream, end, key, limits, read_value)
+}
+
+/// Read the header of the tag at the current position of the stream.
+///
+/// This validates that the tag fits inside of the stream, so a corrupt size can never
+/// move the cursor backwards or past the end of the data, and that the stream and the tag are within the limits of the stream.
+/// After this the stream is positioned at the start of the name of the tag.
+pub(crate) fn read_tag_header(read_stream: &mut Stream) -> Result<TagBuilder> {
+    let limits = read_stream.get_limits();
+    limits.check(Limit::TotalSize, read_stream.size())?;
+    let end = read_stream.size() as u64;
+    read_header(read_stream, end, &limits)
 }
 
-fn get_key(s: Vec<&str>) -> Option<String> {
-    let mut list = Vec::from(s);
-    list.remove(0);
-    if list.len() == 1 {
-        return Some(String::from(list[0]));
+/// Read the header of the tag at the current position of any stream. (See `read_tag_header`.)
+///
+/// # Params
+/// read_stream: `&mut S` -> The stream to read from.
+/// end: u64 -> The position that the tag has to end before. (Ex: The end of its parent.)
+/// limits: `&ParseLimits` -> The limits of the data that holds the tag.
+pub(crate) fn read_header<S: IORead + StandardIO>(read_stream: &mut S, end: u64, limits: &ParseLimits) -> Result<TagBuilder> {
+    let mut current_builder = TagBuilder::new();
+    current_builder.set_data_type(read_stream.read()? as i32);
+    current_builder.set_data_size(read_stream.read_i32()?);
+    current_builder.set_starting_index(read_stream.position() as i64);
+
+    let data_size = current_builder.data_size as i64;
+    if data_size < 2 || current_builder.starting_index + data_size > end as i64 {
+        return Err(OdsError::Truncated);
     }
-    if list.len() < 1 {
-        return Option::None;
+    limits.check(Limit::TagSize, data_size as usize)?;
+
+    current_builder.set_name_size(read_stream.read_i16()? as i32);
+    if current_builder.name_size < 0 || current_builder.name_size as i64 > data_size - 2 {
+        return Err(OdsError::Truncated);
     }
+    limits.check(Limit::NameLength, current_builder.name_size as usize)?;
 
-    Some(list.join("."))
+    Ok(current_builder)
 }
 
-pub fn get_sub_object_by_index<T: Taggable<T>>(mut read_stream: Stream, index: i32) -> Option<Tag<T>> {
-    let mut current_builder = TagBuilder::new();
-    let mut i = -1;
-    while read_stream.can_read_more() {
-        i += 1;
-        current_builder.set_data_type(read_stream.read() as i32);
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
+/// Count a tag that was read from the stream.
+///
+/// # Returns
+/// `OdsError::LimitExceeded` if the stream holds more tags than its limits allow.
+fn count_tag(read_stream: &Stream, count: &mut usize) -> Result<()> {
+    *count += 1;
+    read_stream.get_limits().check(Limit::Elements, *count)
+}
 
-        if i != index {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
+/// Move the stream to the end of the tag described by the builder.
+fn skip_tag(read_stream: &mut Stream, current_builder: &TagBuilder) {
+    read_stream.set_position((current_builder.starting_index + current_builder.data_size as i64) as u64);
+}
+
+/// Finish building a tag whose name has already been read.
+/// This sets the value length and reads the value bytes into the builder, which moves the stream to the end of the tag.
+fn finish_tag<S: IORead + StandardIO>(read_stream: &mut S, current_builder: &mut TagBuilder, limits: ParseLimits) -> Result<()> {
+    let value_end = current_builder.starting_index + current_builder.data_size as i64;
+    current_builder.set_value_length((value_end - read_stream.position() as i64) as i32);
+
+    let mut value = vec![0; current_builder.value_length as usize];
+    read_stream.read_vec(&mut value)?;
+    let mut value_stream = Stream::new_with_data(value);
+    value_stream.set_limits(limits);
+    value_stream.set_position(0);
+    current_builder.set_value_bytes(value_stream);
+    Ok(())
+}
+
+/// Find the tag with the specified name in the stream. The name is not split into a key, so it may contain dots.
+///
+/// # Returns
+/// The builder of the tag (with the value bytes set), or None if the tag could not be found.
+fn find_tag(read_stream: &mut Stream, name: &str) -> Result<Option<TagBuilder>> {
+    find_path(read_stream, &KeyPath::from_segments(vec![KeySegment::Name(name.to_string())]), true)
+}
+
+/// Find the tag at the specified position in the stream.
+///
+/// # Returns
+/// The builder of the tag (with the value bytes set), or None if there are not enough tags.
+fn find_tag_by_index(read_stream: &mut Stream, index: usize) -> Result<Option<TagBuilder>> {
+    find_path(read_stream, &KeyPath::from_segments(vec![KeySegment::Index(index)]), true)
+}
+
+/// Follow a key path through any stream, without reading the tags that are not on the path.
+///
+/// Tags that do not match the key are skipped using their data size and containers are searched in place,
+/// so only the headers on the path and the value of the found tag are read. The value of a Compressed Object Tag
+/// has to be decompressed, so the rest of the key is followed in memory.
+///
+/// # Params
+/// read_stream: `&mut S` -> The stream to read from. The search starts at the current position.
+/// end: u64 -> The position where the tags end.
+/// key: `&KeyPath` -> The key of the tag.
+/// limits: `ParseLimits` -> The limits of the data.
+/// read_value: bool -> If the value of the found tag should be read into the builder. Without it only the header
+/// and the name of the tag are read.
+///
+/// # Returns
+/// The builder of the tag, or None if the tag could not be found.
+/// `OdsError::TypeMismatch` if the key path continues inside of a tag that cannot hold other tags.
+/// `OdsError::InvalidKey` if the key path is empty.
+fn find_path_in<S: IORead + StandardIO>(read_stream: &mut S, mut end: u64, key: &KeyPath, mut limits: ParseLimits,
+                                                   read_value: bool) -> Result<Option<TagBuilder>> {
+    let parents = match key.segments().split_last() {
+        Some((_, parents)) => parents,
+        None => return Err(OdsError::InvalidKey(String::new()))
+    };
+    limits.check(Limit::Depth, parents.len())?;
+
+    for (i, segment) in key.segments().iter().enumerate() {
+        let mut current_builder = match seek_segment(read_stream, end, segment, &limits)? {
+            Some(current_builder) => current_builder,
+            None => return Ok(Option::None)
+        };
+        let value_end = (current_builder.starting_index + current_builder.data_size as i64) as u64;
+
+        if i == parents.len() {
+            if read_value {
+                finish_tag(read_stream, &mut current_builder, limits)?;
+            }
+            return Ok(Some(current_builder));
+        }
+        if current_builder.data_type == COMPRESSED_OBJECT_TAG_ID {
+            finish_tag(read_stream, &mut current_builder, limits)?;
+            let rest = KeyPath::from_segments(key.segments()[i + 1..].to_vec());
+            return find_path(&mut read_container_value(&current_builder)?, &rest, read_value);
+        }
+        if !is_container(current_builder.data_type) {
+            return Err(OdsError::TypeMismatch {
+                expected: OBJECT_TAG_ID as u8,
+                found: current_builder.data_type as u8
+            });
+        }
+        limits = limits.descend()?;
+        end = value_end;
+    }
+    Ok(Option::None)
+}
+
+/// Find the tag that a segment of a key path points to, skipping the tags before it.
+///
+/// # Returns
+/// The builder of the tag (with the name set), or None if the tag could not be found.
+/// The stream is positioned at the start of the value of the tag.
+fn seek_segment<S: IORead + StandardIO>(read_stream: &mut S, end: u64, segment: &KeySegment, limits: &ParseLimits) -> Result<Option<TagBuilder>> {
+    let mut count = 0;
+    while read_stream.position() < end {
+        count += 1;
+        limits.check(Limit::Elements, count)?;
+        let mut current_builder = read_header(read_stream, end, limits)?;
+
+        let found = match segment {
+            KeySegment::Name(name) => current_builder.name_size == name.len() as i32,
+            KeySegment::Index(index) => count - 1 == *index
+        };
+        if found {
+            let tag_name = read_stream.read_string(current_builder.name_size as u64)?;
+            if let KeySegment::Name(name) = segment {
+                if *name != tag_name {
+                    read_stream.set_position((current_builder.starting_index + current_builder.data_size as i64) as u64);
+                    continue;
+                }
+            }
+            current_builder.set_name(tag_name);
+            return Ok(Some(current_builder));
         }
+        read_stream.set_position((current_builder.starting_index + current_builder.data_size as i64) as u64);
+    }
 
-        let tag_name = read_stream.read_string(current_builder.get_name_size() as u64);
-        current_builder.set_name(tag_name.clone());
+    Ok(Option::None)
+}
 
-        let starting_index = current_builder.get_starting_index();
-        let data_size = current_builder.get_data_size();
+/// Find where the tag with the specified key is stored in any stream, reading only the headers on the path of the key.
+///
+/// # Params
+/// read_stream: `&mut S` -> The stream to read from. The search starts at the current position.
+/// key: `&KeyPath` -> The key of the tag.
+/// limits: `ParseLimits` -> The limits of the data.
+///
+/// # Returns
+/// The builder of the tag (with the name set), or None if the tag could not be found. A tag inside of a Compressed Object
+/// Tag is not found either, since it is not stored in the stream as it is.
+pub(crate) fn scout_path_data<S: IORead + StandardIO>(read_stream: &mut S, key: &KeyPath, mut limits: ParseLimits) -> Result<Option<TagBuilder>> {
+    let (last, parents) = key.segments().split_last().ok_or_else(|| OdsError::InvalidKey(String::new()))?;
+    limits.check(Limit::Depth, parents.len())?;
+    limits.check(Limit::TotalSize, read_stream.size())?;
+
+    let mut end = read_stream.size() as u64;
+    for segment in parents {
+        let current_builder = match seek_segment(read_stream, end, segment, &limits)? {
+            Some(current_builder) => current_builder,
+            None => return Ok(Option::None)
+        };
+        if !is_container(current_builder.data_type) || current_builder.data_type == COMPRESSED_OBJECT_TAG_ID {
+            return Ok(Option::None);
+        }
+        limits = limits.descend()?;
+        end = (current_builder.starting_index + current_builder.data_size as i64) as u64;
+    }
+    seek_segment(read_stream, end, last, &limits)
+}
 
-        current_builder.set_value_length(((starting_index - read_stream.position() as i64) + data_size as i64) as i32);
-        current_builder.set_value_bytes(read_stream);
+/// Create a Stream that contains only the value of a tag that holds other tags.
+/// The value of a Compressed Object Tag is decompressed.
+///
+/// # Returns
+/// `OdsError::TypeMismatch` if the tag cannot hold other tags.
+fn read_container_value(current_builder: &TagBuilder) -> Result<Stream> {
+    if !is_container(current_builder.data_type) {
+        return Err(OdsError::TypeMismatch {
+            expected: OBJECT_TAG_ID as u8,
+            found: current_builder.data_type as u8
+        });
+    }
 
-        return current_builder.process::<T>();
+    let mut value_stream = current_builder.get_value_bytes()?;
+    let limits = value_stream.get_limits();
+    let mut value = vec![0; current_builder.value_length as usize];
+    value_stream.read_vec(&mut value)?;
+    if current_builder.data_type == COMPRESSED_OBJECT_TAG_ID {
+        value = OBJECT_COMPRESSION.decompress_limited(&value, &limits)?;
     }
 
-    Option::None
+    let mut output = Stream::new_with_data(value);
+    output.set_limits(limits.descend()?);
+    output.set_position(0);
+    Ok(output)
 }
 
-pub fn get_data_type_by_index<T: Taggable<T>>(mut read_stream: Stream, index: i32) -> i32 {
-    let mut current_builder = TagBuilder::new();
+pub fn get_sub_object_by_index<T: Taggable<T>>(read_stream: &mut Stream, index: i32) -> Result<Tag<T>> {
+    let current_builder = match usize::try_from(index) {
+        Ok(index) => find_tag_by_index(read_stream, index)?,
+        Err(_) => Option::None
+    };
+    match current_builder {
+        Some(current_builder) => current_builder.process::<T>(),
+        None => Err(OdsError::KeyNotFound(index.to_string()))
+    }
+}
+
+pub fn get_data_type_by_index<T: Taggable<T>>(read_stream: &mut Stream, index: i32) -> Result<i32> {
     let mut i = -1;
+    let mut count = 0;
     while read_stream.can_read_more() {
         i += 1;
-        current_builder.set_data_type(read_stream.read() as i32);
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
+        count_tag(read_stream, &mut count)?;
+        let current_builder = read_tag_header(read_stream)?;
 
         if i != index {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
+            skip_tag(read_stream, &current_builder);
             continue;
         }
 
-        return current_builder.get_data_type();
+        return Ok(current_builder.data_type);
     }
 
-    0
+    Ok(0)
 }
 
-pub fn get_sub_object_type<T: Taggable<T>>(mut read_stream: Stream, key: String) -> i32 {
-    let name_list: Vec<&str> = key.as_str().split('.').collect();
-    let name = name_list[0].to_string();
-    let other_key = get_key(key.as_str().split('.').collect());
-
-    let mut current_builder = TagBuilder::new();
-    while read_stream.can_read_more() {
-        current_builder.set_data_type(read_stream.read() as i32);
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
-
-        if current_builder.get_name_size() != name.len() as i32 {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
-        }
-
-        let tag_name = read_stream.read_string(current_builder.get_name_size() as u64);
-        current_builder.set_name(tag_name.clone());
+/// Get the id of the tag with the specified key.
+///
+/// # Returns
+/// The id of the tag, or 0 if the tag could not be found.
+pub fn get_sub_object_type<T: Taggable<T>>(read_stream: &mut Stream, key: &KeyPath) -> Result<i32> {
+    match find_path(read_stream, key, false) {
+        Ok(Some(current_builder)) => Ok(current_builder.data_type),
+        Ok(None) | Err(OdsError::TypeMismatch { .. }) => Ok(0),
+        Err(err) => Err(err)
+    }
+}
 
-        if name != tag_name {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
-        }
+/// Get the tag with the exact name from the stream. Unlike `get_sub_object_data` the name is not split into a key,
+/// so it may contain dots.
+pub fn get_sub_object_by_name<T: Taggable<T>>(read_stream: &mut Stream, name: String) -> Result<Tag<T>> {
+    match find_tag(read_stream, &name)? {
+        Some(current_builder) => current_builder.process::<T>(),
+        None => Err(OdsError::KeyNotFound(name))
+    }
+}
 
-        return current_builder.get_data_type();
+/// Get the names of all of the tags in the stream.
+pub fn get_object_names(read_stream: &mut Stream) -> Result<Vec<String>> {
+    let mut output = Vec::new();
+    let mut count = 0;
+    while read_stream.can_read_more() {
+        count_tag(read_stream, &mut count)?;
+        let current_builder = read_tag_header(read_stream)?;
+        output.push(read_stream.read_string(current_builder.name_size as u64)?);
+        skip_tag(read_stream, &current_builder);
     }
 
-    0
+    Ok(output)
 }
 
-pub fn get_object_count(mut read_stream: Stream) -> usize {
-    let mut current_builder = TagBuilder::new();
+pub fn get_object_count(read_stream: &mut Stream) -> Result<usize> {
     let mut i: usize = 0;
     while read_stream.can_read_more() {
-        i += 1;
-        current_builder.set_data_type(read_stream.read() as i32);
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
-
-        read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-        current_builder = TagBuilder::new();
+        count_tag(read_stream, &mut i)?;
+        let current_builder = read_tag_header(read_stream)?;
+        skip_tag(read_stream, &current_builder);
     }
 
-    i
+    Ok(i)
 }
 
-pub fn get_list_data(mut read_stream: Stream, limit: i32) -> Vec<AnyTag> {
-    let mut output: Vec<AnyTag> = Vec::new();
-
+pub fn get_list_data(mut read_stream: Stream, limit: i32) -> Result<Vec<AnyTag>> {
+    let limits = read_stream.get_limits();
+    limits.check(Limit::TotalSize, read_stream.size())?;
+    let end = (read_stream.position() as i64 + limit as i64) as u64;
+    read_list_data(&mut read_stream, end, limits)
+}
 
-    let initial_pos: i32 = read_stream.position() as i32;
+/// Read all of the tags from any stream. (See `get_list_data`.)
+///
+/// # Params
+/// read_stream: `&mut S` -> The stream to read from. The tags start at the current position.
+/// end: u64 -> The position where the tags end.
+/// limits: `ParseLimits` -> The limits of the data.
+pub(crate) fn read_list_data<S: IORead + StandardIO>(read_stream: &mut S, end: u64, limits: ParseLimits) -> Result<Vec<AnyTag>> {
+    let mut output: Vec<AnyTag> = Vec::new();
 
-    while (read_stream.position() as i32) < initial_pos + limit {
-        let mut current_builder = TagBuilder::new();
-        current_builder.set_data_type(read_stream.read() as i32);
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
-        let tag_name = read_stream.read_string(current_builder.get_name_size() as u64);
+    let size = read_stream.size() as u64;
+    while read_stream.position() < end {
+        limits.check(Limit::Elements, output.len() + 1)?;
+        let mut current_builder = read_header(read_stream, size, &limits)?;
+        let tag_name = read_stream.read_string(current_builder.name_size as u64)?;
         current_builder.set_name(tag_name);
-        current_builder.set_value_length(((current_builder.starting_index as i32) - (read_stream.position() as i32)) + current_builder.data_size);
-        current_builder.set_value_bytes(read_stream.clone());
-        read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-        output.push(current_builder.process::<Box<dyn Any>>().unwrap());
+        finish_tag(read_stream, &mut current_builder, limits)?;
+        output.push(current_builder.process::<Box<dyn Any>>()?);
     }
 
-    output
+    Ok(output)
 }
 
-pub fn find_sub_object_data(mut read_stream: Stream, key: String) -> bool {
-    let name_list: Vec<&str> = key.as_str().split('.').collect();
-    let name = name_list[0].to_string();
-    let other_key = get_key(key.as_str().split('.').collect());
-
-    let mut current_builder = TagBuilder::new();
-    while read_stream.can_read_more() {
-        current_builder.set_data_type(read_stream.read() as i32);
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
-        if current_builder.get_name_size() != name.len() as i32 {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
-        }
+pub fn find_sub_object_data(read_stream: &mut Stream, key: &KeyPath) -> Result<bool> {
+    let limits = read_stream.get_limits();
+    find_path_data(read_stream, key, limits)
+}
 
-        let tag_name = read_stream.read_string(current_builder.get_name_size() as u64);
-        current_builder.set_name(tag_name.clone());
+pub fn delete_sub_object_data<'a>(data: &'a mut Vec<u8>, counter: &'a mut KeyScout) -> Result<&'a mut Vec<u8>> {
+    replace_sub_object_data(data, counter, &[])
+}
 
-        if name != tag_name {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
-        }
+pub fn replace_sub_object_data<'a>(data: &'a mut Vec<u8>, counter: &'a mut KeyScout, data_to_replace: &[u8]) -> Result<&'a mut Vec<u8>> {
+    let end = match counter.get_end() {
+        Some(end) => end.clone(),
+        None => return Err(OdsError::KeyNotFound(String::new()))
+    };
+    let start = (end.get_starting_index() - 1) as usize;
+    let size = (end.get_size() + 5) as usize;
+    if data_to_replace.len() == size {
+        // Nothing else has to move or change size, so only the bytes of the tag are overwritten.
+        data[start..start + size].copy_from_slice(data_to_replace);
+        return Ok(data);
+    }
+    counter.remove_amount(end.get_size() + 5);
+    counter.add_amount(data_to_replace.len() as i32);
 
-        let starting_index = current_builder.get_starting_index();
-        let data_size = current_builder.get_data_size();
+    // Replace the data
+    data.splice(start..start + size, data_to_replace.iter().cloned());
 
-        current_builder.set_value_length(((starting_index - read_stream.position() as i64) + data_size as i64) as i32);
-        current_builder.set_value_bytes(read_stream);
+    for child in counter.get_children().iter() {
+        let index = child.get_starting_index() as usize;
+        data[index..index + 4].copy_from_slice(&child.get_size().to_be_bytes());
+    }
 
-        if other_key.is_some() {
-            // TODO Validate not compressed
-            return find_sub_object_data(current_builder.get_value_bytes().unwrap(), other_key.unwrap());
-        }
+    Ok(data)
+}
 
-        return true;
+/// Replace the tag with the specified key. The tag is deleted if `data_to_replace` is empty.
+///
+/// Keys that continue into a Compressed Object Tag are supported. The value of the Compressed Object
+/// is decompressed, edited and compressed again, and the sizes of its parents are updated.
+///
+/// # Params
+/// data: `&mut Vec<u8>` -> The data to edit.
+/// key: `&KeyPath` -> The key of the tag to replace.
+/// data_to_replace: `&[u8]` -> The encoded replacement tag.
+/// limits: `&ParseLimits` -> The limits to read the data with.
+///
+/// # Returns
+/// If the tag was found.
+pub fn edit_object_data(data: &mut Vec<u8>, key: &KeyPath, data_to_replace: &[u8], limits: &ParseLimits) -> Result<bool> {
+    let mut read_stream = Stream::new_with_data(data.clone());
+    read_stream.set_limits(*limits);
+    read_stream.set_position(0);
+    let mut counter = KeyScout::new();
+    scout_object_data(&mut read_stream, key, &mut counter)?;
+
+    if counter.get_end().is_some() {
+        replace_sub_object_data(data, &mut counter, data_to_replace)?;
+        return Ok(true);
     }
 
-    false
+    edit_compressed_object(data, &mut counter, limits, |value, other_key, limits| edit_object_data(value, &other_key, data_to_replace, limits))
 }
 
-pub fn delete_sub_object_data<'a>(data: &'a mut Vec<u8>, counter: &'a mut KeyScout) -> &'a mut Vec<u8> {
-    let end_amount = counter.get_end().as_ref().unwrap().get_size() + 5;
-    counter.remove_amount(end_amount);
-    let mut end = counter.get_end().as_ref().unwrap();
-
-    // Remove the data
-    data.splice(((end.get_starting_index() - 1) as usize)..((end.get_starting_index() + 4 + end.get_size()) as usize), Vec::new().iter().cloned());
+/// Set the tag with the specified key. An existing tag is replaced, otherwise the tag is added
+/// and any Object Tags that are missing along the key are created.
+///
+/// # Params
+/// data: `&mut Vec<u8>` -> The data to edit.
+/// key: `&KeyPath` -> The key of the tag to set.
+/// tag_data: `&[u8]` -> The encoded tag. (Its name should be the last section of the key.)
+/// limits: `&ParseLimits` -> The limits to read the data with.
+///
+/// # Returns
+/// `OdsError::TypeMismatch` if a section of the key exists but is not an object, or `OdsError::KeyNotFound`
+/// if an index section of the key does not exist. (Only names can be created.)
+pub fn set_object_data(data: &mut Vec<u8>, key: &KeyPath, tag_data: &[u8], limits: &ParseLimits) -> Result<()> {
+    let mut read_stream = Stream::new_with_data(data.clone());
+    read_stream.set_limits(*limits);
+    read_stream.set_position(0);
+    let mut counter = KeyScout::new();
+    scout_object_data(&mut read_stream, key, &mut counter)?;
+
+    if counter.get_end().is_some() {
+        replace_sub_object_data(data, &mut counter, tag_data)?;
+        return Ok(());
+    }
 
-    for child in counter.get_children().iter() {
-        let index = child.get_starting_index() as usize;
-        let size : usize = child.get_size() as usize;
-        data[index] = (size >> 24) as u8;
-        data[index + 1] = (size >> 16) as u8;
-        data[index + 2] = (size >> 8) as u8;
-        data[index + 3] = (size) as u8;
+    if counter.get_compressed().is_some() {
+        edit_compressed_object(data, &mut counter, limits, |value, other_key, limits| set_object_data(value, &other_key, tag_data, limits).map(|_| true))?;
+        return Ok(());
     }
 
-    data
-}
+    let segments = key.segments();
+    let existing = counter.get_children().len();
+
+    // The first missing section must not exist as a tag that cannot hold other tags.
+    if existing + 1 < segments.len() {
+        let mut found_stream = Stream::new_with_data(data.clone());
+        found_stream.set_limits(*limits);
+        found_stream.set_position(0);
+        let data_type = get_sub_object_type::<Box<dyn Any>>(&mut found_stream, &KeyPath::from_segments(segments[..existing + 1].to_vec()))?;
+        if data_type != 0 {
+            return Err(OdsError::TypeMismatch {
+                expected: OBJECT_TAG_ID as u8,
+                found: data_type as u8
+            });
+        }
+    }
 
-pub fn replace_sub_object_data<'a, 'b>(data: &'a mut Vec<u8>, counter: &'a mut KeyScout, data_to_replace: &'b Vec<u8>) -> &'a mut Vec<u8> {
-    let end_amount = counter.get_end().as_ref().unwrap().get_size() + 5;
-    counter.remove_amount(end_amount);
-    counter.add_amount(data_to_replace.len() as i32);
+    if let Some(KeySegment::Index(_)) = key.last() {
+        return Err(OdsError::KeyNotFound(key.to_string()));
+    }
 
-    let mut end = counter.get_end().as_ref().unwrap();
+    // Wrap the tag in the missing Object Tags.
+    let mut new_data = tag_data.to_vec();
+    for segment in segments[existing..].iter().rev().skip(1) {
+        let name = match segment {
+            KeySegment::Name(name) => name,
+            KeySegment::Index(_) => return Err(OdsError::KeyNotFound(key.to_string()))
+        };
+        let mut write_tag = Stream::new_empty();
+        let tag = ObjectTag::new(name.clone(), Container::new_with_data(new_data));
+        Container::<ObjectContainer>::write_data(tag, &mut write_tag)?;
+        new_data = write_tag.bytes();
+    }
 
-    // Remove the data
-    data.splice(((end.get_starting_index() - 1) as usize)..((end.get_starting_index() + 4 + end.get_size()) as usize), data_to_replace.iter().cloned());
+    // Insert the data at the end of the deepest existing parent.
+    let insert_index = match counter.get_children().last() {
+        Some(parent) => (parent.get_starting_index() + 4 + parent.get_size()) as usize,
+        None => data.len()
+    };
+    counter.add_amount(new_data.len() as i32);
+    data.splice(insert_index..insert_index, new_data);
 
     for child in counter.get_children().iter() {
         let index = child.get_starting_index() as usize;
-        let size : usize = child.get_size() as usize;
-        data[index] = (size >> 24) as u8;
-        data[index + 1] = (size >> 16) as u8;
-        data[index + 2] = (size >> 8) as u8;
-        data[index + 3] = (size) as u8;
+        data[index..index + 4].copy_from_slice(&child.get_size().to_be_bytes());
     }
 
-    data
+    Ok(())
 }
 
-pub fn scout_object_data<'a, 'b>(read_stream: &'a mut Stream, key: String, counter: &'b mut KeyScout) -> &'b mut KeyScout {
-    let name_list: Vec<&str> = key.as_str().split('.').collect();
-    let name = name_list[0].to_string();
-    let other_key = get_key(key.as_str().split('.').collect());
-
-    let mut current_builder = TagBuilder::new();
-    while read_stream.can_read_more() {
-        let mut child = KeyScoutChild::new();
-        current_builder.set_data_type(read_stream.read() as i32);
-
-        child.set_starting_index(read_stream.position() as i32);
+/// Edit the value of the Compressed Object Tag that a key continues into.
+/// The value is decompressed, edited and compressed again, and the sizes of its parents are updated.
+///
+/// # Params
+/// limits: `&ParseLimits` -> The limits of the data.
+/// edit: `FnOnce(&mut Vec<u8>, KeyPath, &ParseLimits) -> Result<bool>` -> Edits the decompressed value using the rest of the key
+/// and the limits inside of the Compressed Object Tag. The value is only written back if this returns true.
+///
+/// # Returns
+/// The output of `edit`, or false if the key does not continue into a Compressed Object Tag.
+fn edit_compressed_object<F>(data: &mut Vec<u8>, counter: &mut KeyScout, limits: &ParseLimits, edit: F) -> Result<bool>
+    where F: FnOnce(&mut Vec<u8>, KeyPath, &ParseLimits) -> Result<bool> {
+    let (compressed, other_key) = match counter.get_compressed() {
+        Some(compressed) => compressed.clone(),
+        None => return Ok(false)
+    };
+
+    // The value is inside of the Compressed Object Tag and every container around it.
+    let mut inner_limits = *limits;
+    for _ in 0..=counter.get_children().len() {
+        inner_limits = inner_limits.descend()?;
+    }
 
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
+    let value_start = compressed.get_starting_index() as usize + 6 + compressed.get_name().len();
+    let value_end = (compressed.get_starting_index() + 4 + compressed.get_size()) as usize;
+    let mut value = OBJECT_COMPRESSION.decompress_limited(&data[value_start..value_end], limits)?;
+    if !edit(&mut value, other_key, &inner_limits)? {
+        return Ok(false);
+    }
 
-        if current_builder.get_name_size() != name.len() as i32 {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
-        }
+    let mut write_tag = Stream::new_empty();
+    let tag = CompressedObjectTag::new(compressed.get_name(), Container::new_with_data(value));
+    Container::<CompressedObjectContainer>::write_data(tag, &mut write_tag)?;
 
-        let tag_name = read_stream.read_string(current_builder.get_name_size() as u64);
-        current_builder.set_name(tag_name.clone());
+    counter.set_end(compressed);
+    replace_sub_object_data(data, counter, &write_tag.bytes())?;
+    Ok(true)
+}
 
-        if name != tag_name {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
-        }
+pub fn scout_object_data<'b>(read_stream: &mut Stream, key: &KeyPath, counter: &'b mut KeyScout) -> Result<&'b mut KeyScout> {
+    if key.is_empty() {
+        return Err(OdsError::InvalidKey(String::new()));
+    }
+    read_stream.get_limits().check(Limit::Depth, key.len() - 1)?;
+    let end = read_stream.size() as u64;
+    scout_segments_until(read_stream, key.segments(), counter, end)?;
+    Ok(counter)
+}
 
-        let starting_index = current_builder.get_starting_index();
-        let data_size = current_builder.get_data_size();
+/// Scout the data of the stream for the tag that the segments point to until the `end` position is reached.
+/// If there is more than one segment, the scouting continues inside of the tag that was found.
+fn scout_segments_until(read_stream: &mut Stream, segments: &[KeySegment], counter: &mut KeyScout, end: u64) -> Result<()> {
+    let (segment, other_segments) = match segments.split_first() {
+        Some(segments) => segments,
+        None => return Ok(())
+    };
+
+    let mut i = 0;
+    let mut count = 0;
+    while read_stream.position() < end {
+        count_tag(read_stream, &mut count)?;
+        let mut child = KeyScoutChild::new();
+        child.set_starting_index(read_stream.position() as i32 + 1);
+
+        let mut current_builder = read_tag_header(read_stream)?;
+
+        let tag_name = match segment {
+            KeySegment::Name(name) => {
+                if current_builder.name_size != name.len() as i32 {
+                    skip_tag(read_stream, &current_builder);
+                    continue;
+                }
+                let tag_name = read_stream.read_string(current_builder.name_size as u64)?;
+                if *name != tag_name {
+                    skip_tag(read_stream, &current_builder);
+                    continue;
+                }
+                tag_name
+            }
+            KeySegment::Index(index) => {
+                if i != *index {
+                    i += 1;
+                    skip_tag(read_stream, &current_builder);
+                    continue;
+                }
+                read_stream.read_string(current_builder.name_size as u64)?
+            }
+        };
+        current_builder.set_name(tag_name);
 
-        current_builder.set_value_length(((starting_index - read_stream.position() as i64) + data_size as i64) as i32);
-        if other_key.is_some() {
-            // TODO Validate not compressed
+        child.set_name(current_builder.get_name());
+        child.set_size(current_builder.data_size);
+
+        if !other_segments.is_empty() {
+            if current_builder.data_type == COMPRESSED_OBJECT_TAG_ID {
+                // The value is compressed, so the rest of the key has to be scouted after decompressing.
+                counter.set_compressed(child, KeyPath::from_segments(other_segments.to_vec()));
+                return Ok(());
+            }
+            if !is_container(current_builder.data_type) {
+                return Ok(());
+            }
 
-            child.set_size(current_builder.get_data_size());
-            child.set_name(current_builder.get_name());
             counter.add_child(child);
-            return scout_object_data(read_stream, other_key.unwrap(), counter);
+            let tag_end = (current_builder.starting_index + current_builder.data_size as i64) as u64;
+            return scout_segments_until(read_stream, other_segments, counter, tag_end);
         }
 
-        child.set_name(current_builder.get_name());
-        child.set_size(current_builder.get_data_size());
         counter.set_end(child);
-
-        return counter;
+        return Ok(());
     }
 
-    counter
+    Ok(())
 }
 
-pub fn scout_index_data<'a, 'b>(read_stream: &'a mut Stream, index: i32, counter: &'b mut KeyScout) -> &'b mut KeyScout {
-    let mut current_builder = TagBuilder::new();
-    let mut i = -1;
-    while read_stream.can_read_more() {
-        i += 1;
-
-        let mut child = KeyScoutChild::new();
-        current_builder.set_data_type(read_stream.read() as i32);
-
-        child.set_starting_index(read_stream.position() as i32);
-
-        current_builder.set_data_size(read_stream.read_i32());
-        current_builder.set_starting_index(read_stream.position() as i64);
-        current_builder.set_name_size(read_stream.read_i16() as i32);
-
-        if i != index {
-            read_stream.set_position((current_builder.get_starting_index() as i64 + current_builder.get_data_size() as i64) as u64);
-            current_builder = TagBuilder::new();
-            continue;
-        }
-
-        let tag_name = read_stream.read_string(current_builder.get_name_size() as u64);
-        current_builder.set_name(tag_name.clone());
-
-        let starting_index = current_builder.get_starting_index();
-        let data_size = current_builder.get_data_size();
-
-        current_builder.set_value_length(((starting_index - read_stream.position() as i64) + data_size as i64) as i32);
-
-        child.set_name(current_builder.get_name());
-        child.set_size(current_builder.get_data_size());
-        counter.set_end(child);
+/// Scout the stream for the tag with the exact name. Unlike `scout_object_data` the name is not parsed as a key,
+/// so it may contain dots.
+pub fn scout_name_data<'b>(read_stream: &mut Stream, name: String, counter: &'b mut KeyScout) -> Result<&'b mut KeyScout> {
+    let end = read_stream.size() as u64;
+    scout_segments_until(read_stream, &[KeySegment::Name(name)], counter, end)?;
+    Ok(counter)
+}
 
-        return counter;
+pub fn scout_index_data<'b>(read_stream: &mut Stream, index: i32, counter: &'b mut KeyScout) -> Result<&'b mut KeyScout> {
+    if let Ok(index) = usize::try_from(index) {
+        let end = read_stream.size() as u64;
+        scout_segments_until(read_stream, &[KeySegment::Index(index)], counter, end)?;
     }
-
-    counter
+    Ok(counter)
 }
\ No newline at end of file
@@ -0,0 +1,264 @@
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{OdsError, Result};
+use crate::internal::memory::ODSMemory;
+use crate::io::streams::{IORead, IOWrite, StandardIO, Stream};
+use crate::key_path::KeyPath;
+
+/// Identifies a journal file.
+const MAGIC: &[u8; 4] = b"ODSJ";
+/// The version of the journal format.
+const VERSION: u8 = 1;
+/// The size of the header: the magic, the version, and the length and hash of the document the journal applies to.
+const HEADER_SIZE: usize = 4 + 1 + 8 + 8;
+
+const APPEND: u8 = 1;
+const DELETE: u8 = 2;
+const REPLACE: u8 = 3;
+const SET: u8 = 4;
+const SET_NONE: u8 = 5;
+
+/// A change to a document that is stored in a journal.
+///
+/// Tags are stored encoded, so a record can be replayed without knowing their types.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JournalRecord {
+    /// Append encoded tags to the end of the document.
+    Append(Vec<u8>),
+    /// Delete the tag with the key.
+    Delete(KeyPath),
+    /// Replace the tag with the key with an encoded tag.
+    Replace(KeyPath, Vec<u8>),
+    /// Set the tag with the key to an encoded tag, or delete it if there is no tag.
+    Set(KeyPath, Option<Vec<u8>>)
+}
+
+/// The version of a document that a journal was started on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BaseVersion {
+    len: u64,
+    hash: u64
+}
+
+impl BaseVersion {
+    /// Get the version of the bytes of a document. This hashes the whole document.
+    pub fn of(base: &[u8]) -> BaseVersion {
+        BaseVersion {
+            len: base.len() as u64,
+            hash: hash(base)
+        }
+    }
+}
+
+impl JournalRecord {
+    /// Apply the change to a document.
+    ///
+    /// # Returns
+    /// If the document was changed. A delete or a replace of a key that does not exist does not change it.
+    pub fn apply(&self, memory: &mut ODSMemory) -> Result<bool> {
+        match self {
+            JournalRecord::Append(data) => memory.append_encoded(data),
+            JournalRecord::Delete(key) => return memory.replace_encoded(key, &[]),
+            JournalRecord::Replace(key, data) => return memory.replace_encoded(key, data),
+            JournalRecord::Set(key, data) => memory.set_encoded(key, data.as_deref())?
+        }
+        Ok(true)
+    }
+
+    /// Encode the record as `[type][length of the body][body][checksum of the type and body]`.
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut body = Stream::new_empty();
+        let record_type = match self {
+            JournalRecord::Append(data) => {
+                body.write_vec(data.clone())?;
+                APPEND
+            }
+            JournalRecord::Delete(key) => {
+                write_key(&mut body, key)?;
+                DELETE
+            }
+            JournalRecord::Replace(key, data) => {
+                write_key(&mut body, key)?;
+                body.write_vec(data.clone())?;
+                REPLACE
+            }
+            JournalRecord::Set(key, Some(data)) => {
+                write_key(&mut body, key)?;
+                body.write_vec(data.clone())?;
+                SET
+            }
+            JournalRecord::Set(key, None) => {
+                write_key(&mut body, key)?;
+                SET_NONE
+            }
+        };
+        let body = body.bytes();
+
+        let mut output = Stream::new_empty();
+        output.write(record_type)?;
+        output.write_i32(body.len() as i32)?;
+        output.write_vec(body.clone())?;
+        output.write_i32(checksum(record_type, &body) as i32)?;
+        Ok(output.bytes())
+    }
+
+    /// Decode the body of a record.
+    fn decode(record_type: u8, body: Vec<u8>) -> Result<JournalRecord> {
+        if record_type == APPEND {
+            return Ok(JournalRecord::Append(body));
+        }
+
+        let mut read_stream = Stream::new_with_data(body);
+        read_stream.set_position(0);
+        let key = read_key(&mut read_stream)?;
+        let mut data = vec![0; read_stream.size() - read_stream.position() as usize];
+        read_stream.read_vec(&mut data)?;
+
+        Ok(match record_type {
+            DELETE => JournalRecord::Delete(key),
+            REPLACE => JournalRecord::Replace(key, data),
+            SET => JournalRecord::Set(key, Some(data)),
+            SET_NONE => JournalRecord::Set(key, Option::None),
+            _ => return Err(OdsError::Truncated)
+        })
+    }
+}
+
+/// Get the path of the journal of a file. (Ex: `data.ods` -> `data.odsj`, `data` -> `data.odsj`)
+pub fn journal_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_os_string();
+    if file.extension() == Some("ods".as_ref()) {
+        name.push("j");
+    } else {
+        name.push(".odsj");
+    }
+    PathBuf::from(name)
+}
+
+/// Read the records of a journal.
+///
+/// A journal is only valid for the document it was started on. If the document has changed since then
+/// (because the journal was already folded into it) no records are returned.
+/// A record that was only partly written (Ex: Because of a crash) ends the journal.
+///
+/// # Params
+/// path: `&Path` -> The journal file.
+/// base: `&[u8]` -> The current bytes of the document.
+pub fn read_journal(path: &Path, base: &[u8]) -> Result<Vec<JournalRecord>> {
+    Ok(read_records(path, BaseVersion::of(base))?.0)
+}
+
+/// Read the records of a journal that was started on a version of a document. (See `read_journal`.)
+///
+/// # Returns
+/// The records, and the end of the last record that could be read. This is 0 if there is no journal for the version.
+pub fn read_records(path: &Path, version: BaseVersion) -> Result<(Vec<JournalRecord>, u64)> {
+    if !path.exists() {
+        return Ok((Vec::new(), 0));
+    }
+    let data = fs::read(path)?;
+    if !header_matches(&data, version) {
+        return Ok((Vec::new(), 0));
+    }
+
+    let mut records = Vec::new();
+    let mut position = HEADER_SIZE;
+    while data.len() >= position + 5 {
+        let record_type = data[position];
+        let length = i32::from_be_bytes([data[position + 1], data[position + 2], data[position + 3], data[position + 4]]);
+        let body_start = position + 5;
+        if length < 0 || data.len() < body_start + length as usize + 4 {
+            break;
+        }
+        let body_end = body_start + length as usize;
+        let body = &data[body_start..body_end];
+        let expected = u32::from_be_bytes([data[body_end], data[body_end + 1], data[body_end + 2], data[body_end + 3]]);
+        if expected != checksum(record_type, body) {
+            break;
+        }
+
+        records.push(JournalRecord::decode(record_type, body.to_vec())?);
+        position = body_end + 4;
+    }
+
+    Ok((records, position as u64))
+}
+
+/// Append a record to a journal and sync it to disk.
+///
+/// Anything after the end of the last record (Ex: A record that was only partly written) is cut off first, since
+/// a record that is written after it would never be read. A journal without records for the version of the
+/// document is started again.
+///
+/// # Params
+/// path: `&Path` -> The journal file.
+/// version: `BaseVersion` -> The version of the document. (Without the changes of the journal.)
+/// end: u64 -> The end of the last record of the journal, from `read_records` or the last `append_record`.
+/// record: `&JournalRecord` -> The record to append.
+///
+/// # Returns
+/// The end of the appended record.
+pub fn append_record(path: &Path, version: BaseVersion, end: u64, record: &JournalRecord) -> Result<u64> {
+    let encoded = record.encode()?;
+
+    let mut journal = OpenOptions::new().create(true).read(true).write(true).truncate(false).open(path)?;
+    let mut start = [0; HEADER_SIZE];
+    let restart = end < HEADER_SIZE as u64 || journal.read_exact(&mut start).is_err() || start != header(version);
+    let end = if restart { 0 } else { end };
+    journal.set_len(end)?;
+    journal.seek(SeekFrom::Start(end))?;
+    if restart {
+        journal.write_all(&header(version))?;
+    }
+    journal.write_all(&encoded)?;
+    journal.sync_all()?;
+    Ok(journal.stream_position()?)
+}
+
+/// Remove a journal if it exists.
+pub fn remove_journal(path: &Path) -> Result<()> {
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn header(version: BaseVersion) -> [u8; HEADER_SIZE] {
+    let mut output = [0; HEADER_SIZE];
+    output[..4].copy_from_slice(MAGIC);
+    output[4] = VERSION;
+    output[5..13].copy_from_slice(&version.len.to_be_bytes());
+    output[13..].copy_from_slice(&version.hash.to_be_bytes());
+    output
+}
+
+fn header_matches(data: &[u8], version: BaseVersion) -> bool {
+    data.len() >= HEADER_SIZE && data[..HEADER_SIZE] == header(version)
+}
+
+fn write_key(write_stream: &mut Stream, key: &KeyPath) -> Result<()> {
+    let key = key.to_string();
+    write_stream.write_i32(key.len() as i32)?;
+    write_stream.write_string(key)
+}
+
+fn read_key(read_stream: &mut Stream) -> Result<KeyPath> {
+    let length = read_stream.read_i32()?;
+    if length < 0 {
+        return Err(OdsError::Truncated);
+    }
+    KeyPath::parse(&read_stream.read_string(length as u64)?)
+}
+
+/// The 64 bit FNV-1a hash of the data.
+pub(crate) fn hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// The 32 bit FNV-1a hash of a record.
+fn checksum(record_type: u8, body: &[u8]) -> u32 {
+    std::iter::once(&record_type).chain(body.iter())
+        .fold(0x811c9dc5, |hash, byte| (hash ^ *byte as u32).wrapping_mul(0x01000193))
+}
\ No newline at end of file
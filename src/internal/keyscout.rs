@@ -1,4 +1,4 @@
-use std::process::Child;
+use crate::key_path::KeyPath;
 
 #[derive(Debug, Clone)]
 pub struct KeyScoutChild {
@@ -49,10 +49,17 @@ impl KeyScoutChild {
     }
 }
 
+impl Default for KeyScoutChild {
+    fn default() -> Self {
+        KeyScoutChild::new()
+    }
+}
+
 #[derive(Clone)]
 pub struct KeyScout {
     children: Vec<KeyScoutChild>,
-    end: Option<KeyScoutChild>
+    end: Option<KeyScoutChild>,
+    compressed: Option<(KeyScoutChild, KeyPath)>
 }
 
 impl KeyScout {
@@ -60,7 +67,8 @@ impl KeyScout {
         KeyScout {
             children: Vec::new(),
             // Null keyscoutchild.
-            end: Option::None
+            end: Option::None,
+            compressed: Option::None
         }
     }
 
@@ -73,13 +81,7 @@ impl KeyScout {
     }
 
     pub fn get_child_by_name(&mut self, name: String) -> Option<&KeyScoutChild> {
-        for child in self.children.iter() {
-            if child.get_name() == name {
-                return Some(child);
-            }
-        }
-
-        Option::None
+        self.children.iter().find(|child| child.get_name() == name)
     }
 
     pub fn set_end(&mut self, end: KeyScoutChild) {
@@ -90,15 +92,35 @@ impl KeyScout {
         &self.end
     }
 
+    /// Set the Compressed Object that the key continues into.
+    ///
+    /// # Params
+    /// compressed: `KeyScoutChild` -> The Compressed Object Tag.
+    /// key: KeyPath -> The rest of the key (inside of the Compressed Object).
+    pub fn set_compressed(&mut self, compressed: KeyScoutChild, key: KeyPath) {
+        self.compressed = Some((compressed, key));
+    }
+
+    /// Get the Compressed Object that the key continues into, and the rest of the key.
+    pub fn get_compressed(&mut self) -> &Option<(KeyScoutChild, KeyPath)> {
+        &self.compressed
+    }
+
     pub fn remove_amount(&mut self, size: i32) {
-        for mut child in self.children.iter_mut() {
+        for child in self.children.iter_mut() {
             child.remove_size(size);
         }
     }
 
     pub fn add_amount(&mut self, size: i32) {
-        for mut child in self.children.iter_mut() {
+        for child in self.children.iter_mut() {
             child.add_size(size);
         }
     }
+}
+
+impl Default for KeyScout {
+    fn default() -> Self {
+        KeyScout::new()
+    }
 }
\ No newline at end of file
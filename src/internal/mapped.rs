@@ -0,0 +1,281 @@
+use std::fs::File;
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use memmap2::Mmap;
+
+use crate::error::{OdsError, Result};
+use crate::internal::footer::Footer;
+use crate::internal::index::{locate, IndexMode, Located, TagIndex};
+use crate::internal::internal_utils::{find_path_data, get_many_data, get_path_data, read_list_data};
+use crate::internal::journal::{journal_path, read_journal};
+use crate::internal::memory::ODSMemory;
+use crate::internal::ODSInternal;
+use crate::internal::tag_builder::TagBuilder;
+use crate::io::lock::{lock_file, FileLock, LockMode};
+use crate::io::streams::{read_only_error, SeekStream};
+use crate::tags::general::{AnyTag, Tag, Taggable};
+use crate::tags::value::OdsValue;
+use crate::key_path::{KeyPath, KeySegment};
+use crate::limits::{Limit, ParseLimits};
+use crate::validate::{validate, ValidationReport};
+
+/// A read only ODS file that is mapped into memory.
+///
+/// The tags are read straight from the mapped bytes, so opening the file does not read it and processes that read
+/// the same file share the page cache instead of each holding a copy. Every change returns an `OdsError::Io` error
+/// with the kind `PermissionDenied`.
+///
+/// The map is made when the file is opened. A file that is replaced or appended to after that (Ex: By `ODSFile`) is
+/// only seen after `remap`. Changes in the journal of the file are read from memory.
+///
+/// The file must not be truncated or written over while it is mapped. (See `ODSMappedFile::new`.)
+pub struct ODSMappedFile {
+    file: PathBuf,
+    map: Mmap,
+    /// The footer index of the file, if it has one.
+    footer: Option<Footer>,
+    lock_mode: LockMode,
+    limits: ParseLimits,
+    index_mode: IndexMode,
+    index: Option<TagIndex>
+}
+
+impl ODSInternal for ODSMappedFile {
+    fn get<T: Taggable<T>>(&mut self, key: KeyPath) -> Result<Tag<T>> {
+        let _lock = self.lock()?;
+        match self.replay()? {
+            Some(mut memory) => memory.get::<T>(key),
+            None => match self.locate(&key)? {
+                Some(located) => get_path_data(&mut located.read_stream(&self.map)?, &located.key, located.limits),
+                None => Err(OdsError::KeyNotFound(key.to_string()))
+            }
+        }
+    }
+
+    fn get_many(&mut self, keys: &[KeyPath]) -> Result<Vec<Result<TagBuilder>>> {
+        let _lock = self.lock()?;
+        match self.replay()? {
+            Some(mut memory) => memory.get_many(keys),
+            None => get_many_data(&mut self.read_stream()?, keys, self.limits)
+        }
+    }
+
+    fn get_all(&mut self) -> Result<Vec<AnyTag>> {
+        let _lock = self.lock()?;
+        match self.replay()? {
+            Some(mut memory) => memory.get_all(),
+            None => read_list_data(&mut self.read_stream()?, self.document().len() as u64, self.limits)
+        }
+    }
+
+    fn load_value(&mut self) -> Result<OdsValue> {
+        let _lock = self.lock()?;
+        match self.replay()? {
+            Some(mut memory) => memory.load_value(),
+            None => OdsValue::from_bytes_with_limits(self.document(), &self.limits)
+        }
+    }
+
+    fn save(&mut self, _tags: Vec<AnyTag>) -> Result<()> {
+        Err(OdsError::Io(read_only_error()))
+    }
+
+    fn save_value(&mut self, _value: &OdsValue) -> Result<()> {
+        Err(OdsError::Io(read_only_error()))
+    }
+
+    fn append<T: Taggable<T>>(&mut self, _tag: Tag<T>) -> Result<()> {
+        Err(OdsError::Io(read_only_error()))
+    }
+
+    fn append_all(&mut self, _tags: Vec<AnyTag>) -> Result<()> {
+        Err(OdsError::Io(read_only_error()))
+    }
+
+    fn find(&mut self, key: KeyPath) -> Result<bool> {
+        let _lock = self.lock()?;
+        match self.replay()? {
+            Some(mut memory) => memory.find(key),
+            None => match self.locate(&key)? {
+                Some(located) => find_path_data(&mut located.read_stream(&self.map)?, &located.key, located.limits),
+                None => Ok(false)
+            }
+        }
+    }
+
+    fn delete(&mut self, _key: KeyPath) -> Result<bool> {
+        Err(OdsError::Io(read_only_error()))
+    }
+
+    fn replace_data<T: Taggable<T>>(&mut self, _key: KeyPath, _replacement: Tag<T>) -> Result<bool> {
+        Err(OdsError::Io(read_only_error()))
+    }
+
+    fn set<T: Taggable<T>>(&mut self, _key: KeyPath, _value: Option<Tag<T>>) -> Result<()> {
+        Err(OdsError::Io(read_only_error()))
+    }
+
+    fn validate(&mut self) -> Result<ValidationReport> {
+        let _lock = self.lock()?;
+        Ok(validate(self.document()))
+    }
+
+    fn set_parse_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+        self.index = Option::None;
+    }
+}
+
+impl ODSMappedFile {
+    /// Map a file into memory.
+    ///
+    /// # Returns
+    /// `OdsError::Io` if the file does not exist or cannot be mapped.
+    ///
+    /// # Safety
+    /// The mapped bytes are read as a normal slice, so they must not change while the ODSMappedFile exists.
+    /// No program may truncate the file or write over its bytes until the ODSMappedFile is dropped, ODS included.
+    /// ODS does this when:
+    /// - `replace_data` or `set` on an `ODSFile` writes a tag over an old tag of the same size.
+    /// - An `ODSStream` over the file writes a new document over the old one and cuts the file to its size.
+    /// - An append to an `ODSFile` fails and the file is cut back to its old size. (This only removes bytes after the
+    ///   old end of the file, which are only mapped if the file was mapped while the append was written.)
+    ///
+    /// Replacing the file (Ex: With `save`, or any change to an `ODSFile` that rewrites it) and appending to it are
+    /// safe. The changes are seen after `remap`.
+    pub unsafe fn new(file: PathBuf) -> Result<Self> {
+        let lock_mode = LockMode::default();
+        let map = map_file(&file, lock_mode)?;
+        Ok(ODSMappedFile {
+            file,
+            footer: read_footer(&map)?,
+            map,
+            lock_mode,
+            limits: ParseLimits::default(),
+            index_mode: IndexMode::Disabled,
+            index: Option::None
+        })
+    }
+
+    /// Map the file into memory again, so a file that was replaced since it was mapped is seen.
+    pub fn remap(&mut self) -> Result<()> {
+        self.map = map_file(&self.file, self.lock_mode)?;
+        self.footer = read_footer(&self.map)?;
+        self.index = Option::None;
+        Ok(())
+    }
+
+    /// Set which tags are indexed to find them without reading the tags before them. (See `IndexMode`.)
+    /// The index is kept until the file is mapped again.
+    pub fn set_index_mode(&mut self, index_mode: IndexMode) {
+        self.index_mode = index_mode;
+        self.index = Option::None;
+    }
+
+    pub fn get_index_mode(&self) -> IndexMode {
+        self.index_mode
+    }
+
+    /// Set how the file waits for processes that are changing it. (See `LockMode`.)
+    pub fn set_lock_mode(&mut self, lock_mode: LockMode) {
+        self.lock_mode = lock_mode;
+    }
+
+    pub fn get_lock_mode(&self) -> LockMode {
+        self.lock_mode
+    }
+
+    /// Get the mapped bytes of the file, without copying them. (Ex: To read them with `TagRef`.)
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Take a shared lock on the file, so it is not changed while it is read.
+    fn lock(&self) -> Result<Option<FileLock>> {
+        lock_file(&self.file, false, self.lock_mode)
+    }
+
+    /// Get the mapped bytes without the footer index of the file.
+    fn document(&self) -> &[u8] {
+        match &self.footer {
+            Some(footer) => &self.map[..footer.offset() as usize],
+            None => &self.map
+        }
+    }
+
+    /// Get a Stream that reads the mapped bytes (without the footer index) without copying them.
+    /// A file that is larger than the total size limit is not read at all.
+    fn read_stream(&self) -> Result<SeekStream<Cursor<&[u8]>>> {
+        self.limits.check(Limit::TotalSize, self.map.len())?;
+        let mut read_stream = SeekStream::new(Cursor::new(self.document()))?;
+        read_stream.set_limits(self.limits);
+        Ok(read_stream)
+    }
+
+    /// Find where to read a tag from the mapped bytes.
+    ///
+    /// The in memory index is used if it is enabled, otherwise the footer index of the file is used if the file has
+    /// one and the key starts with a name. Without either the whole document is searched.
+    ///
+    /// # Returns
+    /// The tag, or None if the tag could not be found.
+    fn locate(&mut self, key: &KeyPath) -> Result<Option<Located>> {
+        self.limits.check(Limit::TotalSize, self.map.len())?;
+        let end = self.document().len();
+        if self.index_mode != IndexMode::Disabled {
+            return locate(&mut self.index, &self.map[..end], key, self.index_mode, self.limits);
+        }
+
+        if let (Some(footer), Some(KeySegment::Name(name))) = (&self.footer, key.segments().first()) {
+            let mut read_stream = SeekStream::new(Cursor::new(&self.map[..]))?;
+            let offset = match footer.find(&mut read_stream, name, &self.limits)? {
+                Some(offset) => offset as usize,
+                None => return Ok(Option::None)
+            };
+            let mut segments = vec![KeySegment::Index(0)];
+            segments.extend_from_slice(&key.segments()[1..]);
+            return Ok(Some(Located {
+                range: offset..end,
+                key: KeyPath::from_segments(segments),
+                limits: self.limits
+            }));
+        }
+        Ok(Some(Located {
+            range: 0..end,
+            key: key.clone(),
+            limits: self.limits
+        }))
+    }
+
+    /// Apply the changes in the journal of the file to the mapped bytes.
+    ///
+    /// # Returns
+    /// None if the journal has no changes for the mapped bytes, so they can be read directly.
+    fn replay(&self) -> Result<Option<ODSMemory>> {
+        let records = read_journal(&journal_path(&self.file), &self.map)?;
+        if records.is_empty() {
+            return Ok(Option::None);
+        }
+        let mut memory = ODSMemory::new_with_limits(self.map.to_vec(), self.limits)?;
+        for record in records.iter() {
+            record.apply(&mut memory)?;
+        }
+        Ok(Some(memory))
+    }
+}
+
+/// Read the footer index at the end of mapped bytes. (See `Footer`.)
+fn read_footer(map: &Mmap) -> Result<Option<Footer>> {
+    Footer::read(&mut SeekStream::new(Cursor::new(&map[..]))?)
+}
+
+/// Map a file into memory while holding a shared lock on it.
+fn map_file(file: &PathBuf, lock_mode: LockMode) -> Result<Mmap> {
+    let _lock = lock_file(file, false, lock_mode)?;
+    let mapped = File::open(file)?;
+    // SAFETY: The caller of `ODSMappedFile::new` guarantees that the file is not truncated or written over while the
+    // ODSMappedFile exists, which includes every map that `remap` makes. Replacing the file (a new file renamed over
+    // it) does not change the mapped bytes, and appended bytes are after the end of the map.
+    Ok(unsafe { Mmap::map(&mapped)? })
+}
\ No newline at end of file
@@ -0,0 +1,226 @@
+use crate::error::{OdsError, Result};
+use std::io::Cursor;
+
+use crate::internal::index::{locate, IndexMode, TagIndex};
+use crate::internal::internal_utils::{edit_object_data, find_path_data, get_many_data, get_path_data, read_list_data, set_object_data};
+use crate::internal::ODSInternal;
+use crate::internal::tag_builder::TagBuilder;
+use crate::io::streams::{SeekStream, StandardIO, Stream};
+use crate::tags::general::{AnyTag, Tag, Taggable};
+use crate::tags::tag_ref::{TagIter, TagRef};
+use crate::tags::value::OdsValue;
+use crate::key_path::{KeyPath, KeySegment};
+use crate::limits::{Limit, ParseLimits};
+use crate::util::write_any_tags;
+use crate::validate::{validate, ValidationReport};
+
+/// An ODS document that is stored in memory as encoded bytes.
+pub struct ODSMemory {
+    data: Vec<u8>,
+    limits: ParseLimits,
+    index_mode: IndexMode,
+    index: Option<TagIndex>
+}
+
+impl ODSInternal for ODSMemory {
+    fn get<T: Taggable<T>>(&mut self, key: KeyPath) -> Result<Tag<T>> {
+        if self.index_mode == IndexMode::Disabled {
+            return get_path_data(&mut self.read_stream()?, &key, self.limits);
+        }
+        match locate(&mut self.index, &self.data, &key, self.index_mode, self.limits)? {
+            Some(located) => get_path_data(&mut located.read_stream(&self.data)?, &located.key, located.limits),
+            None => Err(OdsError::KeyNotFound(key.to_string()))
+        }
+    }
+
+    fn get_many(&mut self, keys: &[KeyPath]) -> Result<Vec<Result<TagBuilder>>> {
+        get_many_data(&mut self.read_stream()?, keys, self.limits)
+    }
+
+    fn get_all(&mut self) -> Result<Vec<AnyTag>> {
+        read_list_data(&mut self.read_stream()?, self.data.len() as u64, self.limits)
+    }
+
+    fn load_value(&mut self) -> Result<OdsValue> {
+        OdsValue::from_bytes_with_limits(&self.data, &self.limits)
+    }
+
+    fn save(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        self.data = write_any_tags(&tags)?;
+        self.index = Option::None;
+        Ok(())
+    }
+
+    fn save_value(&mut self, value: &OdsValue) -> Result<()> {
+        self.data = value.to_bytes()?;
+        self.index = Option::None;
+        Ok(())
+    }
+
+    fn append<T: Taggable<T>>(&mut self, tag: Tag<T>) -> Result<()> {
+        self.append_encoded(&encode_tag(tag)?);
+        Ok(())
+    }
+
+    fn append_all(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        self.append_encoded(&write_any_tags(&tags)?);
+        Ok(())
+    }
+
+    fn find(&mut self, key: KeyPath) -> Result<bool> {
+        if self.index_mode == IndexMode::Disabled {
+            return find_path_data(&mut self.read_stream()?, &key, self.limits);
+        }
+        match locate(&mut self.index, &self.data, &key, self.index_mode, self.limits)? {
+            Some(located) => find_path_data(&mut located.read_stream(&self.data)?, &located.key, located.limits),
+            None => Ok(false)
+        }
+    }
+
+    fn delete(&mut self, key: KeyPath) -> Result<bool> {
+        self.replace_encoded(&key, &[])
+    }
+
+    fn replace_data<T: Taggable<T>>(&mut self, key: KeyPath, replacement: Tag<T>) -> Result<bool> {
+        self.replace_encoded(&key, &encode_tag(replacement)?)
+    }
+
+    fn set<T: Taggable<T>>(&mut self, key: KeyPath, value: Option<Tag<T>>) -> Result<()> {
+        match value {
+            Some(value) => self.set_encoded(&key, Some(&encode_set_tag(&key, value)?)),
+            None => self.set_encoded(&key, Option::None)
+        }
+    }
+
+    fn validate(&mut self) -> Result<ValidationReport> {
+        Ok(validate(&self.data))
+    }
+
+    fn set_parse_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+        self.index = Option::None;
+    }
+}
+
+/// Encode a tag.
+pub(crate) fn encode_tag<T: Taggable<T>>(tag: Tag<T>) -> Result<Vec<u8>> {
+    let mut write_tag = Stream::new_empty();
+    T::write_data(tag, &mut write_tag)?;
+    Ok(write_tag.bytes())
+}
+
+/// Encode a tag for `set`. The name of the tag is set to the last section of the key, unless it is an index.
+pub(crate) fn encode_set_tag<T: Taggable<T>>(key: &KeyPath, mut value: Tag<T>) -> Result<Vec<u8>> {
+    if let Some(KeySegment::Name(name)) = key.last() {
+        value.set_name(name.clone());
+    }
+    encode_tag(value)
+}
+
+impl ODSMemory {
+    /// Create an ODSMemory from the encoded bytes of an ODS document.
+    ///
+    /// # Params
+    /// data: `Vec<u8>` -> The encoded tags. (This can be empty.)
+    pub fn new(data: Vec<u8>) -> Self {
+        ODSMemory {
+            data,
+            limits: ParseLimits::default(),
+            index_mode: IndexMode::Disabled,
+            index: Option::None
+        }
+    }
+
+    /// Create an ODSMemory that reads its data with the specified limits. (See `ParseLimits`.)
+    ///
+    /// # Returns
+    /// `OdsError::LimitExceeded` if the data is larger than the total size limit.
+    pub fn new_with_limits(data: Vec<u8>, limits: ParseLimits) -> Result<Self> {
+        limits.check(Limit::TotalSize, data.len())?;
+        Ok(ODSMemory {
+            data,
+            limits,
+            index_mode: IndexMode::Disabled,
+            index: Option::None
+        })
+    }
+
+    /// Set which tags are indexed to find them without reading the tags before them. (See `IndexMode`.)
+    ///
+    /// Appending tags adds them to the index, any other change removes the index until the next lookup.
+    pub fn set_index_mode(&mut self, index_mode: IndexMode) {
+        self.index_mode = index_mode;
+        self.index = Option::None;
+    }
+
+    pub fn get_index_mode(&self) -> IndexMode {
+        self.index_mode
+    }
+
+    /// Get the encoded bytes of the document.
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Convert the ODSMemory into the encoded bytes of the document.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Borrow the tag with the specified key from the document, without copying it. (See `TagRef`.)
+    ///
+    /// # Returns
+    /// `OdsError::KeyNotFound` if the key is not found.
+    pub fn get_tag_ref(&self, key: &KeyPath) -> Result<TagRef<'_>> {
+        TagIter::new(&self.data, self.limits).get(key)?.ok_or_else(|| OdsError::KeyNotFound(key.to_string()))
+    }
+
+    /// Append encoded tags to the end of the document.
+    pub(crate) fn append_encoded(&mut self, data: &[u8]) {
+        let offset = self.data.len() as u64;
+        self.index = self.index.take().and_then(|mut index| {
+            index.extend(TagIndex::build(data, self.index_mode, &self.limits).ok()?, offset);
+            Some(index)
+        });
+        self.data.extend_from_slice(data);
+    }
+
+    /// Replace the tag with the specified key with an encoded tag. The tag is deleted if the data is empty.
+    ///
+    /// # Returns
+    /// If the tag was found.
+    pub(crate) fn replace_encoded(&mut self, key: &KeyPath, data: &[u8]) -> Result<bool> {
+        self.index = Option::None;
+        edit_object_data(&mut self.data, key, data, &self.limits)
+    }
+
+    /// Set the tag with the specified key to an encoded tag. (See `encode_set_tag`.)
+    /// If the data is None the tag is deleted, and an empty key replaces the whole document.
+    pub(crate) fn set_encoded(&mut self, key: &KeyPath, tag_data: Option<&[u8]>) -> Result<()> {
+        let tag_data = match tag_data {
+            Some(tag_data) => tag_data,
+            None => {
+                if !self.replace_encoded(key, &[])? {
+                    return Err(OdsError::KeyNotFound(key.to_string()));
+                }
+                return Ok(());
+            }
+        };
+
+        self.index = Option::None;
+        if key.is_empty() {
+            self.data = tag_data.to_vec();
+            return Ok(());
+        }
+        set_object_data(&mut self.data, key, tag_data, &self.limits)
+    }
+
+    /// Get a Stream that reads the document without copying it. The position is set to the start of the stream.
+    /// A document that is larger than the total size limit is not read at all.
+    fn read_stream(&self) -> Result<SeekStream<Cursor<&[u8]>>> {
+        self.limits.check(Limit::TotalSize, self.data.len())?;
+        let mut read_stream = SeekStream::new(Cursor::new(&self.data[..]))?;
+        read_stream.set_limits(self.limits);
+        Ok(read_stream)
+    }
+}
\ No newline at end of file
@@ -1,16 +1,37 @@
+use crate::error::Result;
 use crate::tags::general::{Tag, Taggable, AnyTag};
+use crate::tags::value::OdsValue;
+use crate::key_path::KeyPath;
+use crate::validate::ValidationReport;
+use crate::limits::ParseLimits;
+use crate::internal::tag_builder::TagBuilder;
 
 pub mod file;
+pub mod compressed_file;
+pub mod memory;
+pub mod stream;
+#[cfg(any(feature = "mmap", test))]
+pub mod mapped;
+pub mod index;
+pub mod footer;
+pub mod journal;
 pub mod internal_utils;
 pub mod tag_builder;
 pub mod keyscout;
 
 pub trait ODSInternal {
-    fn get<T: Taggable<T>>(&mut self, key: String) -> Option<Tag<T>>;
-    fn get_all(&mut self) -> Option<Vec<AnyTag>>;
-    fn append<T: Taggable<T>>(&mut self, tag: Tag<T>);
-    fn find(&mut self, key: String) -> bool;
-    fn delete(&mut self, key: String) -> bool;
-    fn replace_data<T: Taggable<T>>(&mut self, key: String, replacement: Tag<T>) -> bool;
-    fn set<T: Taggable<T>>(&mut self, key: String, value: Option<Tag<T>>);
+    fn get<T: Taggable<T>>(&mut self, key: KeyPath) -> Result<Tag<T>>;
+    fn get_many(&mut self, keys: &[KeyPath]) -> Result<Vec<Result<TagBuilder>>>;
+    fn get_all(&mut self) -> Result<Vec<AnyTag>>;
+    fn load_value(&mut self) -> Result<OdsValue>;
+    fn save(&mut self, tags: Vec<AnyTag>) -> Result<()>;
+    fn save_value(&mut self, value: &OdsValue) -> Result<()>;
+    fn append<T: Taggable<T>>(&mut self, tag: Tag<T>) -> Result<()>;
+    fn append_all(&mut self, tags: Vec<AnyTag>) -> Result<()>;
+    fn find(&mut self, key: KeyPath) -> Result<bool>;
+    fn delete(&mut self, key: KeyPath) -> Result<bool>;
+    fn replace_data<T: Taggable<T>>(&mut self, key: KeyPath, replacement: Tag<T>) -> Result<bool>;
+    fn set<T: Taggable<T>>(&mut self, key: KeyPath, value: Option<Tag<T>>) -> Result<()>;
+    fn validate(&mut self) -> Result<ValidationReport>;
+    fn set_parse_limits(&mut self, limits: ParseLimits);
 }
\ No newline at end of file
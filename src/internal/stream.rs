@@ -0,0 +1,147 @@
+use std::io::{Read, Seek, Write};
+
+use crate::error::Result;
+use crate::internal::internal_utils::{find_path_data, get_many_data, get_path_data};
+use crate::internal::memory::{encode_tag, ODSMemory};
+use crate::internal::ODSInternal;
+use crate::internal::tag_builder::TagBuilder;
+use crate::io::streams::{IOWrite, SeekStream, StandardIO, Truncate};
+use crate::tags::general::{AnyTag, Tag, Taggable};
+use crate::tags::value::OdsValue;
+use crate::key_path::KeyPath;
+use crate::limits::{Limit, ParseLimits};
+use crate::util::write_any_tags;
+use crate::validate::{validate, ValidationReport};
+
+/// An ODS document that is stored in any stream that can seek. (Ex: A `File`, an archive entry or a custom virtual filesystem.)
+///
+/// `get` and `find` only read the tags on the path of the key, and `append` only writes the new tags to the end of the stream.
+/// The other methods read the whole document, and changes write the whole document back to the stream.
+/// **Note:** Unlike `ODSFile` the changes are not atomic. If writing to the stream fails, the document can be left half written.
+pub struct ODSStream<S> {
+    stream: SeekStream<S>
+}
+
+impl<S: Read + Write + Seek + Truncate> ODSInternal for ODSStream<S> {
+    fn get<T: Taggable<T>>(&mut self, key: KeyPath) -> Result<Tag<T>> {
+        let limits = self.stream.get_limits();
+        self.stream.set_position(0);
+        get_path_data(&mut self.stream, &key, limits)
+    }
+
+    fn get_many(&mut self, keys: &[KeyPath]) -> Result<Vec<Result<TagBuilder>>> {
+        let limits = self.stream.get_limits();
+        self.stream.set_position(0);
+        get_many_data(&mut self.stream, keys, limits)
+    }
+
+    fn get_all(&mut self) -> Result<Vec<AnyTag>> {
+        self.load()?.get_all()
+    }
+
+    fn load_value(&mut self) -> Result<OdsValue> {
+        self.load()?.load_value()
+    }
+
+    fn save(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        self.export(write_any_tags(&tags)?)
+    }
+
+    fn save_value(&mut self, value: &OdsValue) -> Result<()> {
+        self.export(value.to_bytes()?)
+    }
+
+    fn append<T: Taggable<T>>(&mut self, tag: Tag<T>) -> Result<()> {
+        self.append_encoded(encode_tag(tag)?)
+    }
+
+    fn append_all(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        self.append_encoded(write_any_tags(&tags)?)
+    }
+
+    fn find(&mut self, key: KeyPath) -> Result<bool> {
+        let limits = self.stream.get_limits();
+        self.stream.set_position(0);
+        find_path_data(&mut self.stream, &key, limits)
+    }
+
+    fn delete(&mut self, key: KeyPath) -> Result<bool> {
+        let mut memory = self.load()?;
+        if !memory.delete(key)? {
+            return Ok(false);
+        }
+        self.export(memory.into_bytes())?;
+        Ok(true)
+    }
+
+    fn replace_data<T: Taggable<T>>(&mut self, key: KeyPath, replacement: Tag<T>) -> Result<bool> {
+        let mut memory = self.load()?;
+        if !memory.replace_data(key, replacement)? {
+            return Ok(false);
+        }
+        self.export(memory.into_bytes())?;
+        Ok(true)
+    }
+
+    fn set<T: Taggable<T>>(&mut self, key: KeyPath, value: Option<Tag<T>>) -> Result<()> {
+        let mut memory = self.load()?;
+        memory.set(key, value)?;
+        self.export(memory.into_bytes())
+    }
+
+    fn validate(&mut self) -> Result<ValidationReport> {
+        Ok(validate(&self.read_all()?))
+    }
+
+    fn set_parse_limits(&mut self, limits: ParseLimits) {
+        self.stream.set_limits(limits);
+    }
+}
+
+impl<S: Seek> ODSStream<S> {
+    /// Create an ODSStream over a stream. The document starts at the beginning of the stream and fills all of it.
+    pub fn new(stream: S) -> Result<Self> {
+        Ok(ODSStream {
+            stream: SeekStream::new(stream)?
+        })
+    }
+
+    pub fn get_ref(&self) -> &S {
+        self.stream.get_ref()
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream.into_inner()
+    }
+}
+
+impl<S: Read + Write + Seek + Truncate> ODSStream<S> {
+    /// Read the whole document.
+    /// A stream that is larger than the total size limit is not read at all.
+    fn read_all(&mut self) -> Result<Vec<u8>> {
+        self.stream.get_limits().check(Limit::TotalSize, self.stream.size())?;
+        self.stream.read_all()
+    }
+
+    /// Read the whole document into memory.
+    fn load(&mut self) -> Result<ODSMemory> {
+        let limits = self.stream.get_limits();
+        ODSMemory::new_with_limits(self.read_all()?, limits)
+    }
+
+    /// Write encoded tags to the end of the stream.
+    fn append_encoded(&mut self, data: Vec<u8>) -> Result<()> {
+        self.stream.goto_end();
+        self.stream.write_vec(data)?;
+        self.stream.flush()
+    }
+
+    /// Replace the contents of the stream with a document.
+    fn export(&mut self, data: Vec<u8>) -> Result<()> {
+        let size = data.len() as u64;
+        self.stream.set_position(0);
+        self.stream.write_vec(data)?;
+        self.stream.set_len(size)?;
+        self.stream.flush()
+    }
+}
\ No newline at end of file
@@ -1,7 +1,6 @@
-use crate::io::streams::{ReadStream, Stream};
-use crate::tags::general::{Tag, StringTag, Taggable};
-use std::any::{Any, TypeId};
-use std::marker::PhantomData;
+use crate::error::{OdsError, Result};
+use crate::io::streams::Stream;
+use crate::tags::general::{Tag, Taggable};
 
 #[derive(Debug, Clone)]
 pub struct TagBuilder {
@@ -68,11 +67,18 @@ impl TagBuilder {
     }
 
     pub fn set_value_bytes(&mut self, read_stream: Stream) {
-        self.value_bytes = Some(read_stream.clone());
+        self.value_bytes = Some(read_stream);
     }
 
-    pub fn get_value_bytes(mut self) -> Option<Stream> {
-        self.value_bytes.clone()
+    /// Get the Stream that holds the value of the tag.
+    /// The Stream's position is set to the start of the value.
+    pub fn get_value_bytes(&self) -> Result<Stream> {
+        self.value_bytes.clone().ok_or(OdsError::Truncated)
+    }
+
+    /// Take the Stream that holds the value of the tag, without copying it. (See `get_value_bytes`.)
+    pub fn take_value_bytes(&mut self) -> Result<Stream> {
+        self.value_bytes.take().ok_or(OdsError::Truncated)
     }
 
     pub fn set_value_length(&mut self, length: i32) {
@@ -83,7 +89,13 @@ impl TagBuilder {
         self.value_length
     }
 
-    pub fn process<T: Taggable<T>>(self) -> Option<Tag<T>> {
+    pub fn process<T: Taggable<T>>(self) -> Result<Tag<T>> {
         T::process(self)
     }
+}
+
+impl Default for TagBuilder {
+    fn default() -> Self {
+        TagBuilder::new()
+    }
 }
\ No newline at end of file
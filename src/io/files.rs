@@ -0,0 +1,110 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::Result;
+
+/// Used to give every temp file of this process a different name.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Get the path of the backup that `write_atomic` keeps of a file. (Ex: `data.ods` -> `data.ods.bak`)
+pub fn backup_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_os_string();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// Replace the contents of a file so that a crash or power loss leaves either the old or the new contents,
+/// never a part of the new contents.
+///
+/// The data is written to a temp file in the same directory, which is synced to disk and then renamed over the file.
+///
+/// # Params
+/// file: `&Path` -> The file to write.
+/// data: `&[u8]` -> The new contents of the file.
+/// keep_backup: bool -> If the previous version of the file should be kept at `backup_path(file)`.
+pub fn write_atomic(file: &Path, data: &[u8], keep_backup: bool) -> Result<()> {
+    let temp = temp_path(file);
+    if let Err(err) = write_synced(&temp, data) {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+
+    if keep_backup && file.exists() {
+        let backup = backup_path(file);
+        if let Err(err) = fs::copy(file, &backup).and_then(|_| File::open(&backup)?.sync_all()) {
+            let _ = fs::remove_file(&temp);
+            return Err(err.into());
+        }
+    }
+
+    if let Err(err) = fs::rename(&temp, file) {
+        let _ = fs::remove_file(&temp);
+        return Err(err.into());
+    }
+    sync_parent(file);
+    Ok(())
+}
+
+/// Append data to the end of a file, without reading or rewriting the data that is already in it.
+/// The file is created if it does not exist.
+///
+/// The bytes that are already in the file are never changed. If the data cannot be written the file is cut back to its
+/// previous size, but a crash while appending can leave a part of the data at the end of the file.
+///
+/// # Params
+/// file: `&Path` -> The file to append to.
+/// data: `&[u8]` -> The data to append.
+/// sync: bool -> If the data should be synced to disk before returning.
+///
+/// # Returns
+/// The position that the data was appended at. (The previous size of the file.)
+pub fn append_to_file(file: &Path, data: &[u8], sync: bool) -> Result<u64> {
+    let created = !file.exists();
+    let mut output = OpenOptions::new().append(true).create(true).open(file)?;
+    let position = output.metadata()?.len();
+    let written = output.write_all(data).and_then(|_| if sync { output.sync_data() } else { Ok(()) });
+    if let Err(err) = written {
+        let _ = output.set_len(position);
+        return Err(err.into());
+    }
+    if created && sync {
+        sync_parent(file);
+    }
+    Ok(position)
+}
+
+/// Get a path for a temp file next to the file. It has to be in the same directory so the rename is atomic.
+fn temp_path(file: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(file.file_name().unwrap_or_default());
+    name.push(format!(".{}.{}.tmp", std::process::id(), TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)));
+    file.with_file_name(name)
+}
+
+/// Write data to a new file and sync it to disk.
+fn write_synced(file: &Path, data: &[u8]) -> Result<()> {
+    let mut output = OpenOptions::new().write(true).create_new(true).open(file)?;
+    output.write_all(data)?;
+    output.sync_all()?;
+    Ok(())
+}
+
+/// Sync the directory of the file, so the rename itself survives a power loss.
+/// This is not supported on every platform, so it is only done where possible.
+fn sync_parent(file: &Path) {
+    #[cfg(unix)]
+    {
+        let parent = match file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new(".")
+        };
+        if let Ok(directory) = File::open(parent) {
+            let _ = directory.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = file;
+}
\ No newline at end of file
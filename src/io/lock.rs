@@ -0,0 +1,94 @@
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fs2::FileExt;
+
+use crate::error::{OdsError, Result};
+
+/// How long to wait between attempts to take a lock that is held by another process.
+const RETRY_DELAY: Duration = Duration::from_millis(10);
+
+/// How a file waits for a lock that is held by another process.
+///
+/// The locks are advisory, they only keep out other ObjectDataStructures (or programs that lock `<file>.lock`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockMode {
+    /// The file is not locked.
+    Disabled,
+    /// Wait until the lock can be taken. (The default.)
+    #[default]
+    Blocking,
+    /// Return `OdsError::LockTimeout` if the lock cannot be taken right away.
+    Try,
+    /// Wait at most the duration, then return `OdsError::LockTimeout`.
+    Timeout(Duration)
+}
+
+/// A lock on a file. The lock is released when it is dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    _lock_file: File
+}
+
+/// Get the path of the file that is locked for a file. (Ex: `data.ods` -> `data.ods.lock`)
+///
+/// A separate file is locked since the file itself is replaced (not changed) when it is written.
+pub fn lock_path(file: &Path) -> PathBuf {
+    let mut name = file.as_os_str().to_os_string();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// Lock a file.
+///
+/// Any number of shared locks can be held at once, but an exclusive lock can only be held while there are no other locks.
+///
+/// # Params
+/// file: `&Path` -> The file to lock.
+/// exclusive: bool -> If the lock is exclusive (for writing) or shared (for reading).
+/// mode: `LockMode` -> How to wait for the lock.
+///
+/// # Returns
+/// The lock, or None if the mode is `LockMode::Disabled`. A shared lock is also None when the lock file cannot be
+/// created but the file exists (Ex: The file is in a read only directory), since then no other ObjectDataStructure can
+/// change the file either.
+pub fn lock_file(file: &Path, exclusive: bool, mode: LockMode) -> Result<Option<FileLock>> {
+    let deadline = match mode {
+        LockMode::Disabled => return Ok(Option::None),
+        LockMode::Blocking => Option::None,
+        LockMode::Try => Some(Instant::now()),
+        LockMode::Timeout(timeout) => Some(Instant::now() + timeout)
+    };
+
+    let path = lock_path(file);
+    let lock_file = match OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path) {
+        Ok(lock_file) => lock_file,
+        Err(_) if !exclusive && file.exists() => return Ok(Option::None),
+        Err(err) => return Err(err.into())
+    };
+    match deadline {
+        // The methods are called through `FileExt`, since newer versions of Rust have `File` methods with the same names.
+        Option::None if exclusive => FileExt::lock_exclusive(&lock_file)?,
+        Option::None => FileExt::lock_shared(&lock_file)?,
+        Some(deadline) => loop {
+            let attempt = if exclusive { FileExt::try_lock_exclusive(&lock_file) } else { FileExt::try_lock_shared(&lock_file) };
+            match attempt {
+                Ok(()) => break,
+                Err(err) if err.raw_os_error() != fs2::lock_contended_error().raw_os_error() => return Err(err.into()),
+                Err(_) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(OdsError::LockTimeout(file.display().to_string()));
+                    }
+                    thread::sleep(RETRY_DELAY.min(deadline - now));
+                }
+            }
+        }
+    }
+
+    Ok(Some(FileLock {
+        _lock_file: lock_file
+    }))
+}
\ No newline at end of file
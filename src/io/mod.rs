@@ -1 +1,3 @@
-pub mod streams;
\ No newline at end of file
+pub mod streams;
+pub mod files;
+pub mod lock;
\ No newline at end of file
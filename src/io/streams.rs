@@ -1,48 +1,63 @@
-use std::fs;
+use std::convert::TryFrom;
 use std::fs::File;
-use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::io;
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 
+use crate::error::{OdsError, Result};
+use crate::io::files::write_atomic;
+use crate::limits::ParseLimits;
+
+/// Get the byte that a char is stored as.
+///
+/// # Returns
+/// `OdsError::InvalidChar` if the char is above `U+00FF`.
+pub(crate) fn char_byte(character: char) -> Result<u8> {
+    u8::try_from(character).map_err(|_| OdsError::InvalidChar(character))
+}
+
 /// The IORead is a trait that is meant for streams that Read data.
 /// See the `ReadStream` and `Stream`
 pub trait IORead {
     /// Reads a single byte.
-    fn read(&mut self) -> u8;
+    fn read(&mut self) -> Result<u8>;
 
     /// Reads a vector from the Stream.
     /// # Example
     /// ```rust
+    /// # use object_data_structure::io::streams::{Stream, IORead};
+    /// # let mut read_stream = Stream::new_empty();
     /// // Read 10 bytes into the vector named vec.
     /// let mut vec = vec![0;10];
     /// read_stream.read_vec(&mut vec);
     /// ```
-    fn read_vec(&mut self, buf: &mut [u8]);
+    fn read_vec(&mut self, buf: &mut [u8]) -> Result<()>;
 
     /// Read a string from the Stream.
     ///
     /// # Params
     /// size: `u64` -> Denotes the length of the string.
-    fn read_string(&mut self, size: u64) -> String;
+    fn read_string(&mut self, size: u64) -> Result<String>;
 
     /// Read a Short (i16) from the Stream.
-    fn read_i16(&mut self) -> i16;
+    fn read_i16(&mut self) -> Result<i16>;
 
     /// Read an Int (i32) from the Stream.
-    fn read_i32(&mut self) -> i32;
+    fn read_i32(&mut self) -> Result<i32>;
 
     /// Read a Long (i64) from the Stream.
-    fn read_i64(&mut self) -> i64;
+    fn read_i64(&mut self) -> Result<i64>;
 
     /// Read a Float (f32) from the Stream.
-    fn read_f32(&mut self) -> f32;
+    fn read_f32(&mut self) -> Result<f32>;
 
     /// Read a Double (f64) from the Stream.
-    fn read_f64(&mut self) -> f64;
+    fn read_f64(&mut self) -> Result<f64>;
 
     /// Read a Character (char) from the Stream.
-    fn read_char(&mut self) -> char;
+    fn read_char(&mut self) -> Result<char>;
 }
 
 /// This trait has the methods for Standard IO operations for the Streams.
@@ -54,6 +69,8 @@ pub trait StandardIO {
     ///
     /// # Example
     /// ```rust
+    /// # use object_data_structure::io::streams::{Stream, StandardIO};
+    /// # let mut read_stream = Stream::new_empty();
     /// read_stream.set_position(0);
     /// ```
     fn set_position(&mut self, position: u64);
@@ -86,38 +103,41 @@ pub trait StandardIO {
 /// cursor to the end to write there.
 pub trait IOWrite {
     /// Write a single byte to the Stream.
-    fn write(&mut self, byte: u8);
+    fn write(&mut self, byte: u8) -> Result<()>;
 
     /// Write a vector of bytes to the Stream.
-    fn write_vec(&mut self, bytes: Vec<u8>);
+    fn write_vec(&mut self, bytes: Vec<u8>) -> Result<()>;
 
     /// Write a Short (i16) to the Stream.
-    fn write_i16(&mut self, short: i16);
+    fn write_i16(&mut self, short: i16) -> Result<()>;
 
     /// Write an Int (i32) to the Stream.
-    fn write_i32(&mut self, int: i32);
+    fn write_i32(&mut self, int: i32) -> Result<()>;
 
     /// Write an Long (i64) to the Stream.
-    fn write_i64(&mut self, long: i64);
+    fn write_i64(&mut self, long: i64) -> Result<()>;
 
     /// Write a Float (f32) to the Stream.
-    fn write_f32(&mut self, float: f32);
+    fn write_f32(&mut self, float: f32) -> Result<()>;
 
     /// Write a Double (f64) to the Stream.
-    fn write_f64(&mut self, double: f64);
+    fn write_f64(&mut self, double: f64) -> Result<()>;
 
     /// Write a Character (char) to the Stream.
-    fn write_char(&mut self, character: char);
+    ///
+    /// A char is stored as one byte, so chars above `U+00FF` return `OdsError::InvalidChar` instead of being cut off.
+    fn write_char(&mut self, character: char) -> Result<()>;
 
     /// Write a String to the Stream.
-    fn write_string(&mut self, string: String);
+    fn write_string(&mut self, string: String) -> Result<()>;
 
     /// Clear all data from the Stream.
     fn clear(&mut self);
 
     /// Export all of the bytes to a file.
-    /// **Note:** This will overwrite the existing data in that file.
-    fn export_to_file(&mut self, file: PathBuf) -> bool;
+    /// **Note:** This will overwrite the existing data in that file. The file is replaced atomically,
+    /// so it never contains only a part of the bytes.
+    fn export_to_file(&mut self, file: PathBuf) -> Result<()>;
 }
 
 /// A Stream is a Stream that can both Read and Write. This
@@ -128,20 +148,23 @@ pub trait IOWrite {
 /// use object_data_structure::io::streams::{Stream, IOWrite, StandardIO, IORead};
 ///
 /// let mut empty_stream = Stream::new_empty();
-/// empty_stream.write_string("This is a test string!".to_string());
+/// empty_stream.write_string("This is a test string!".to_string())?;
 /// empty_stream.set_position(0);
-/// let string = empty_stream.read_string(22); // This is a test string!
+/// let string = empty_stream.read_string(22)?; // This is a test string!
+/// # Ok::<(), object_data_structure::error::OdsError>(())
 /// ```
 /// Create from a file:
-/// ```rust
+/// ```rust,no_run
 ///  use object_data_structure::io::streams::{Stream, IOWrite, StandardIO, IORead};
 ///  use std::path::PathBuf;
 ///
-///  let mut empty_stream = Stream::new_from_file(&PathBuf::from("./test.ods"));
+///  let mut empty_stream = Stream::new_from_file(&PathBuf::from("./test.ods"))?;
+/// # Ok::<(), object_data_structure::error::OdsError>(())
 /// ```
 #[derive(Clone, Debug)]
 pub struct Stream {
-    buffer: Cursor<Vec<u8>>
+    buffer: Cursor<Vec<u8>>,
+    limits: ParseLimits
 }
 
 impl Stream {
@@ -150,14 +173,15 @@ impl Stream {
     ///
     /// # Params
     /// file: `&PathBuf` -> The PathBuf of the file to read.
-    pub fn new_from_file(file: &PathBuf) -> Stream {
-        let mut f = File::open(file).unwrap();
+    pub fn new_from_file(file: &PathBuf) -> Result<Stream> {
+        let mut f = File::open(file)?;
         let mut buffer = Vec::new();
-        f.read_to_end(&mut buffer).unwrap();
+        f.read_to_end(&mut buffer)?;
 
-        Stream {
-            buffer: Cursor::new(buffer)
-        }
+        Ok(Stream {
+            buffer: Cursor::new(buffer),
+            limits: ParseLimits::default()
+        })
     }
 
     /// Create a Stream with no existing data.
@@ -165,7 +189,8 @@ impl Stream {
         let buffer: Vec<u8> = Vec::new();
         let cursor = Cursor::new(buffer);
         Stream {
-            buffer: cursor
+            buffer: cursor,
+            limits: ParseLimits::default()
         }
     }
 
@@ -178,104 +203,109 @@ impl Stream {
         let mut cursor = Cursor::new(data);
         cursor.set_position(len as u64);
         Stream {
-            buffer: cursor
+            buffer: cursor,
+            limits: ParseLimits::default()
         }
     }
+
+    /// Set the limits that tags read from the Stream are checked against. (See `ParseLimits`.)
+    pub fn set_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+    }
+
+    /// Get the limits that tags read from the Stream are checked against.
+    pub fn get_limits(&self) -> ParseLimits {
+        self.limits
+    }
 }
 
 impl IORead for Stream {
-    fn read(&mut self) -> u8 {
-        self.buffer.read_u8().unwrap()
+    fn read(&mut self) -> Result<u8> {
+        Ok(self.buffer.read_u8()?)
     }
 
-    fn read_vec(&mut self, buf: &mut [u8]) {
-        self.buffer.read_exact(buf);
+    fn read_vec(&mut self, buf: &mut [u8]) -> Result<()> {
+        Ok(self.buffer.read_exact(buf)?)
     }
 
-    fn read_string(&mut self, size: u64) -> String {
-        let old_pos = self.buffer.position();
-        let mut string_buf = Vec::new();
-        while self.buffer.position() < old_pos + size {
-            string_buf.push(self.buffer.read_u8().unwrap());
+    fn read_string(&mut self, size: u64) -> Result<String> {
+        if self.buffer.position() + size > self.buffer.get_ref().len() as u64 {
+            return Err(OdsError::Truncated);
         }
+        let mut string_buf = vec![0; size as usize];
+        self.buffer.read_exact(&mut string_buf)?;
 
-        String::from_utf8(string_buf).unwrap()
+        String::from_utf8(string_buf).map_err(|err| OdsError::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
     }
 
-    fn read_i16(&mut self) -> i16 {
-        self.buffer.read_i16::<BigEndian>().unwrap()
+    fn read_i16(&mut self) -> Result<i16> {
+        Ok(self.buffer.read_i16::<BigEndian>()?)
     }
 
-    fn read_i32(&mut self) -> i32 {
-        self.buffer.read_i32::<BigEndian>().unwrap()
+    fn read_i32(&mut self) -> Result<i32> {
+        Ok(self.buffer.read_i32::<BigEndian>()?)
     }
 
-    fn read_i64(&mut self) -> i64 {
-        self.buffer.read_i64::<BigEndian>().unwrap()
+    fn read_i64(&mut self) -> Result<i64> {
+        Ok(self.buffer.read_i64::<BigEndian>()?)
     }
 
-    fn read_f32(&mut self) -> f32 {
-        self.buffer.read_f32::<BigEndian>().unwrap()
+    fn read_f32(&mut self) -> Result<f32> {
+        Ok(self.buffer.read_f32::<BigEndian>()?)
     }
 
-    fn read_f64(&mut self) -> f64 {
-        self.buffer.read_f64::<BigEndian>().unwrap()
+    fn read_f64(&mut self) -> Result<f64> {
+        Ok(self.buffer.read_f64::<BigEndian>()?)
     }
 
-    fn read_char(&mut self) -> char {
-        self.buffer.read_u8().unwrap() as char
+    fn read_char(&mut self) -> Result<char> {
+        Ok(self.buffer.read_u8()? as char)
     }
 }
 
 impl IOWrite for Stream {
-    fn write(&mut self, byte: u8) {
-        self.buffer.write_u8(byte);
+    fn write(&mut self, byte: u8) -> Result<()> {
+        Ok(self.buffer.write_u8(byte)?)
     }
 
-    fn write_vec(&mut self, bytes: Vec<u8>) {
-        for byte in bytes {
-            self.buffer.write_u8(byte);
-        }
+    fn write_vec(&mut self, bytes: Vec<u8>) -> Result<()> {
+        Ok(self.buffer.write_all(&bytes)?)
     }
 
-    fn write_i16(&mut self, short: i16) {
-        self.buffer.write_i16::<BigEndian>(short);
+    fn write_i16(&mut self, short: i16) -> Result<()> {
+        Ok(self.buffer.write_i16::<BigEndian>(short)?)
     }
 
-    fn write_i32(&mut self, int: i32) {
-        self.buffer.write_i32::<BigEndian>(int);
+    fn write_i32(&mut self, int: i32) -> Result<()> {
+        Ok(self.buffer.write_i32::<BigEndian>(int)?)
     }
 
-    fn write_i64(&mut self, long: i64) {
-        self.buffer.write_i64::<BigEndian>(long);
+    fn write_i64(&mut self, long: i64) -> Result<()> {
+        Ok(self.buffer.write_i64::<BigEndian>(long)?)
     }
 
-    fn write_f32(&mut self, float: f32) {
-        self.buffer.write_f32::<BigEndian>(float);
+    fn write_f32(&mut self, float: f32) -> Result<()> {
+        Ok(self.buffer.write_f32::<BigEndian>(float)?)
     }
 
-    fn write_f64(&mut self, double: f64) {
-        self.buffer.write_f64::<BigEndian>(double);
+    fn write_f64(&mut self, double: f64) -> Result<()> {
+        Ok(self.buffer.write_f64::<BigEndian>(double)?)
     }
 
-    fn write_char(&mut self, character: char) {
-        self.buffer.write_u8(character as u8);
+    fn write_char(&mut self, character: char) -> Result<()> {
+        Ok(self.buffer.write_u8(char_byte(character)?)?)
     }
 
-    fn write_string(&mut self, string: String) {
-        let data = string.as_bytes();
-        for byte in data.iter() {
-            self.buffer.write_u8(*byte);
-        }
+    fn write_string(&mut self, string: String) -> Result<()> {
+        Ok(self.buffer.write_all(string.as_bytes())?)
     }
 
     fn clear(&mut self) {
         self.buffer = Cursor::new(Vec::new());
     }
 
-    fn export_to_file(&mut self, file: PathBuf) -> bool {
-        // TODO Add error handling.
-        fs::write(file, self.bytes()).is_ok()
+    fn export_to_file(&mut self, file: PathBuf) -> Result<()> {
+        write_atomic(&file, &self.bytes(), false)
     }
 }
 
@@ -289,15 +319,7 @@ impl StandardIO for Stream {
     }
 
     fn size(&mut self) -> usize {
-        // Store the current position.
-        let pos = self.position();
-        // Go to the end of the buffer.
-        self.buffer.seek(SeekFrom::End(0));
-        // The length of the buffer is the end position. (Add 1 to count position 0).
-        let output = self.buffer.position();
-        self.buffer.set_position(pos);
-
-        output as usize
+        self.buffer.get_ref().len()
     }
 
     fn position(&mut self) -> u64 {
@@ -309,19 +331,12 @@ impl StandardIO for Stream {
     }
 
     fn goto_end(&mut self) {
-        self.buffer.seek(SeekFrom::End(0));
+        let len = self.buffer.get_ref().len();
+        self.buffer.set_position(len as u64);
     }
 
     fn bytes(&mut self) -> Vec<u8> {
-        let pos = self.position();
-        // Go to front
-        self.buffer.set_position(0);
-        let mut end_vec: Vec<u8> = Vec::new();
-        // Read entire buffer.
-        self.buffer.read_to_end(&mut end_vec);
-        // Go back to the previous position
-        self.buffer.set_position(pos);
-        end_vec
+        self.buffer.get_ref().clone()
     }
 }
 
@@ -334,59 +349,59 @@ pub struct ReadStream {
 }
 
 impl ReadStream {
-    pub fn new(file: &PathBuf) -> ReadStream {
-        let mut f = File::open(file).unwrap();
+    pub fn new(file: &PathBuf) -> Result<ReadStream> {
+        let mut f = File::open(file)?;
         let mut buffer = Vec::new();
-        let size = f.read_to_end(&mut buffer).unwrap();
+        let size = f.read_to_end(&mut buffer)?;
 
-        ReadStream {
+        Ok(ReadStream {
             buffer: Cursor::new(buffer),
             size,
-        }
+        })
     }
 }
 
 impl IORead for ReadStream {
-    fn read(&mut self) -> u8 {
-        self.buffer.read_u8().unwrap()
+    fn read(&mut self) -> Result<u8> {
+        Ok(self.buffer.read_u8()?)
     }
 
-    fn read_vec(&mut self, buf: &mut [u8]) {
-        self.buffer.read_exact(buf);
+    fn read_vec(&mut self, buf: &mut [u8]) -> Result<()> {
+        Ok(self.buffer.read_exact(buf)?)
     }
 
-    fn read_string(&mut self, size: u64) -> String {
-        let old_pos = self.buffer.position();
-        let mut string_buf = Vec::new();
-        while self.buffer.position() < old_pos + size {
-            string_buf.push(self.buffer.read_u8().unwrap());
+    fn read_string(&mut self, size: u64) -> Result<String> {
+        if self.buffer.position() + size > self.buffer.get_ref().len() as u64 {
+            return Err(OdsError::Truncated);
         }
+        let mut string_buf = vec![0; size as usize];
+        self.buffer.read_exact(&mut string_buf)?;
 
-        String::from_utf8(string_buf).unwrap()
+        String::from_utf8(string_buf).map_err(|err| OdsError::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
     }
 
-    fn read_i16(&mut self) -> i16 {
-        self.buffer.read_i16::<BigEndian>().unwrap()
+    fn read_i16(&mut self) -> Result<i16> {
+        Ok(self.buffer.read_i16::<BigEndian>()?)
     }
 
-    fn read_i32(&mut self) -> i32 {
-        self.buffer.read_i32::<BigEndian>().unwrap()
+    fn read_i32(&mut self) -> Result<i32> {
+        Ok(self.buffer.read_i32::<BigEndian>()?)
     }
 
-    fn read_i64(&mut self) -> i64 {
-        self.buffer.read_i64::<BigEndian>().unwrap()
+    fn read_i64(&mut self) -> Result<i64> {
+        Ok(self.buffer.read_i64::<BigEndian>()?)
     }
 
-    fn read_f32(&mut self) -> f32 {
-        self.buffer.read_f32::<BigEndian>().unwrap()
+    fn read_f32(&mut self) -> Result<f32> {
+        Ok(self.buffer.read_f32::<BigEndian>()?)
     }
 
-    fn read_f64(&mut self) -> f64 {
-        self.buffer.read_f64::<BigEndian>().unwrap()
+    fn read_f64(&mut self) -> Result<f64> {
+        Ok(self.buffer.read_f64::<BigEndian>()?)
     }
 
-    fn read_char(&mut self) -> char {
-        self.buffer.read_u8().unwrap() as char
+    fn read_char(&mut self) -> Result<char> {
+        Ok(self.buffer.read_u8()? as char)
     }
 }
 
@@ -412,19 +427,12 @@ impl StandardIO for ReadStream {
     }
 
     fn goto_end(&mut self) {
-        self.buffer.seek(SeekFrom::End(0));
+        let len = self.buffer.get_ref().len();
+        self.buffer.set_position(len as u64);
     }
 
     fn bytes(&mut self) -> Vec<u8> {
-        let pos = self.position();
-        // Go to front
-        self.buffer.set_position(0);
-        let mut end_vec: Vec<u8> = Vec::new();
-        // Read entire buffer.
-        self.buffer.read_to_end(&mut end_vec);
-        // Go back to the previous position
-        self.buffer.set_position(pos);
-        end_vec
+        self.buffer.get_ref().clone()
     }
 }
 
@@ -453,55 +461,55 @@ impl WriteStream {
     }
 }
 
+impl Default for WriteStream {
+    fn default() -> Self {
+        WriteStream::new()
+    }
+}
+
 impl IOWrite for WriteStream {
-    fn write(&mut self, byte: u8) {
-        self.buffer.write_u8(byte);
+    fn write(&mut self, byte: u8) -> Result<()> {
+        Ok(self.buffer.write_u8(byte)?)
     }
 
-    fn write_vec(&mut self, bytes: Vec<u8>) {
-        for byte in bytes {
-            self.buffer.write_u8(byte);
-        }
+    fn write_vec(&mut self, bytes: Vec<u8>) -> Result<()> {
+        Ok(self.buffer.write_all(&bytes)?)
     }
 
-    fn write_i16(&mut self, short: i16) {
-        self.buffer.write_i16::<BigEndian>(short);
+    fn write_i16(&mut self, short: i16) -> Result<()> {
+        Ok(self.buffer.write_i16::<BigEndian>(short)?)
     }
 
-    fn write_i32(&mut self, int: i32) {
-        self.buffer.write_i32::<BigEndian>(int);
+    fn write_i32(&mut self, int: i32) -> Result<()> {
+        Ok(self.buffer.write_i32::<BigEndian>(int)?)
     }
 
-    fn write_i64(&mut self, long: i64) {
-        self.buffer.write_i64::<BigEndian>(long);
+    fn write_i64(&mut self, long: i64) -> Result<()> {
+        Ok(self.buffer.write_i64::<BigEndian>(long)?)
     }
 
-    fn write_f32(&mut self, float: f32) {
-        self.buffer.write_f32::<BigEndian>(float);
+    fn write_f32(&mut self, float: f32) -> Result<()> {
+        Ok(self.buffer.write_f32::<BigEndian>(float)?)
     }
 
-    fn write_f64(&mut self, double: f64) {
-        self.buffer.write_f64::<BigEndian>(double);
+    fn write_f64(&mut self, double: f64) -> Result<()> {
+        Ok(self.buffer.write_f64::<BigEndian>(double)?)
     }
 
-    fn write_char(&mut self, character: char) {
-        self.buffer.write_u8(character as u8);
+    fn write_char(&mut self, character: char) -> Result<()> {
+        Ok(self.buffer.write_u8(char_byte(character)?)?)
     }
 
-    fn write_string(&mut self, string: String) {
-        let data = string.as_bytes();
-        for byte in data.iter() {
-            self.buffer.write_u8(*byte);
-        }
+    fn write_string(&mut self, string: String) -> Result<()> {
+        Ok(self.buffer.write_all(string.as_bytes())?)
     }
 
     fn clear(&mut self) {
         self.buffer = Cursor::new(Vec::new());
     }
 
-    fn export_to_file(&mut self, file: PathBuf) -> bool {
-        // TODO Add error handling.
-        fs::write(file, self.bytes()).is_ok()
+    fn export_to_file(&mut self, file: PathBuf) -> Result<()> {
+        write_atomic(&file, &self.bytes(), false)
     }
 }
 
@@ -515,15 +523,7 @@ impl StandardIO for WriteStream {
     }
 
     fn size(&mut self) -> usize {
-        // Store the current position.
-        let pos = self.position();
-        // Go to the end of the buffer.
-        self.buffer.seek(SeekFrom::End(0));
-        // The length of the buffer is the end position. (Add 1 to count position 0).
-        let output = self.buffer.position();
-        self.buffer.set_position(pos);
-
-        output as usize
+        self.buffer.get_ref().len()
     }
 
     fn position(&mut self) -> u64 {
@@ -535,18 +535,393 @@ impl StandardIO for WriteStream {
     }
 
     fn goto_end(&mut self) {
-        self.buffer.seek(SeekFrom::End(0));
+        let len = self.buffer.get_ref().len();
+        self.buffer.set_position(len as u64);
     }
 
     fn bytes(&mut self) -> Vec<u8> {
-        let pos = self.position();
-        // Go to front
-        self.buffer.set_position(0);
-        let mut end_vec: Vec<u8> = Vec::new();
-        // Read entire buffer.
-        self.buffer.read_to_end(&mut end_vec);
-        // Go back to the previous position
-        self.buffer.set_position(pos);
-        end_vec
+        self.buffer.get_ref().clone()
+    }
+}
+
+/// A SeekStream is a Stream over any reader that can seek, such as a `File`, an archive entry or a custom virtual filesystem.
+/// Only the bytes that are read are loaded into memory.
+///
+/// It implements `IORead` and `StandardIO`, and `IOWrite` when the reader can also be written to and truncated. (See `Truncate`.)
+/// The size of the data is read when the SeekStream is created, so the data must not be changed
+/// by anything other than the SeekStream while it is used.
+///
+/// Small reads are served from a read ahead buffer, so reading the headers of tags that are next to each other does not
+/// need a system call for every header. The reader does not have to be buffered (Ex: A `File` can be used as it is).
+/// Writes are passed straight to the writer.
+///
+/// **Note:** `StandardIO::bytes` reads the whole stream and returns an empty Vector if that fails. Use `read_all` to get the error.
+///
+/// # Example
+/// ```rust
+/// use object_data_structure::io::streams::{SeekStream, IORead, StandardIO};
+/// use std::io::Cursor;
+///
+/// let mut seek_stream = SeekStream::new(Cursor::new(vec![0, 0, 0, 5, 1]))?;
+/// assert_eq!(seek_stream.read_i32()?, 5);
+/// assert!(seek_stream.can_read_more());
+/// # Ok::<(), object_data_structure::error::OdsError>(())
+/// ```
+#[derive(Debug)]
+pub struct SeekStream<S> {
+    inner: S,
+    position: u64,
+    size: u64,
+    /// If the position of the inner reader no longer matches `position`.
+    seek_needed: bool,
+    limits: ParseLimits,
+    /// The bytes that were read ahead, starting at `buffer_start`.
+    buffer: Vec<u8>,
+    buffer_start: u64
+}
+
+/// The number of bytes a SeekStream reads ahead.
+const READ_AHEAD: usize = 8 * 1024;
+
+impl<S: Seek> SeekStream<S> {
+    /// Create a SeekStream over a reader.
+    /// **Note:** The position starts at 0.
+    ///
+    /// # Params
+    /// inner: `S` -> The reader. It can be at any position, the size is found by seeking to the end.
+    pub fn new(mut inner: S) -> Result<SeekStream<S>> {
+        let size = inner.seek(SeekFrom::End(0))?;
+        Ok(SeekStream {
+            inner,
+            position: 0,
+            size,
+            seek_needed: true,
+            limits: ParseLimits::default(),
+            buffer: Vec::new(),
+            buffer_start: 0
+        })
+    }
+
+    /// Set the limits that tags read from the Stream are checked against. (See `ParseLimits`.)
+    pub fn set_limits(&mut self, limits: ParseLimits) {
+        self.limits = limits;
+    }
+
+    /// Get the limits that tags read from the Stream are checked against.
+    pub fn get_limits(&self) -> ParseLimits {
+        self.limits
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Get the reader. It must not be used to change the size of the data.
+    pub fn get_mut(&mut self) -> &mut S {
+        self.seek_needed = true;
+        self.buffer.clear();
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    /// End the Stream at a position, so the data after it is never read. (Ex: The footer index of a file.)
+    pub(crate) fn limit(&mut self, size: u64) {
+        self.size = self.size.min(size);
+    }
+
+    /// Move the inner reader to the position of the Stream.
+    fn sync(&mut self) -> Result<()> {
+        if self.seek_needed {
+            self.inner.seek(SeekFrom::Start(self.position))?;
+            self.seek_needed = false;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Read + Seek> SeekStream<S> {
+    /// Read all of the bytes of the stream. The position of the stream does not change.
+    pub fn read_all(&mut self) -> Result<Vec<u8>> {
+        let position = self.position;
+        self.position = 0;
+        self.seek_needed = true;
+        let mut output = vec![0; self.size as usize];
+        let result = self.read_vec(&mut output);
+        self.position = position;
+        self.seek_needed = true;
+        result.map(|_| output)
+    }
+}
+
+impl<S: Write + Seek + Truncate> SeekStream<S> {
+    /// Set the size of the data. The position of the stream does not change.
+    pub fn set_len(&mut self, size: u64) -> Result<()> {
+        self.buffer.clear();
+        self.inner.set_len(size)?;
+        self.size = size;
+        Ok(())
+    }
+
+    /// Flush the written bytes to the writer.
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.inner.flush()?)
+    }
+}
+
+impl<S> SeekStream<S> {
+    /// Check if the bytes from the position up to a length are in the read ahead buffer.
+    fn buffered(&self, length: usize) -> bool {
+        self.position >= self.buffer_start && self.position + length as u64 <= self.buffer_start + self.buffer.len() as u64
+    }
+}
+
+impl<S: Read + Seek> IORead for SeekStream<S> {
+    fn read(&mut self) -> Result<u8> {
+        let mut buf = [0; 1];
+        self.read_vec(&mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_vec(&mut self, buf: &mut [u8]) -> Result<()> {
+        if self.position + buf.len() as u64 > self.size {
+            return Err(OdsError::Truncated);
+        }
+        if buf.len() < READ_AHEAD && !self.buffered(buf.len()) {
+            // Fill the buffer with the bytes at the position, then read from it.
+            let length = READ_AHEAD.min((self.size - self.position) as usize);
+            self.buffer.resize(length, 0);
+            self.buffer_start = self.position;
+            self.sync()?;
+            let result = self.inner.read_exact(&mut self.buffer);
+            self.seek_needed = true;
+            if let Err(err) = result {
+                self.buffer.clear();
+                return Err(err.into());
+            }
+        }
+        if self.buffered(buf.len()) {
+            let start = (self.position - self.buffer_start) as usize;
+            buf.copy_from_slice(&self.buffer[start..start + buf.len()]);
+        } else {
+            self.sync()?;
+            if let Err(err) = self.inner.read_exact(buf) {
+                self.seek_needed = true;
+                return Err(err.into());
+            }
+        }
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+
+    fn read_string(&mut self, size: u64) -> Result<String> {
+        if self.position + size > self.size {
+            return Err(OdsError::Truncated);
+        }
+        let mut string_buf = vec![0; size as usize];
+        self.read_vec(&mut string_buf)?;
+
+        String::from_utf8(string_buf).map_err(|err| OdsError::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
+    }
+
+    fn read_i16(&mut self) -> Result<i16> {
+        let mut buf = [0; 2];
+        self.read_vec(&mut buf)?;
+        Ok(i16::from_be_bytes(buf))
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        let mut buf = [0; 4];
+        self.read_vec(&mut buf)?;
+        Ok(i32::from_be_bytes(buf))
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        let mut buf = [0; 8];
+        self.read_vec(&mut buf)?;
+        Ok(i64::from_be_bytes(buf))
+    }
+
+    fn read_f32(&mut self) -> Result<f32> {
+        let mut buf = [0; 4];
+        self.read_vec(&mut buf)?;
+        Ok(f32::from_be_bytes(buf))
+    }
+
+    fn read_f64(&mut self) -> Result<f64> {
+        let mut buf = [0; 8];
+        self.read_vec(&mut buf)?;
+        Ok(f64::from_be_bytes(buf))
+    }
+
+    fn read_char(&mut self) -> Result<char> {
+        Ok(self.read()? as char)
+    }
+}
+
+impl<S: Read + Write + Seek + Truncate> IOWrite for SeekStream<S> {
+    fn write(&mut self, byte: u8) -> Result<()> {
+        self.write_vec(vec![byte])
+    }
+
+    fn write_vec(&mut self, bytes: Vec<u8>) -> Result<()> {
+        self.buffer.clear();
+        self.sync()?;
+        if let Err(err) = self.inner.write_all(&bytes) {
+            self.seek_needed = true;
+            return Err(err.into());
+        }
+        self.position += bytes.len() as u64;
+        self.size = self.size.max(self.position);
+        Ok(())
+    }
+
+    fn write_i16(&mut self, short: i16) -> Result<()> {
+        self.write_vec(short.to_be_bytes().to_vec())
+    }
+
+    fn write_i32(&mut self, int: i32) -> Result<()> {
+        self.write_vec(int.to_be_bytes().to_vec())
+    }
+
+    fn write_i64(&mut self, long: i64) -> Result<()> {
+        self.write_vec(long.to_be_bytes().to_vec())
+    }
+
+    fn write_f32(&mut self, float: f32) -> Result<()> {
+        self.write_vec(float.to_be_bytes().to_vec())
+    }
+
+    fn write_f64(&mut self, double: f64) -> Result<()> {
+        self.write_vec(double.to_be_bytes().to_vec())
+    }
+
+    fn write_char(&mut self, character: char) -> Result<()> {
+        self.write(char_byte(character)?)
+    }
+
+    fn write_string(&mut self, string: String) -> Result<()> {
+        self.write_vec(string.into_bytes())
+    }
+
+    /// Remove all of the data. If the writer cannot be truncated the data is kept.
+    fn clear(&mut self) {
+        if self.set_len(0).is_ok() {
+            self.set_position(0);
+        }
+    }
+
+    fn export_to_file(&mut self, file: PathBuf) -> Result<()> {
+        write_atomic(&file, &self.read_all()?, false)
+    }
+}
+
+impl<S: Read + Seek> StandardIO for SeekStream<S> {
+    fn set_position(&mut self, position: u64) {
+        self.position = position;
+        self.seek_needed = true;
+    }
+
+    fn skip(&mut self, skip_amount: u64) {
+        self.set_position(self.position + skip_amount);
+    }
+
+    fn size(&mut self) -> usize {
+        self.size as usize
+    }
+
+    fn position(&mut self) -> u64 {
+        self.position
+    }
+
+    fn can_read_more(&mut self) -> bool {
+        self.position < self.size
+    }
+
+    fn goto_end(&mut self) {
+        self.set_position(self.size);
+    }
+
+    fn bytes(&mut self) -> Vec<u8> {
+        self.read_all().unwrap_or_default()
+    }
+}
+
+/// A writer whose data can be made shorter (or longer). This is needed to write a document over a longer one.
+pub trait Truncate {
+    /// Set the size of the data. New bytes are set to 0.
+    fn set_len(&mut self, size: u64) -> io::Result<()>;
+}
+
+impl Truncate for File {
+    fn set_len(&mut self, size: u64) -> io::Result<()> {
+        File::set_len(self, size)
+    }
+}
+
+impl Truncate for Cursor<Vec<u8>> {
+    fn set_len(&mut self, size: u64) -> io::Result<()> {
+        self.get_mut().resize(size as usize, 0);
+        Ok(())
+    }
+}
+
+impl Truncate for Cursor<&mut Vec<u8>> {
+    fn set_len(&mut self, size: u64) -> io::Result<()> {
+        self.get_mut().resize(size as usize, 0);
+        Ok(())
+    }
+}
+
+impl<T: Truncate + ?Sized> Truncate for &mut T {
+    fn set_len(&mut self, size: u64) -> io::Result<()> {
+        (**self).set_len(size)
+    }
+}
+
+/// A reader that cannot be written to. Every write returns an `io::ErrorKind::PermissionDenied` error.
+///
+/// This allows a reader that only implements `Read + Seek` to be used where a writer is expected.
+#[derive(Debug, Clone)]
+pub struct ReadOnly<R>(pub R);
+
+impl<R> ReadOnly<R> {
+    pub fn into_inner(self) -> R {
+        self.0
+    }
+}
+
+/// The error that is returned when a ReadOnly reader is written to.
+pub(crate) fn read_only_error() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "the stream is read only")
+}
+
+impl<R: Read> Read for ReadOnly<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for ReadOnly<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl<R> Write for ReadOnly<R> {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(read_only_error())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<R> Truncate for ReadOnly<R> {
+    fn set_len(&mut self, _size: u64) -> io::Result<()> {
+        Err(read_only_error())
     }
 }
\ No newline at end of file
@@ -0,0 +1,200 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{OdsError, Result};
+
+/// A section of a `KeyPath`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KeySegment {
+    /// The tag with this name.
+    Name(String),
+    /// The tag at this position. (Ex: An element of a Vec Tag.)
+    Index(usize)
+}
+
+/// The location of a tag inside of other tags.
+///
+/// A key path is written as the names of the tags separated by dots, with `[n]` to select the tag at
+/// position `n` (Ex: `players[3].name`). A `\` escapes the next character, so names can contain
+/// dots and brackets. (Ex: `version\.1` is the tag named `version.1`.)
+///
+/// Anything that accepts a key also accepts a `KeyPath`, so a path only has to be parsed once.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct KeyPath {
+    segments: Vec<KeySegment>
+}
+
+impl KeyPath {
+    /// Create an empty key path.
+    pub fn new() -> KeyPath {
+        KeyPath {
+            segments: Vec::new()
+        }
+    }
+
+    /// Parse a key path.
+    ///
+    /// # Returns
+    /// `OdsError::InvalidKey` if the key contains an empty name, an unfinished escape or an invalid index.
+    /// An empty string is the empty key path.
+    pub fn parse(key: &str) -> Result<KeyPath> {
+        let invalid = || OdsError::InvalidKey(key.to_string());
+        let mut segments = Vec::new();
+        let mut name = String::new();
+        // If the last segment was an index. A name has to be separated from an index by a dot.
+        let mut after_index = false;
+        // If a dot was read that has not been followed by a name yet.
+        let mut after_dot = false;
+
+        let mut chars = key.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => {
+                    if !name.is_empty() {
+                        segments.push(KeySegment::Name(std::mem::take(&mut name)));
+                    } else if !after_index {
+                        return Err(invalid());
+                    }
+                    after_index = false;
+                    after_dot = true;
+                }
+                '[' => {
+                    if !name.is_empty() {
+                        segments.push(KeySegment::Name(std::mem::take(&mut name)));
+                    } else if after_dot || !(segments.is_empty() || after_index) {
+                        return Err(invalid());
+                    }
+
+                    let mut index = String::new();
+                    loop {
+                        match chars.next() {
+                            Some(']') => break,
+                            Some(digit) if digit.is_ascii_digit() => index.push(digit),
+                            _ => return Err(invalid())
+                        }
+                    }
+                    segments.push(KeySegment::Index(index.parse().map_err(|_| invalid())?));
+                    after_index = true;
+                    after_dot = false;
+                }
+                ']' => return Err(invalid()),
+                c => {
+                    if after_index {
+                        return Err(invalid());
+                    }
+                    name.push(if c == '\\' { chars.next().ok_or_else(invalid)? } else { c });
+                    after_dot = false;
+                }
+            }
+        }
+
+        if !name.is_empty() {
+            segments.push(KeySegment::Name(name));
+        } else if after_dot {
+            return Err(invalid());
+        }
+
+        Ok(KeyPath {
+            segments
+        })
+    }
+
+    /// Create a key path from its segments.
+    pub fn from_segments(segments: Vec<KeySegment>) -> KeyPath {
+        KeyPath {
+            segments
+        }
+    }
+
+    /// Add the tag with the specified name to the end of the path. The name is not escaped.
+    pub fn push_name(&mut self, name: String) {
+        self.segments.push(KeySegment::Name(name));
+    }
+
+    /// Add the tag at the specified position to the end of the path.
+    pub fn push_index(&mut self, index: usize) {
+        self.segments.push(KeySegment::Index(index));
+    }
+
+    pub fn segments(&self) -> &[KeySegment] {
+        &self.segments
+    }
+
+    pub fn last(&self) -> Option<&KeySegment> {
+        self.segments.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+impl fmt::Display for KeyPath {
+    /// Write the key path in the format that `parse` reads, escaping the names where needed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                KeySegment::Name(name) => {
+                    if i > 0 {
+                        write!(f, ".")?;
+                    }
+                    for c in name.chars() {
+                        if matches!(c, '.' | '[' | ']' | '\\') {
+                            write!(f, "\\")?;
+                        }
+                        write!(f, "{}", c)?;
+                    }
+                }
+                KeySegment::Index(index) => write!(f, "[{}]", index)?
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeyPath {
+    type Err = OdsError;
+
+    fn from_str(key: &str) -> Result<KeyPath> {
+        KeyPath::parse(key)
+    }
+}
+
+/// A value that can be used as a key. Strings are parsed with `KeyPath::parse`.
+pub trait IntoKeyPath {
+    fn into_key_path(self) -> Result<KeyPath>;
+}
+
+impl IntoKeyPath for KeyPath {
+    fn into_key_path(self) -> Result<KeyPath> {
+        Ok(self)
+    }
+}
+
+impl IntoKeyPath for &KeyPath {
+    fn into_key_path(self) -> Result<KeyPath> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoKeyPath for String {
+    fn into_key_path(self) -> Result<KeyPath> {
+        KeyPath::parse(&self)
+    }
+}
+
+impl IntoKeyPath for &String {
+    fn into_key_path(self) -> Result<KeyPath> {
+        KeyPath::parse(self)
+    }
+}
+
+impl IntoKeyPath for &str {
+    fn into_key_path(self) -> Result<KeyPath> {
+        KeyPath::parse(self)
+    }
+}
\ No newline at end of file
@@ -4,76 +4,1401 @@
 pub mod io;
 pub mod tags;
 pub mod ods;
+pub mod error;
+pub mod compression;
+pub mod key_path;
+pub mod limits;
+pub mod validate;
+pub mod salvage;
 #[macro_use]
 pub mod util;
 pub mod internal;
+#[cfg(any(feature = "serde", test))]
+pub mod serialization;
 
 extern crate byteorder;
+extern crate flate2;
 
 #[cfg(test)]
 mod tests {
-    use crate::tags::general::{StringTag, DoubleTag, VecTag, AnyTag, Container, VectorContainer, FloatTag, ShortTag, ObjectTag};
-    use crate::io::streams::WriteStream;
+    use crate::tags::general::{StringTag, DoubleTag, VecTag, ObjectTag, CompressedObjectTag, MapTag, Container, VectorContainer, IntTag, FloatTag, AnyTag, Taggable};
+    use crate::error::OdsError;
+    use crate::io::streams::{IOWrite, Stream};
     use std::fs;
     use std::path::PathBuf;
     use crate::ods::ObjectDataStructure;
-    use std::fmt::Debug;
-    use crate::tags::container_types::Object;
+    use crate::tags::container_types::{Object, CompressedObject, Map};
+    use std::any::Any;
+    use std::collections::HashMap;
+    use crate::compression::Compression;
+    use crate::limits::ParseLimits;
+    use crate::tags::value::OdsValue;
+    use crate::key_path::{KeyPath, KeySegment};
+
+    /// Get a path in the temp directory for a test file. Any existing file is removed.
+    fn test_file(name: &str) -> PathBuf {
+        let file = std::env::temp_dir().join(format!("ods_{}_{}.ods", name, std::process::id()));
+        let _ = fs::remove_file(&file);
+        file
+    }
 
     #[test]
     fn it_works() {
-        // let tag = TagIMPL::new();
-        // print_data(tag);
+        let file = test_file("it_works");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
 
+        let example_tag = VecTag::from_vec("My_Double".to_string(), &mut anytag_vec!(
+            StringTag::new("My_Cool_Tag".to_string(), "My Next Value!".to_string()),
+            StringTag::new("3".to_string(), " greig erjiog eorjg oe!".to_string()),
+            StringTag::new("f".to_string(), " few fw efwe!".to_string()),
+            DoubleTag::new("db".to_string(), 20.5436)
+        )).unwrap();
+        let object_tag = ObjectTag::from_vec("OOF".to_string(), anytag_vec!(
+            StringTag::new("My_Cool_Tag".to_string(), "My Next Value!".to_string()),
+            StringTag::new("Name".to_string(), " greig erjiog eorjg oe!".to_string()),
+            StringTag::new("Fun".to_string(), " few fw efwe!".to_string()),
+            DoubleTag::new("db".to_string(), 20.5436)
+        )).unwrap();
+        ods.append(example_tag).unwrap();
+        ods.append(object_tag).unwrap();
+        ods.append(StringTag::new("My_Cool_Tag".to_string(), "My Value!".to_string())).unwrap();
 
-        // let tag = StringTag::new("Test".to_string(), "Test String from Rust".to_string());
-        // let mut stream = WriteStream::new();
-        // tag.write_data(&mut stream);
-        // stream.export_to_file(PathBuf::from("./test.ods"));
+        let mut test = ods.get::<Container<VectorContainer>>("My_Double".to_string()).unwrap();
+        assert_eq!(test.get::<String>(1).unwrap().get_value(), " greig erjiog eorjg oe!");
+        assert!(test.is_type::<f64>(3).unwrap());
+        assert_eq!(test.len().unwrap(), 4);
 
-        let mut ods = ObjectDataStructure::new_file(PathBuf::from("./test.ods"));
-        // let data = ods.get::<String>("Test".to_string());
+        let mut obj = ods.get::<Object>("OOF".to_string()).unwrap();
+        assert_eq!(obj.get::<String>("Fun".to_string()).unwrap().get_value(), " few fw efwe!");
+        assert_eq!(ods.get::<String>("OOF.Fun".to_string()).unwrap().get_value(), " few fw efwe!");
 
-        // println!("Value From Test Tag: {}", data.unwrap().get_value());
+        assert!(!ods.find("Test_Tag".to_string()).unwrap());
 
-        // let tags = ods.get_all().unwrap();
-        // println!("{:?}", tags[0].downcast_any_tag::<VecTag>().get_value());
+        ods.append(tag![StringTag, "BOB", "Test".to_string()]).unwrap();
+        assert!(ods.find("BOB".to_string()).unwrap());
 
-        let mut test = ods.get::<Container<VectorContainer>>("My_Double".to_string()).unwrap();
-        println!("Is Value: {:?}", test.get::<String>(1).unwrap().get_value());
+        assert!(ods.replace_data("My_Cool_Tag".to_string(), StringTag::new("My_Cool_Tag".to_string(), "My Other Value!".to_string())).unwrap());
+        assert_eq!(ods.get::<String>("My_Cool_Tag".to_string()).unwrap().get_value(), "My Other Value!");
+
+        assert!(ods.delete("OOF.Fun".to_string()).unwrap());
+        assert!(!ods.find("OOF.Fun".to_string()).unwrap());
+        assert!(ods.find("OOF.db".to_string()).unwrap());
+
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn errors_instead_of_panics() {
+        let file = test_file("errors");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.append(IntTag::new("Number".to_string(), 5)).unwrap();
+        ods.append(ObjectTag::from_vec("Obj".to_string(), anytag_vec!(IntTag::new("Inner".to_string(), 1))).unwrap()).unwrap();
 
+        assert!(matches!(ods.get::<i32>("Missing".to_string()), Err(OdsError::KeyNotFound(key)) if key == "Missing"));
+        assert!(matches!(ods.get::<i32>("Obj.Missing".to_string()), Err(OdsError::KeyNotFound(key)) if key == "Obj.Missing"));
+        assert!(matches!(ods.get::<String>("Number".to_string()), Err(OdsError::TypeMismatch { expected: 1, found: 2 })));
+        assert!(matches!(ods.get::<i32>("Number.Inner".to_string()), Err(OdsError::TypeMismatch { expected: 11, found: 2 })));
+        assert!(matches!(ods.get::<i32>("Obj..Inner".to_string()), Err(OdsError::InvalidKey(_))));
+        assert!(matches!(ods.get::<i32>("".to_string()), Err(OdsError::InvalidKey(_))));
 
-        println!("Is Double: {:?}", test.is_type::<f64>(3));
+        let any_tag = ods.get_all().unwrap().remove(0);
+        assert!(matches!(any_tag.downcast_any_tag::<String>(), Err(OdsError::TypeMismatch { expected: 1, found: 2 })));
+        assert_eq!(*any_tag.downcast_any_tag::<i32>().unwrap().get_value(), 5);
+        assert!(matches!(Taggable::as_tag(any_tag.get_value(), "Copy".to_string()), Err(OdsError::Unsupported(_))));
+        assert_eq!(*5i32.as_tag("Copy".to_string()).unwrap().get_value(), 5);
 
-        println!("Size: {}", test.len());
+        // Cut the file in the middle of the last tag.
+        let data = fs::read(&file).unwrap();
+        fs::write(&file, &data[..data.len() - 3]).unwrap();
+        assert!(matches!(ods.get_all(), Err(OdsError::Truncated)));
+        assert!(matches!(ods.get::<i32>("Obj.Inner".to_string()), Err(OdsError::Truncated)));
 
-        // let mut example_tag = VecTag::from_vec("My_Double".to_string(), &mut vec_tag!(
-        //     StringTag::new("My_Cool_Tag".to_string(), "My Next Value!".to_string()),
-        //     StringTag::new("3".to_string(), " greig erjiog eorjg oe!".to_string()),
-        //     StringTag::new("f".to_string(), " few fw efwe!".to_string()),
-        //     DoubleTag::new("db".to_string(), 20.5436)
-        // ));
-        // let mut object_tag = ObjectTag::from_vec("OOF".to_string(), vec_tag!(
-        //     StringTag::new("My_Cool_Tag".to_string(), "My Next Value!".to_string()),
-        //     StringTag::new("Name".to_string(), " greig erjiog eorjg oe!".to_string()),
-        //     StringTag::new("Fun".to_string(), " few fw efwe!".to_string()),
-        //     DoubleTag::new("db".to_string(), 20.5436)
-        // ));
-        //
-        // ods.append(object_tag);
+        // A tag with an unknown id.
+        let mut stream = Stream::new_empty();
+        stream.write(42).unwrap();
+        stream.write_i32(2).unwrap();
+        stream.write_i16(0).unwrap();
+        stream.export_to_file(file.clone()).unwrap();
+        assert!(matches!(ods.get_all(), Err(OdsError::UnknownTag(42))));
 
-        let mut obj = ods.get::<Object>("OOF".to_string()).unwrap();
+        // A negative size must not send the reader backwards.
+        let mut stream = Stream::new_empty();
+        stream.write(1).unwrap();
+        stream.write_i32(-4).unwrap();
+        stream.export_to_file(file.clone()).unwrap();
+        assert!(matches!(ods.get::<String>("Test".to_string()), Err(OdsError::Truncated)));
+
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn memory_round_trip() {
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        ods.append(StringTag::new("Name".to_string(), "Memory".to_string())).unwrap();
+        ods.append(ObjectTag::from_vec("Obj".to_string(), anytag_vec!(IntTag::new("Count".to_string(), 1))).unwrap()).unwrap();
+
+        assert!(ods.find("Obj.Count".to_string()).unwrap());
+        assert!(ods.replace_data("Obj.Count".to_string(), IntTag::new("Count".to_string(), 2)).unwrap());
+        assert_eq!(*ods.get::<i32>("Obj.Count".to_string()).unwrap().get_value(), 2);
+        assert!(ods.delete("Name".to_string()).unwrap());
+        assert!(!ods.delete("Name".to_string()).unwrap());
+
+        let bytes = ods.into_bytes();
+        let mut copy = ObjectDataStructure::new_memory(bytes.clone());
+        assert_eq!(copy.get_all().unwrap().len(), 1);
+        assert_eq!(*copy.get::<i32>("Obj.Count".to_string()).unwrap().get_value(), 2);
+
+        // The memory and file backends produce the same bytes.
+        let file = test_file("memory_round_trip");
+        let mut file_ods = ObjectDataStructure::new_file(file.clone());
+        file_ods.append(ObjectTag::from_vec("Obj".to_string(), anytag_vec!(IntTag::new("Count".to_string(), 2))).unwrap()).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), bytes);
+        fs::remove_file(file).unwrap();
+    }
+
+    /// A StringTag named `Name` with the value `Java`, compressed by Java's GZIPOutputStream and DeflaterOutputStream.
+    const JAVA_GZIP: [u8; 35] = [0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0x63, 0x64, 0x60, 0x60, 0xe0, 0x62, 0x60, 0xf1, 0x4b, 0xcc, 0x4d, 0xf5, 0x4a, 0x2c, 0x4b, 0x04, 0x00, 0x40, 0x34, 0x69, 0xdb, 0x0f, 0x00, 0x00, 0x00];
+    const JAVA_ZLIB: [u8; 23] = [0x78, 0x9c, 0x63, 0x64, 0x60, 0x60, 0xe0, 0x62, 0x60, 0xf1, 0x4b, 0xcc, 0x4d, 0xf5, 0x4a, 0x2c, 0x4b, 0x04, 0x00, 0x0d, 0xe6, 0x03, 0x13];
+
+    #[test]
+    fn compressed_file_matches_java() {
+        for (compression, java_bytes) in [(Compression::Gzip, &JAVA_GZIP[..]), (Compression::Zlib, &JAVA_ZLIB[..])].iter() {
+            let file = test_file(&format!("compressed_{:?}", compression));
+            fs::write(&file, java_bytes).unwrap();
+
+            let mut ods = ObjectDataStructure::new_compressed_file(file.clone(), *compression);
+            assert_eq!(ods.get::<String>("Name".to_string()).unwrap().get_value(), "Java");
+
+            // Writing the same document again gives the same bytes as Java.
+            fs::remove_file(&file).unwrap();
+            ods.append(StringTag::new("Name".to_string(), "Java".to_string())).unwrap();
+            assert_eq!(fs::read(&file).unwrap(), java_bytes.to_vec());
+
+            ods.append(IntTag::new("Number".to_string(), 12)).unwrap();
+            assert!(ods.replace_data("Name".to_string(), StringTag::new("Name".to_string(), "Rust".to_string())).unwrap());
+            assert!(ods.delete("Number".to_string()).unwrap());
+            assert_eq!(ods.get::<String>("Name".to_string()).unwrap().get_value(), "Rust");
+            assert_eq!(compression.decompress(&fs::read(&file).unwrap()).unwrap().len(), 15);
+
+            fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn compressed_object_traversal() {
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        let compressed = CompressedObjectTag::from_vec("Compressed".to_string(), anytag_vec!(
+            StringTag::new("Name".to_string(), "Inside".to_string()),
+            ObjectTag::from_vec("Inner".to_string(), anytag_vec!(IntTag::new("Value".to_string(), 7))).unwrap()
+        )).unwrap();
+        ods.append(ObjectTag::from_vec("Outer".to_string(), anytag_vec!(compressed, IntTag::new("After".to_string(), 1))).unwrap()).unwrap();
+        ods.append(IntTag::new("Last".to_string(), 2)).unwrap();
+
+        assert_eq!(ods.get::<String>("Outer.Compressed.Name".to_string()).unwrap().get_value(), "Inside");
+        assert_eq!(*ods.get::<i32>("Outer.Compressed.Inner.Value".to_string()).unwrap().get_value(), 7);
+        assert!(ods.find("Outer.Compressed.Inner".to_string()).unwrap());
+        assert!(!ods.find("Outer.Compressed.Missing".to_string()).unwrap());
+
+        let mut tag = ods.get::<CompressedObject>("Outer.Compressed".to_string()).unwrap();
+        assert_eq!(tag.len().unwrap(), 2);
+        assert!(tag.is_type::<i32>("Inner.Value".to_string()).unwrap());
+
+        assert!(ods.replace_data("Outer.Compressed.Name".to_string(), StringTag::new("Name".to_string(), "A much longer value than before".to_string())).unwrap());
+        assert!(ods.delete("Outer.Compressed.Inner.Value".to_string()).unwrap());
+        assert!(!ods.delete("Outer.Compressed.Inner.Value".to_string()).unwrap());
+
+        assert_eq!(ods.get::<String>("Outer.Compressed.Name".to_string()).unwrap().get_value(), "A much longer value than before");
+        assert_eq!(ods.get::<Object>("Outer.Compressed.Inner".to_string()).unwrap().len().unwrap(), 0);
+        // The sizes of the parents were updated, so the tags after them can still be read.
+        assert_eq!(*ods.get::<i32>("Outer.After".to_string()).unwrap().get_value(), 1);
+        assert_eq!(*ods.get::<i32>("Last".to_string()).unwrap().get_value(), 2);
+        assert_eq!(ods.get_all().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn map_tag() {
+        let mut map = HashMap::new();
+        map.insert("first".to_string(), 1);
+        map.insert("second.key".to_string(), 2);
+
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        let map_tag = MapTag::from_map("Map".to_string(), map.clone()).unwrap();
+        // Maps can be written through an AnyTag.
+        ods.append(ObjectTag::from_vec("Obj".to_string(), anytag_vec!(map_tag)).unwrap()).unwrap();
+
+        let mut map_tag = ods.get::<Map<i32>>("Obj.Map".to_string()).unwrap();
+        assert_eq!(map_tag.to_map().unwrap(), map);
+        assert_eq!(*map_tag.get("second.key".to_string()).unwrap().get_value(), 2);
+        assert!(matches!(map_tag.get("third".to_string()), Err(OdsError::KeyNotFound(_))));
+
+        map_tag.insert("first".to_string(), 10).unwrap();
+        map_tag.insert("third".to_string(), 3).unwrap();
+        assert!(map_tag.remove("second.key".to_string()).unwrap());
+        assert!(!map_tag.remove("second.key".to_string()).unwrap());
+        let mut keys = map_tag.keys().unwrap();
+        keys.sort();
+        assert_eq!(keys, vec!["first".to_string(), "third".to_string()]);
+        assert_eq!(*map_tag.get("first".to_string()).unwrap().get_value(), 10);
+        assert_eq!(map_tag.len().unwrap(), 2);
+
+        // A map of the wrong type reports a type mismatch for its values.
+        let mut wrong = map_tag.clone().convert::<String>();
+        assert!(matches!(wrong.get("first".to_string()), Err(OdsError::TypeMismatch { expected: 1, found: 2 })));
+
+        assert!(ods.replace_data("Obj.Map".to_string(), map_tag).unwrap());
+        let any_tag = ods.get::<Object>("Obj".to_string()).unwrap().get::<Box<dyn Any>>("Map".to_string()).unwrap();
+        assert_eq!(any_tag.get_id(), 10);
+        let mut any_map = any_tag.downcast_any_map_tag().unwrap();
+        let mut typed = any_map.clone().convert::<i32>();
+        assert_eq!(typed.to_map().unwrap().get("third"), Some(&3));
+        assert_eq!(*any_map.get("third".to_string()).unwrap().downcast_any_tag::<i32>().unwrap().get_value(), 3);
+
+        // An AnyTag that was read from a file can be written again.
+        let mut copy = ObjectDataStructure::new_memory(Vec::new());
+        copy.append(any_tag).unwrap();
+        assert_eq!(copy.get::<Map<i32>>("Map".to_string()).unwrap().to_map().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn set_creates_parents() {
+        let file = test_file("set");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.append(IntTag::new("Number".to_string(), 1)).unwrap();
+
+        // Missing parents at the root are created.
+        ods.set("a.b.c".to_string(), Some(IntTag::new("ignored".to_string(), 5))).unwrap();
+        assert_eq!(*ods.get::<i32>("a.b.c".to_string()).unwrap().get_value(), 5);
+
+        // Missing parents inside of an existing object are created, and the parent sizes stay correct.
+        ods.set("a.x.y".to_string(), Some(StringTag::new("y".to_string(), "Hello".to_string()))).unwrap();
+        ods.set("a.b.d".to_string(), Some(IntTag::new("d".to_string(), 6))).unwrap();
+        ods.append(IntTag::new("Last".to_string(), 9)).unwrap();
+        assert_eq!(ods.get::<String>("a.x.y".to_string()).unwrap().get_value(), "Hello");
+        assert_eq!(*ods.get::<i32>("a.b.d".to_string()).unwrap().get_value(), 6);
+        assert_eq!(*ods.get::<i32>("a.b.c".to_string()).unwrap().get_value(), 5);
+        assert_eq!(ods.get::<Object>("a".to_string()).unwrap().len().unwrap(), 2);
+
+        // Existing tags are replaced.
+        ods.set("a.b.c".to_string(), Some(StringTag::new("c".to_string(), "Replaced".to_string()))).unwrap();
+        assert_eq!(ods.get::<String>("a.b.c".to_string()).unwrap().get_value(), "Replaced");
+        assert_eq!(*ods.get::<i32>("Last".to_string()).unwrap().get_value(), 9);
+
+        // None deletes.
+        ods.set::<i32>("a.b.d".to_string(), None).unwrap();
+        assert!(!ods.find("a.b.d".to_string()).unwrap());
+        assert!(matches!(ods.set::<i32>("a.b.d".to_string(), None), Err(OdsError::KeyNotFound(_))));
+
+        // A tag that is not an object cannot be used as a parent.
+        assert!(matches!(ods.set("Number.x".to_string(), Some(IntTag::new("x".to_string(), 1))), Err(OdsError::TypeMismatch { expected: 11, found: 2 })));
+
+        // Keys inside of compressed objects work as well.
+        ods.append(CompressedObjectTag::from_vec("Compressed".to_string(), Vec::new()).unwrap()).unwrap();
+        ods.set("Compressed.Inner.Value".to_string(), Some(IntTag::new("Value".to_string(), 3))).unwrap();
+        assert_eq!(*ods.get::<i32>("Compressed.Inner.Value".to_string()).unwrap().get_value(), 3);
+        assert_eq!(ods.get_all().unwrap().len(), 4);
+
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn save_and_append_all() {
+        let file = test_file("save");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.append(IntTag::new("Old".to_string(), 1)).unwrap();
+
+        ods.save(anytag_vec!(
+            StringTag::new("Name".to_string(), "Saved".to_string()),
+            FloatTag::new("Float".to_string(), 1.5)
+        )).unwrap();
+        assert!(!ods.find("Old".to_string()).unwrap());
+        assert_eq!(*ods.get::<f32>("Float".to_string()).unwrap().get_value(), 1.5);
+
+        ods.append_all(anytag_vec!(
+            IntTag::new("Int".to_string(), 2),
+            ObjectTag::from_vec("Obj".to_string(), anytag_vec!(DoubleTag::new("Double".to_string(), 2.5))).unwrap()
+        )).unwrap();
+        assert_eq!(ods.get_all().unwrap().len(), 4);
+        assert_eq!(*ods.get::<f64>("Obj.Double".to_string()).unwrap().get_value(), 2.5);
+
+        // A batch that cannot be written does not change the file.
+        let before = fs::read(&file).unwrap();
+        let invalid = AnyTag::from_tag(AnyTag::from_tag(IntTag::new("Invalid".to_string(), 0)));
+        assert!(matches!(ods.append_all(vec![AnyTag::from_tag(IntTag::new("Valid".to_string(), 0)), invalid]), Err(OdsError::UnknownTag(0))));
+        assert_eq!(fs::read(&file).unwrap(), before);
+
+        // An empty key replaces the whole document.
+        ods.set("".to_string(), Some(IntTag::new("Only".to_string(), 3))).unwrap();
+        assert_eq!(ods.get_all().unwrap().len(), 1);
+
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn value_tree() {
+        let mut map = HashMap::new();
+        map.insert("Key".to_string(), 5);
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        ods.append_all(anytag_vec!(
+            StringTag::new("Name".to_string(), "Value".to_string()),
+            ObjectTag::from_vec("Obj".to_string(), anytag_vec!(
+                FloatTag::new("Float".to_string(), 1.5),
+                CompressedObjectTag::from_vec("Compressed".to_string(), anytag_vec!(IntTag::new("Int".to_string(), 7))).unwrap()
+            )).unwrap(),
+            VecTag::from_vec("Vec".to_string(), &mut anytag_vec!(DoubleTag::new("".to_string(), 2.5))).unwrap(),
+            MapTag::from_map("Map".to_string(), map).unwrap()
+        )).unwrap();
+        let original = ods.get_bytes().to_vec();
+
+        // Parsing and writing back gives the same bytes.
+        let mut value = ods.load_value().unwrap();
+        assert_eq!(value.to_bytes().unwrap(), original);
+        assert_eq!(value.get("Name").unwrap().as_str(), Some("Value"));
+        assert_eq!(value.get("Obj.Compressed.Int"), Some(&OdsValue::Int(7)));
+        assert_eq!(value.get("Vec").unwrap().as_vec().unwrap()[0].as_f64(), Some(2.5));
+        assert_eq!(value.get("Map.Key").unwrap().as_i64(), Some(5));
+        assert_eq!(value.get("Obj.Missing"), None);
+
+        // Mutations are written back.
+        value.set("Obj.Compressed.Int", OdsValue::Long(8)).unwrap();
+        value.set("New.Inner", OdsValue::Char('c')).unwrap();
+        *value.get_mut("Obj.Float").unwrap() = OdsValue::Short(3);
+        assert_eq!(value.remove("Name"), Some(OdsValue::String("Value".to_string())));
+        assert!(matches!(value.set("Vec.Inner", OdsValue::Byte(1)), Err(OdsError::TypeMismatch { expected: 11, found: 9 })));
+        ods.save_value(&value).unwrap();
+        assert!(!ods.find("Name".to_string()).unwrap());
+        assert_eq!(*ods.get::<i64>("Obj.Compressed.Int".to_string()).unwrap().get_value(), 8);
+        assert_eq!(*ods.get::<char>("New.Inner".to_string()).unwrap().get_value(), 'c');
+        assert_eq!(*ods.get::<i16>("Obj.Float".to_string()).unwrap().get_value(), 3);
+
+        // A Char Tag is one byte, so chars above U+00FF are an error instead of being cut off.
+        value.set("New.Accent", OdsValue::Char('é')).unwrap();
+        ods.save_value(&value).unwrap();
+        assert_eq!(*ods.get::<char>("New.Accent").unwrap().get_value(), 'é');
+        value.set("New.Emoji", OdsValue::Char('😀')).unwrap();
+        assert!(matches!(ods.save_value(&value), Err(OdsError::InvalidChar('😀'))));
+        assert!(matches!(value.to_bytes(), Err(OdsError::InvalidChar('😀'))));
+        assert!(matches!(ods.append(crate::tags::general::CharTag::new("Euro".to_string(), '€')), Err(OdsError::InvalidChar('€'))));
+        assert!(!ods.find("New.Emoji").unwrap());
+        assert!(!ods.find("Euro").unwrap());
+        value.remove("New.Emoji");
+
+        // Values convert to and from AnyTags.
+        let tag = value.get("Obj").unwrap().to_any_tag("Obj".to_string()).unwrap();
+        let (name, converted) = OdsValue::from_any_tag(&tag).unwrap();
+        assert_eq!(name, "Obj");
+        assert_eq!(&converted, value.get("Obj").unwrap());
+
+        assert!(matches!(OdsValue::from_bytes(&original[..original.len() - 1]), Err(OdsError::Truncated)));
+    }
+
+    #[test]
+    fn serde_round_trip() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum Kind {
+            Unit,
+            Newtype(u32),
+            Tuple(i8, bool),
+            Struct { name: String }
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Meters(f32);
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Owner {
+            name: String,
+            age: u16,
+            nickname: Option<String>
+        }
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Car {
+            model: String,
+            year: i32,
+            length: Meters,
+            price: f64,
+            grade: char,
+            electric: bool,
+            miles: u64,
+            owner: Owner,
+            previous_owners: Vec<Owner>,
+            kinds: Vec<Kind>,
+            parts: HashMap<u32, String>,
+            inspection: Option<i64>,
+            unit: ()
+        }
+
+        let mut parts = HashMap::new();
+        parts.insert(7, "Wheel".to_string());
+        let car = Car {
+            model: "Van".to_string(),
+            year: 2019,
+            length: Meters(4.5),
+            price: 15000.5,
+            grade: 'A',
+            electric: true,
+            miles: 40000,
+            owner: Owner { name: "Sam".to_string(), age: 30, nickname: None },
+            previous_owners: vec![Owner { name: "Alex".to_string(), age: 40, nickname: Some("Al".to_string()) }],
+            kinds: vec![Kind::Unit, Kind::Newtype(3), Kind::Tuple(-1, false), Kind::Struct { name: "Kind".to_string() }],
+            parts,
+            inspection: Some(5),
+            unit: ()
+        };
+
+        let bytes = crate::ods::to_bytes(&car).unwrap();
+        assert_eq!(crate::ods::from_bytes::<Car>(&bytes).unwrap(), car);
+
+        // The output can be read with get.
+        let mut ods = ObjectDataStructure::new_memory(bytes);
+        assert_eq!(ods.get::<String>("owner.name".to_string()).unwrap().get_value(), "Sam");
+        assert_eq!(*ods.get::<f32>("length".to_string()).unwrap().get_value(), 4.5);
+        assert_eq!(*ods.get::<u8>("electric".to_string()).unwrap().get_value(), 1);
+        assert_eq!(*ods.get::<i64>("miles".to_string()).unwrap().get_value(), 40000);
+        assert_eq!(ods.get::<String>("parts.7".to_string()).unwrap().get_value(), "Wheel");
+        assert!(!ods.find("owner.nickname".to_string()).unwrap());
+        let mut kinds = ods.get::<Container<VectorContainer>>("kinds".to_string()).unwrap();
+        assert_eq!(kinds.get::<String>(0).unwrap().get_value(), "Unit");
+        assert_eq!(*ods.get::<i32>("year".to_string()).unwrap().get_value(), 2019);
+
+        let mut writer = Vec::new();
+        crate::ods::to_writer(&mut writer, &car).unwrap();
+        assert_eq!(crate::ods::from_reader::<_, Car>(&writer[..]).unwrap(), car);
+
+        assert!(matches!(crate::ods::to_bytes(&5), Err(OdsError::Serde(_))));
+        assert!(matches!(crate::ods::to_bytes(&vec![None::<i32>]), Err(OdsError::Serde(_))));
+
+        // A Char Tag is one byte, so chars above it are an error instead of being cut off.
+        let mut chars = HashMap::new();
+        chars.insert("accent".to_string(), 'é');
+        assert_eq!(crate::ods::from_bytes::<HashMap<String, char>>(&crate::ods::to_bytes(&chars).unwrap()).unwrap(), chars);
+        chars.insert("euro".to_string(), '€');
+        assert!(matches!(crate::ods::to_bytes(&chars), Err(OdsError::InvalidChar('€'))));
+        assert!(matches!(crate::ods::from_bytes::<Owner>(&ods.into_bytes()), Err(OdsError::Serde(_))));
+    }
+
+    #[test]
+    fn key_paths() {
+        let key = KeyPath::parse("players[3].name\\.first").unwrap();
+        assert_eq!(key.segments(), &[
+            KeySegment::Name("players".to_string()),
+            KeySegment::Index(3),
+            KeySegment::Name("name.first".to_string())
+        ]);
+        assert_eq!(key.to_string(), "players[3].name\\.first");
+        assert_eq!(KeyPath::parse("[0][1].a\\[b\\]").unwrap().to_string(), "[0][1].a\\[b\\]");
+        for invalid in ["a..b", ".a", "a.", "a[x]", "a[0]b", "a]", "a\\", "a.[0]", "a[1"].iter() {
+            assert!(matches!(KeyPath::parse(invalid), Err(OdsError::InvalidKey(_))), "{}", invalid);
+        }
+
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        ods.append(StringTag::new("version.1".to_string(), "Dotted".to_string())).unwrap();
+        ods.append(VecTag::from_vec("players".to_string(), &mut anytag_vec!(
+            ObjectTag::from_vec("".to_string(), anytag_vec!(StringTag::new("name".to_string(), "Sam".to_string()))).unwrap(),
+            ObjectTag::from_vec("".to_string(), anytag_vec!(StringTag::new("name".to_string(), "Alex".to_string()))).unwrap()
+        )).unwrap()).unwrap();
+        ods.append(CompressedObjectTag::from_vec("Compressed".to_string(), anytag_vec!(
+            VecTag::from_vec("Numbers".to_string(), &mut anytag_vec!(IntTag::new("".to_string(), 1), IntTag::new("".to_string(), 2))).unwrap()
+        )).unwrap()).unwrap();
+
+        assert_eq!(ods.get::<String>("version\\.1").unwrap().get_value(), "Dotted");
+        assert!(matches!(ods.get::<String>("version.1"), Err(OdsError::KeyNotFound(_))));
+        assert_eq!(ods.get::<String>("players[1].name").unwrap().get_value(), "Alex");
+        assert_eq!(*ods.get::<i32>("Compressed.Numbers[1]").unwrap().get_value(), 2);
+        assert_eq!(ods.get::<String>(&"[0]".parse::<KeyPath>().unwrap()).unwrap().get_value(), "Dotted");
+        assert!(ods.find("players[0].name").unwrap());
+        assert!(!ods.find("players[2].name").unwrap());
+
+        assert!(ods.replace_data("players[1].name", StringTag::new("name".to_string(), "Max".to_string())).unwrap());
+        ods.set("players[0].score", Some(IntTag::new("".to_string(), 10))).unwrap();
+        ods.set("Compressed.Numbers[0]", Some(IntTag::new("".to_string(), 5))).unwrap();
+        assert_eq!(ods.get::<String>("players[1].name").unwrap().get_value(), "Max");
+        assert_eq!(*ods.get::<i32>("players[0].score").unwrap().get_value(), 10);
+        assert_eq!(*ods.get::<i32>("Compressed.Numbers[0]").unwrap().get_value(), 5);
+        assert!(matches!(ods.set("players[5].score", Some(IntTag::new("".to_string(), 1))), Err(OdsError::KeyNotFound(_))));
+
+        assert!(ods.delete("players[0]").unwrap());
+        assert_eq!(ods.get::<String>("players[0].name").unwrap().get_value(), "Max");
+        assert_eq!(ods.get::<Container<VectorContainer>>("players").unwrap().len().unwrap(), 1);
+
+        // Object Tags accept key paths as well.
+        let mut object = ObjectTag::from_vec("Obj".to_string(), anytag_vec!(
+            VecTag::from_vec("List".to_string(), &mut anytag_vec!(StringTag::new("".to_string(), "First".to_string()))).unwrap()
+        )).unwrap();
+        assert_eq!(object.get::<String>("List[0]").unwrap().get_value(), "First");
+        assert!(object.replace_data("List[0]", StringTag::new("".to_string(), "Second".to_string())).unwrap());
+        object.set("a\\.b.c", Some(IntTag::new("".to_string(), 3))).unwrap();
+        assert_eq!(object.get::<String>("List[0]").unwrap().get_value(), "Second");
+        assert_eq!(*object.get::<i32>("a\\.b.c").unwrap().get_value(), 3);
+        assert!(object.is_type::<i32>("a\\.b.c").unwrap());
+        assert!(object.remove("List[0]").unwrap());
+        assert!(!object.has_tag("List[0]").unwrap());
+
+        let value = ods.load_value().unwrap();
+        assert_eq!(value.get("players[0].name").unwrap().as_str(), Some("Max"));
+        assert_eq!(value.get("[0]").unwrap().as_str(), Some("Dotted"));
+    }
+
+    #[test]
+    fn atomic_writes() {
+        let file = test_file("atomic_writes");
+        let backup = crate::io::files::backup_path(&file);
+        let _ = fs::remove_file(&backup);
+
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.append(IntTag::new("First".to_string(), 1)).unwrap();
+        assert!(!backup.exists());
+
+        ods.set_keep_backup(true);
+        let before = fs::read(&file).unwrap();
+        ods.append(IntTag::new("Second".to_string(), 2)).unwrap();
+        assert_eq!(fs::read(&backup).unwrap(), before);
+        assert_eq!(ods.get_all().unwrap().len(), 2);
+
+        // No temp files are left behind.
+        let name = file.file_name().unwrap().to_str().unwrap().to_string();
+        let leftovers = fs::read_dir(file.parent().unwrap()).unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&format!(".{}.", name)))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        // A write that fails does not create the file.
+        let missing = std::env::temp_dir().join(format!("ods_missing_{}", std::process::id())).join("test.ods");
+        let mut ods = ObjectDataStructure::new_file(missing.clone());
+        assert!(matches!(ods.append(IntTag::new("First".to_string(), 1)), Err(OdsError::Io(_))));
+        assert!(!missing.exists());
+
+        fs::remove_file(file).unwrap();
+        fs::remove_file(backup).unwrap();
+    }
+
+    #[test]
+    fn journaled_file() {
+        let file = test_file("journaled_file");
+        let journal = crate::internal::journal::journal_path(&file);
+        let _ = fs::remove_file(&journal);
+
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.append_all(anytag_vec!(
+            StringTag::new("Name".to_string(), "Base".to_string()),
+            ObjectTag::from_vec("Obj".to_string(), anytag_vec!(IntTag::new("Int".to_string(), 1))).unwrap()
+        )).unwrap();
+        let base = fs::read(&file).unwrap();
+
+        let mut ods = ObjectDataStructure::new_journaled_file(file.clone()).unwrap();
+        ods.append(IntTag::new("Appended".to_string(), 2)).unwrap();
+        assert!(ods.replace_data("Name", StringTag::new("Name".to_string(), "Replaced".to_string())).unwrap());
+        ods.set("Obj.New.Inner", Some(DoubleTag::new("".to_string(), 1.5))).unwrap();
+        assert!(ods.delete("Obj.Int").unwrap());
+        assert!(!ods.delete("Missing").unwrap());
+
+        // The file is untouched, but every read includes the journal.
+        assert_eq!(fs::read(&file).unwrap(), base);
+        assert!(journal.exists());
+        let mut reader = ObjectDataStructure::new_file(file.clone());
+        assert_eq!(reader.get::<String>("Name").unwrap().get_value(), "Replaced");
+        assert_eq!(*reader.get::<f64>("Obj.New.Inner").unwrap().get_value(), 1.5);
+        assert!(!reader.find("Obj.Int").unwrap());
+        let expected = reader.load_value().unwrap();
+
+        // A record that was only partly written is ignored.
+        let mut journal_data = fs::read(&journal).unwrap();
+        let complete = journal_data.clone();
+        journal_data.extend_from_slice(&[1, 0, 0, 0, 20, 1, 2]);
+        fs::write(&journal, &journal_data).unwrap();
+        assert_eq!(reader.load_value().unwrap(), expected);
+
+        // The next record is written over it instead of after it, so it is not lost.
+        ods.append(IntTag::new("AfterTear".to_string(), 3)).unwrap();
+        assert_eq!(fs::read(&journal).unwrap()[..complete.len()], complete[..]);
+        assert_eq!(*reader.get::<i32>("AfterTear").unwrap().get_value(), 3);
+
+        // A document that is kept in memory between changes sees the changes of other writers.
+        let mut other = ObjectDataStructure::new_journaled_file(file.clone()).unwrap();
+        other.append(IntTag::new("Other".to_string(), 4)).unwrap();
+        assert!(ods.delete("AfterTear").unwrap());
+        assert_eq!(*reader.get::<i32>("Other").unwrap().get_value(), 4);
+        assert!(!reader.find("AfterTear").unwrap());
+        assert!(other.delete("Other").unwrap());
+        assert_eq!(reader.load_value().unwrap(), expected);
+
+        // Reads of a file with a journal come from the document in memory, which is read again after another writer
+        // changes it.
+        assert_eq!(ods.load_value().unwrap(), expected);
+        other.append(IntTag::new("Seen".to_string(), 5)).unwrap();
+        assert_eq!(*ods.get::<i32>("Seen").unwrap().get_value(), 5);
+        assert_eq!(*reader.get::<i32>("Seen").unwrap().get_value(), 5);
+        assert!(other.delete("Seen").unwrap());
+        assert!(!ods.find("Seen").unwrap());
+        assert_eq!(ods.get_all().unwrap().len(), 3);
+        let complete = fs::read(&journal).unwrap();
 
-        println!("OBJ DATA: {}", obj.get::<String>("Fun".to_string()).unwrap().get_value());
-        println!("OBJ DATA DIRECT: {}", ods.get::<String>("OOF.Fun".to_string()).unwrap().get_value());
+        // Opening the file again folds the journal into it.
+        let mut ods = ObjectDataStructure::new_journaled_file(file.clone()).unwrap();
+        assert!(!journal.exists());
+        assert_eq!(ods.load_value().unwrap(), expected);
+        assert_eq!(*ods.get::<i32>("Appended").unwrap().get_value(), 2);
 
-        println!("{}", ods.find("Test_Tag".to_string()));
+        // A journal that was already folded into the file is not applied again.
+        fs::write(&journal, &complete).unwrap();
+        assert_eq!(ods.load_value().unwrap(), expected);
+        ods.checkpoint().unwrap();
+        assert!(!journal.exists());
+        assert_eq!(ods.load_value().unwrap(), expected);
+
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn file_locks() {
+        use crate::io::lock::{lock_file, lock_path, LockMode};
+        use std::time::Duration;
+
+        let file = test_file("file_locks");
+
+        // Appends from several writers at once are never lost.
+        let writers: Vec<_> = (0..4).map(|writer| {
+            let file = file.clone();
+            std::thread::spawn(move || {
+                let mut ods = ObjectDataStructure::new_file(file);
+                for i in 0..10 {
+                    ods.append(IntTag::new(format!("Writer{}_{}", writer, i), i)).unwrap();
+                }
+            })
+        }).collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        assert_eq!(ods.get_all().unwrap().len(), 40);
+
+        // Reads can share the lock, but changes have to wait for it.
+        let shared = lock_file(&file, false, LockMode::Blocking).unwrap();
+        ods.set_lock_mode(LockMode::Try);
+        assert!(ods.find("Writer0_0").unwrap());
+        assert!(matches!(ods.append(IntTag::new("Blocked".to_string(), 0)), Err(OdsError::LockTimeout(_))));
+        drop(shared);
+
+        let exclusive = lock_file(&file, true, LockMode::Blocking).unwrap();
+        ods.set_lock_mode(LockMode::Timeout(Duration::from_millis(50)));
+        assert!(matches!(ods.get::<i32>("Writer0_0"), Err(OdsError::LockTimeout(_))));
+        ods.set_lock_mode(LockMode::Disabled);
+        assert_eq!(*ods.get::<i32>("Writer0_0").unwrap().get_value(), 0);
+        drop(exclusive);
+
+        ods.set_lock_mode(LockMode::Try);
+        ods.append(IntTag::new("Unblocked".to_string(), 1)).unwrap();
+        assert_eq!(ods.get_all().unwrap().len(), 41);
+
+        // Files are locked by default.
+        assert_eq!(LockMode::default(), LockMode::Blocking);
+        fs::remove_file(lock_path(&file)).unwrap();
+        let mut reader = ObjectDataStructure::new_file(file.clone());
+        assert!(reader.find("Unblocked").unwrap());
+        assert!(lock_path(&file).exists());
+        fs::remove_file(lock_path(&file)).unwrap();
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn validate_documents() {
+        use crate::validate::ProblemKind;
+
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        ods.save_value(&OdsValue::Object(vec![
+            ("Name".to_string(), OdsValue::String("Van".to_string())),
+            ("Items".to_string(), OdsValue::Vec(vec![OdsValue::Int(1), OdsValue::Int(2)])),
+            ("Car".to_string(), OdsValue::Object(vec![
+                ("Owner".to_string(), OdsValue::CompressedObject(vec![("Age".to_string(), OdsValue::Int(30))]))
+            ]))
+        ])).unwrap();
+        let report = ods.validate().unwrap();
+        assert!(report.is_valid());
+        assert_eq!(report.tag_count(), 7);
+
+        fn raw(id: u8, name: &str, value: &[u8]) -> Vec<u8> {
+            let mut output = vec![id];
+            output.extend_from_slice(&((2 + name.len() + value.len()) as i32).to_be_bytes());
+            output.extend_from_slice(&(name.len() as i16).to_be_bytes());
+            output.extend_from_slice(name.as_bytes());
+            output.extend_from_slice(value);
+            output
+        }
+        let mut list = raw(2, "", &[0, 0, 0, 1]);
+        list.extend_from_slice(&[1, 0, 0]);
+        let mut object = raw(42, "Odd", &[]);
+        object.extend(raw(9, "List", &list));
+
+        let mut data = raw(2, "Int", &[0, 0, 1]);
+        let string_offset = data.len() as u64;
+        data.extend(raw(1, "Str", &[0xff]));
+        let object_offset = data.len() as u64;
+        data.extend(raw(11, "Obj", &object));
+        let end_offset = data.len() as u64;
+        data.extend_from_slice(&[2, 0, 0, 0, 100, 0, 0]);
+
+        let report = ObjectDataStructure::new_memory(data.clone()).validate().unwrap();
+        let problems: Vec<(u64, String, ProblemKind)> = report.problems().iter()
+            .map(|problem| (problem.offset, problem.key.to_string(), problem.kind.clone()))
+            .collect();
+        assert_eq!(problems, vec![
+            (10, "Int".to_string(), ProblemKind::InvalidValueSize { expected: 4, found: 3 }),
+            (string_offset + 10, "Str".to_string(), ProblemKind::InvalidString),
+            (object_offset + 10, "Obj.Odd".to_string(), ProblemKind::UnknownTag(42)),
+            (object_offset + 20 + 11 + 11, "Obj.List".to_string(), ProblemKind::TrailingBytes(3)),
+            (end_offset, String::new(), ProblemKind::InvalidSize { size: 100, available: 2 })
+        ]);
+        assert_eq!(report.tag_count(), 6);
+        assert!(report.to_string().starts_with("5 problems found in 6 tags:"));
+
+        // A file is checked without loading it, so a corrupt file can still be checked.
+        let file = test_file("validate_documents");
+        fs::write(&file, &data).unwrap();
+        assert_eq!(ObjectDataStructure::new_file(file.clone()).validate().unwrap(), report);
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn salvage_documents() {
+        use crate::salvage::{salvage, SkippedRange};
+
+        let value = OdsValue::Object(vec![
+            ("Name".to_string(), OdsValue::String("Van".to_string())),
+            ("Items".to_string(), OdsValue::Vec(vec![OdsValue::Int(1), OdsValue::Int(2)])),
+            ("Car".to_string(), OdsValue::Object(vec![
+                ("Model".to_string(), OdsValue::String("Van".to_string())),
+                ("Owner".to_string(), OdsValue::Object(vec![("Age".to_string(), OdsValue::Int(30))]))
+            ]))
+        ]);
+        let data = value.to_bytes().unwrap();
+        let report = salvage(&data);
+        assert!(report.is_intact());
+        assert_eq!(report.value(), &value);
+
+        // The file ends in the middle of `Car.Owner.Age`, but the rest of `Car` is kept.
+        let truncated = &data[..data.len() - 2];
+        let report = salvage(truncated);
+        let length = truncated.len() as u64;
+        assert_eq!(report.value(), &OdsValue::Object(vec![
+            ("Name".to_string(), OdsValue::String("Van".to_string())),
+            ("Items".to_string(), OdsValue::Vec(vec![OdsValue::Int(1), OdsValue::Int(2)])),
+            ("Car".to_string(), OdsValue::Object(vec![
+                ("Model".to_string(), OdsValue::String("Van".to_string())),
+                ("Owner".to_string(), OdsValue::Object(Vec::new()))
+            ]))
+        ]));
+        let ranges: Vec<_> = report.skipped().iter().map(|skipped| skipped.range.clone()).collect();
+        assert_eq!(ranges, vec![length - 12..length + 2, length..length + 2, length..length + 2]);
+
+        // A tag with an unknown id is skipped, and a broken size is skipped up to the next valid tag.
+        let name_size = 7 + 4 + 3;
+        let mut corrupt = data.clone();
+        corrupt[0] = 42;
+        corrupt[name_size + 4] = 1;
+        let report = salvage(&corrupt);
+        assert_eq!(report.skipped(), &[
+            SkippedRange { range: 0..name_size as u64, compressed: None },
+            SkippedRange { range: name_size as u64..(name_size + 7 + 5) as u64, compressed: None }
+        ]);
+        // The header of `Items` is skipped, so its elements are found as tags of their own.
+        assert_eq!(report.tags().unwrap().len(), 3);
+        assert!(report.value().get("Car.Owner.Age").is_some());
+
+        // A damaged file can be repaired, and the damaged file is kept as a backup.
+        let file = test_file("salvage_documents");
+        let backup = crate::io::files::backup_path(&file);
+        fs::write(&file, truncated).unwrap();
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        assert!(ods.get_all().is_err());
+        assert!(!ods.salvage(true).unwrap().is_intact());
+        assert_eq!(ods.get_all().unwrap().len(), 3);
+        assert!(ods.validate().unwrap().is_valid());
+        assert_eq!(fs::read(&backup).unwrap(), truncated);
+        assert!(ods.salvage(true).unwrap().is_intact());
+
+        fs::remove_file(file).unwrap();
+        fs::remove_file(backup).unwrap();
+    }
+
+    #[test]
+    fn parse_limits() {
+        use crate::limits::Limit;
+        use crate::io::streams::StandardIO;
+        use crate::validate::ProblemKind;
+
+        fn exceeded<T: std::fmt::Debug>(result: Result<T, OdsError>, expected: Limit) -> bool {
+            matches!(result, Err(OdsError::LimitExceeded { limit, .. }) if limit == expected)
+        }
+
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        ods.set("a.b.c.d", Some(IntTag::new(String::new(), 1))).unwrap();
+        ods.append(StringTag::new("LongName".to_string(), "x".repeat(100))).unwrap();
+        ods.append(IntTag::new("e".to_string(), 2)).unwrap();
+
+        ods.set_parse_limits(ParseLimits { max_total_size: 50, ..ParseLimits::default() });
+        assert!(exceeded(ods.get_all(), Limit::TotalSize));
+        ods.set_parse_limits(ParseLimits { max_tag_size: 64, ..ParseLimits::default() });
+        assert!(exceeded(ods.get::<String>("LongName"), Limit::TagSize));
+        ods.set_parse_limits(ParseLimits { max_name_length: 4, ..ParseLimits::default() });
+        assert!(exceeded(ods.get::<i32>("e"), Limit::NameLength));
+        ods.set_parse_limits(ParseLimits { max_elements: 2, ..ParseLimits::default() });
+        assert_eq!(*ods.get::<i32>("a.b.c.d").unwrap().get_value(), 1);
+        assert!(exceeded(ods.get_all(), Limit::Elements));
+
+        // Containers keep the limits of the data they were read from.
+        ods.set_parse_limits(ParseLimits { max_depth: 2, ..ParseLimits::default() });
+        assert!(ods.find("a.b.c").unwrap());
+        assert!(exceeded(ods.find("a.b.c.d"), Limit::Depth));
+        assert!(exceeded(ods.replace_data("a.b.c.d", IntTag::new("d".to_string(), 2)), Limit::Depth));
+        assert!(exceeded(ods.load_value(), Limit::Depth));
+        let mut object = ods.get::<Object>("a").unwrap();
+        assert!(exceeded(object.get::<i32>("b.c.d"), Limit::Depth));
+
+        // A size that does not fit is rejected before anything is allocated.
+        let mut read_stream = Stream::new_with_data(vec![0; 10]);
+        read_stream.set_position(0);
+        assert!(matches!(ObjectTag::new(String::new(), Object::new()).create_from_data(read_stream.clone(), i32::MAX), Err(OdsError::Truncated)));
+        read_stream.set_limits(ParseLimits { max_tag_size: 4, ..ParseLimits::default() });
+        assert!(exceeded(ObjectTag::new(String::new(), Object::new()).create_from_data(read_stream, 8), Limit::TagSize));
+
+        // Compressed objects cannot be decompressed past the total size.
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        ods.append(CompressedObjectTag::from_vec("Zip".to_string(), anytag_vec!(StringTag::new("Zeros".to_string(), "0".repeat(10_000)))).unwrap()).unwrap();
+        assert!(ods.get_bytes().len() < 1000);
+        ods.set_parse_limits(ParseLimits { max_total_size: 1000, ..ParseLimits::default() });
+        assert!(exceeded(ods.get::<String>("Zip.Zeros"), Limit::TotalSize));
+
+        // Deeply nested data is rejected instead of overflowing the stack.
+        let mut data = Vec::new();
+        for _ in 0..1000 {
+            let mut tag = vec![11];
+            tag.extend_from_slice(&(data.len() as i32 + 2).to_be_bytes());
+            tag.extend_from_slice(&[0, 0]);
+            tag.extend(data);
+            data = tag;
+        }
+        assert!(exceeded(OdsValue::from_bytes(&data), Limit::Depth));
+        let report = ObjectDataStructure::new_memory(data.clone()).validate().unwrap();
+        assert_eq!(report.problems().len(), 1);
+        assert_eq!(report.problems()[0].kind, ProblemKind::TooDeep);
+        assert!(!crate::salvage::salvage(&data).is_intact());
+    }
+
+    #[test]
+    fn stream_documents() {
+        use std::io::{Cursor, Read, Seek, SeekFrom};
+
+        /// A reader that counts how many bytes are read from it, and how many times.
+        struct CountingReader {
+            inner: Cursor<Vec<u8>>,
+            read: usize,
+            calls: usize
+        }
+
+        impl Read for CountingReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let read = self.inner.read(buf)?;
+                self.read += read;
+                self.calls += 1;
+                Ok(read)
+            }
+        }
+
+        impl Seek for CountingReader {
+            fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+                self.inner.seek(pos)
+            }
+        }
+
+        let tags = anytag_vec!(
+            StringTag::new("Big".to_string(), "x".repeat(100_000)),
+            ObjectTag::from_vec("Car".to_string(), anytag_vec!(
+                StringTag::new("Model".to_string(), "Van".to_string()),
+                ObjectTag::from_vec("Owner".to_string(), anytag_vec!(IntTag::new("Age".to_string(), 30))).unwrap()
+            )).unwrap(),
+            CompressedObjectTag::from_vec("Zip".to_string(), anytag_vec!(IntTag::new("Inner".to_string(), 5))).unwrap()
+        );
+        let mut memory = ObjectDataStructure::new_memory(Vec::new());
+        memory.save(tags).unwrap();
+
+        let mut ods = ObjectDataStructure::new_stream(Cursor::new(Vec::new())).unwrap();
+        ods.save_value(&memory.load_value().unwrap()).unwrap();
+        assert_eq!(ods.get_ref().get_ref(), &memory.get_bytes().to_vec());
+        assert_eq!(*ods.get::<i32>("Car.Owner.Age").unwrap().get_value(), 30);
+        assert_eq!(ods.get::<String>("Car[0]").unwrap().get_value(), "Van");
+        assert_eq!(*ods.get::<i32>("Zip.Inner").unwrap().get_value(), 5);
+        assert!(ods.find("Car.Owner").unwrap());
+        assert!(!ods.find("Car.Model.Name").unwrap());
+        assert!(matches!(ods.get::<i32>("Car.Wheels"), Err(OdsError::KeyNotFound(_))));
+
+        // Changes give the same document as in memory, even when the document gets shorter.
+        ods.delete("Big").unwrap();
+        memory.delete("Big").unwrap();
+        ods.replace_data("Car.Owner.Age", IntTag::new("Age".to_string(), 31)).unwrap();
+        memory.replace_data("Car.Owner.Age", IntTag::new("Age".to_string(), 31)).unwrap();
+        ods.set("Zip.Count", Some(IntTag::new(String::new(), 1))).unwrap();
+        memory.set("Zip.Count", Some(IntTag::new(String::new(), 1))).unwrap();
+        ods.append(IntTag::new("Last".to_string(), 2)).unwrap();
+        memory.append(IntTag::new("Last".to_string(), 2)).unwrap();
+        assert_eq!(ods.into_inner().into_inner(), memory.get_bytes());
+
+        // Only the headers on the path of the key and the found tag are read. (With the bytes that are read ahead
+        // of them, but not the value of the Big Tag.)
+        let mut writer = ObjectDataStructure::new_memory(Vec::new());
+        writer.append(StringTag::new("Big".to_string(), "x".repeat(100_000))).unwrap();
+        writer.append(IntTag::new("Small".to_string(), 7)).unwrap();
+        let bytes = writer.into_bytes();
+        let mut ods = ObjectDataStructure::new_read_only_stream(CountingReader {
+            inner: Cursor::new(bytes.clone()),
+            read: 0,
+            calls: 0
+        }).unwrap();
+        assert_eq!(*ods.get::<i32>("Small").unwrap().get_value(), 7);
+        assert!(ods.get_ref().0.read < 2 * 8 * 1024);
+
+        // The headers of small tags are read ahead, instead of reading every header from the reader.
+        let mut writer = ObjectDataStructure::new_memory(Vec::new());
+        for i in 0..100 {
+            writer.append(IntTag::new(format!("Int{}", i), i)).unwrap();
+        }
+        let mut small = ObjectDataStructure::new_read_only_stream(CountingReader {
+            inner: Cursor::new(writer.into_bytes()),
+            read: 0,
+            calls: 0
+        }).unwrap();
+        assert_eq!(*small.get::<i32>("Int99").unwrap().get_value(), 99);
+        assert!(small.get_ref().0.calls < 5);
+
+        // A read only stream cannot be changed.
+        match ods.append(IntTag::new("New".to_string(), 1)) {
+            Err(OdsError::Io(err)) => assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied),
+            other => panic!("expected a permission error, found {:?}", other)
+        }
+        assert_eq!(ods.into_inner().into_inner().inner.into_inner(), bytes);
+
+        // Files can be used as streams too.
+        let file = test_file("stream_documents");
+        fs::write(&file, &bytes).unwrap();
+        let stream = fs::OpenOptions::new().read(true).write(true).open(&file).unwrap();
+        let mut ods = ObjectDataStructure::new_stream(stream).unwrap();
+        assert!(ods.delete("Big").unwrap());
+        drop(ods);
+        assert_eq!(ObjectDataStructure::new_file(file.clone()).get_all().unwrap().len(), 1);
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn lazy_file_reads() {
+        let file = test_file("lazy_file_reads");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        assert!(matches!(ods.get::<i32>("Missing"), Err(OdsError::KeyNotFound(_))));
+        for i in 0..100 {
+            ods.append(StringTag::new(format!("Filler{}", i), "x".repeat(1000))).unwrap();
+        }
+        ods.append(ObjectTag::from_vec("World".to_string(), anytag_vec!(
+            VecTag::from_vec("Chunks".to_string(), &mut anytag_vec!(IntTag::new(String::new(), 1), IntTag::new(String::new(), 2))).unwrap(),
+            CompressedObjectTag::from_vec("Spawn".to_string(), anytag_vec!(IntTag::new("X".to_string(), 10))).unwrap()
+        )).unwrap()).unwrap();
+
+        assert_eq!(ods.get::<String>("Filler99").unwrap().get_value().len(), 1000);
+        assert_eq!(*ods.get::<i32>("World.Chunks[1]").unwrap().get_value(), 2);
+        assert_eq!(*ods.get::<i32>("World.Spawn.X").unwrap().get_value(), 10);
+        assert!(ods.get::<Object>("World").unwrap().has_tag("Spawn.X").unwrap());
+        assert!(ods.find("World.Spawn.X").unwrap());
+        assert!(!ods.find("World.Spawn.Y").unwrap());
+        assert!(!ods.find("Filler0.Inner").unwrap());
+        assert!(matches!(ods.get::<i32>("Filler0"), Err(OdsError::TypeMismatch { expected: 2, found: 1 })));
+
+        // Changes in a journal are read from memory.
+        let mut journaled = ObjectDataStructure::new_journaled_file(file.clone()).unwrap();
+        journaled.replace_data("World.Spawn.X", IntTag::new("X".to_string(), 11)).unwrap();
+        assert_eq!(*ods.get::<i32>("World.Spawn.X").unwrap().get_value(), 11);
+        journaled.checkpoint().unwrap();
+        assert_eq!(*ods.get::<i32>("World.Spawn.X").unwrap().get_value(), 11);
+
+        // The limits apply to lazy reads as well.
+        ods.set_parse_limits(ParseLimits { max_depth: 1, ..ParseLimits::default() });
+        assert!(matches!(ods.get::<i32>("World.Spawn.X"), Err(OdsError::LimitExceeded { .. })));
+        ods.set_parse_limits(ParseLimits { max_total_size: 1000, ..ParseLimits::default() });
+        assert!(matches!(ods.find("Filler0"), Err(OdsError::LimitExceeded { .. })));
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn mapped_files() {
+        let file = test_file("mapped_files");
+        // SAFETY: Nothing in these tests truncates or writes over a file while it is mapped.
+        assert!(matches!(unsafe { ObjectDataStructure::new_mapped_file(file.clone()) }, Err(OdsError::Io(_))));
+
+        let mut writer = ObjectDataStructure::new_file(file.clone());
+        writer.append(StringTag::new("Name".to_string(), "Map".to_string())).unwrap();
+        writer.append(ObjectTag::from_vec("Car".to_string(), anytag_vec!(
+            IntTag::new("Year".to_string(), 2019),
+            CompressedObjectTag::from_vec("Engine".to_string(), anytag_vec!(IntTag::new("Power".to_string(), 300))).unwrap()
+        )).unwrap()).unwrap();
+
+        let mut ods = unsafe { ObjectDataStructure::new_mapped_file(file.clone()) }.unwrap();
+        assert_eq!(ods.get_bytes(), fs::read(&file).unwrap().as_slice());
+        assert_eq!(ods.get::<String>("Name").unwrap().get_value(), "Map");
+        assert_eq!(*ods.get::<i32>("Car[0]").unwrap().get_value(), 2019);
+        assert_eq!(*ods.get::<i32>("Car.Engine.Power").unwrap().get_value(), 300);
+        assert!(ods.find("Car.Engine").unwrap());
+        assert!(!ods.find("Car.Wheels").unwrap());
+        assert_eq!(ods.get_all().unwrap().len(), 2);
+        assert_eq!(ods.load_value().unwrap(), writer.load_value().unwrap());
+        assert!(ods.validate().unwrap().is_valid());
+        match ods.append(IntTag::new("New".to_string(), 1)) {
+            Err(OdsError::Io(err)) => assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied),
+            other => panic!("expected a permission error, found {:?}", other)
+        }
+
+        // Changes in the journal are seen right away, a replaced file only after remap.
+        let mut journaled = ObjectDataStructure::new_journaled_file(file.clone()).unwrap();
+        journaled.replace_data("Car.Year", IntTag::new("Year".to_string(), 2020)).unwrap();
+        assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2020);
+        assert_eq!(ods.get_all().unwrap().len(), 2);
+        journaled.checkpoint().unwrap();
+        writer.append(IntTag::new("Count".to_string(), 1)).unwrap();
+        assert!(!ods.find("Count").unwrap());
+        ods.remap().unwrap();
+        assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2020);
+        assert!(ods.find("Count").unwrap());
+        drop(ods);
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn tag_refs() {
+        use crate::tags::tag_ref::TagRef;
+
+        let mut ods = ObjectDataStructure::new_memory(Vec::new());
+        ods.append(StringTag::new("Name".to_string(), "Refs".to_string())).unwrap();
+        ods.append(ObjectTag::from_vec("Car".to_string(), anytag_vec!(
+            IntTag::new("Year".to_string(), 2019),
+            VecTag::from_vec("Wheels".to_string(), &mut anytag_vec!(
+                FloatTag::new("Size".to_string(), 17.5),
+                DoubleTag::new("Size".to_string(), 18.0)
+            )).unwrap(),
+            CompressedObjectTag::from_vec("Engine".to_string(), anytag_vec!(IntTag::new("Power".to_string(), 300))).unwrap()
+        )).unwrap()).unwrap();
+
+        let name = ods.get_tag_ref("Name").unwrap();
+        assert_eq!(name.id(), 1);
+        assert_eq!(name.as_str().unwrap(), "Refs");
+        assert!(matches!(name.as_i32(), Err(OdsError::TypeMismatch { expected: 2, found: 1 })));
+        assert!(matches!(name.children(), Err(OdsError::TypeMismatch { .. })));
+        assert!(matches!(ods.get_tag_ref("Missing"), Err(OdsError::KeyNotFound(_))));
+
+        let data = ods.get_bytes();
+        assert_eq!(TagRef::iter(data).get("Car.Year").unwrap().unwrap().as_i32().unwrap(), 2019);
+        assert_eq!(TagRef::iter(data).get("Car.Wheels[1]").unwrap().unwrap().as_f64().unwrap(), 18.0);
+        assert!(TagRef::iter(data).get("Car.Doors").unwrap().is_none());
+        assert!(matches!(TagRef::iter(data).get("Car.Engine.Power"), Err(OdsError::TypeMismatch { .. })));
+
+        let car = ods.get_tag_ref("Car").unwrap();
+        let names: Vec<&str> = car.children().unwrap().map(|tag| tag.unwrap().name()).collect();
+        assert_eq!(names, vec!["Year", "Wheels", "Engine"]);
+        assert_eq!(car.get("Wheels[0]").unwrap().unwrap().as_f32().unwrap(), 17.5);
+
+        let engine = car.get("Engine").unwrap().unwrap().decompress().unwrap();
+        assert_eq!(TagRef::iter(&engine).get("Power").unwrap().unwrap().as_i32().unwrap(), 300);
+
+        assert_eq!(car.to_value().unwrap(), OdsValue::from_bytes(data).unwrap().get("Car").unwrap().clone());
+        assert_eq!(*car.get("Year").unwrap().unwrap().to_tag::<i32>().unwrap().get_value(), 2019);
+        assert!(matches!(car.to_tag::<i32>(), Err(OdsError::TypeMismatch { .. })));
+
+        // A truncated tag is returned as an error and ends the iterator.
+        let mut tags = TagRef::iter(&data[..data.len() - 1]);
+        assert!(tags.next().unwrap().is_ok());
+        assert!(matches!(tags.next(), Some(Err(OdsError::Truncated))));
+        assert!(tags.next().is_none());
+    }
+
+    #[test]
+    fn indexed_lookups() {
+        use crate::internal::index::IndexMode;
+
+        let tags = || anytag_vec!(
+            StringTag::new("Name".to_string(), "First".to_string()),
+            ObjectTag::from_vec("Car".to_string(), anytag_vec!(
+                IntTag::new("Year".to_string(), 2019),
+                CompressedObjectTag::from_vec("Engine".to_string(), anytag_vec!(IntTag::new("Power".to_string(), 300))).unwrap()
+            )).unwrap(),
+            StringTag::new("Name".to_string(), "Second".to_string())
+        );
+        for mode in [IndexMode::TopLevel, IndexMode::Nested] {
+            let mut ods = ObjectDataStructure::new_memory(Vec::new());
+            ods.save(tags()).unwrap();
+            ods.set_index_mode(mode);
+            assert_eq!(ods.get::<String>("Name").unwrap().get_value(), "First");
+            assert_eq!(ods.get::<String>("[2]").unwrap().get_value(), "Second");
+            assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2019);
+            assert_eq!(*ods.get::<i32>("Car[0]").unwrap().get_value(), 2019);
+            assert_eq!(*ods.get::<i32>("Car.Engine.Power").unwrap().get_value(), 300);
+            assert!(matches!(ods.get::<i32>("Car.Wheels"), Err(OdsError::KeyNotFound(_))));
+            assert!(matches!(ods.get::<i32>("Name.Length"), Err(OdsError::TypeMismatch { .. })));
+            assert!(!ods.find("Name.Length").unwrap());
+            assert!(!ods.find("[3]").unwrap());
+
+            // Appended tags are added to the index, other changes are seen after the index is built again.
+            ods.append(IntTag::new("Count".to_string(), 1)).unwrap();
+            assert_eq!(*ods.get::<i32>("Count").unwrap().get_value(), 1);
+            assert_eq!(*ods.get::<i32>("[3]").unwrap().get_value(), 1);
+            ods.delete("Name").unwrap();
+            assert_eq!(ods.get::<String>("Name").unwrap().get_value(), "Second");
+            ods.replace_data("Car.Year", IntTag::new("Year".to_string(), 2020)).unwrap();
+            assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2020);
+            assert_eq!(*ods.get::<i32>("Count").unwrap().get_value(), 1);
+        }
+
+        // A file is indexed again when another ObjectDataStructure changes it.
+        let file = test_file("indexed_lookups");
+        let mut writer = ObjectDataStructure::new_file(file.clone());
+        writer.save(tags()).unwrap();
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.set_index_mode(IndexMode::Nested);
+        assert_eq!(*ods.get::<i32>("Car.Engine.Power").unwrap().get_value(), 300);
+        assert!(!ods.find("Count").unwrap());
+        writer.delete("Name").unwrap();
+        writer.append(IntTag::new("Count".to_string(), 1)).unwrap();
+        assert_eq!(ods.get::<String>("Name").unwrap().get_value(), "Second");
+        assert_eq!(*ods.get::<i32>("Count").unwrap().get_value(), 1);
+        assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2019);
+
+        // SAFETY: The file is only replaced and appended to while it is mapped.
+        let mut mapped = unsafe { ObjectDataStructure::new_mapped_file(file.clone()) }.unwrap();
+        mapped.set_index_mode(IndexMode::TopLevel);
+        assert_eq!(*mapped.get::<i32>("Count").unwrap().get_value(), 1);
+        assert!(mapped.find("Car.Engine.Power").unwrap());
+        drop(mapped);
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn footer_index() {
+        use crate::internal::footer::FOOTER_NAME;
+
+        let file = test_file("footer_index");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.set_footer_index(true);
+        ods.save(anytag_vec!(
+            StringTag::new("Name".to_string(), "First".to_string()),
+            ObjectTag::from_vec("Car".to_string(), anytag_vec!(IntTag::new("Year".to_string(), 2019))).unwrap(),
+            StringTag::new("Name".to_string(), "Second".to_string())
+        )).unwrap();
+
+        // Readers that do not know about the index see it as a normal tag at the end of the file.
+        let mut legacy = ObjectDataStructure::new_memory(fs::read(&file).unwrap());
+        let legacy_tags = legacy.get_all().unwrap();
+        assert_eq!(legacy_tags.len(), 4);
+        assert_eq!(legacy_tags[3].get_name(), FOOTER_NAME);
+
+        assert_eq!(ods.get_all().unwrap().len(), 3);
+        assert_eq!(ods.get::<String>("Name").unwrap().get_value(), "First");
+        assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2019);
+        assert!(matches!(ods.get::<i32>("[3]"), Err(OdsError::KeyNotFound(_))));
+        assert!(!ods.find(FOOTER_NAME).unwrap());
+        assert!(!ods.find("Wheels").unwrap());
+
+        // The index is kept in sync by every change.
+        ods.append(IntTag::new("Count".to_string(), 1)).unwrap();
+        ods.delete("Name").unwrap();
+        ods.replace_data("Car.Year", IntTag::new("Year".to_string(), 2020)).unwrap();
+        let mut reader = ObjectDataStructure::new_file(file.clone());
+        assert_eq!(reader.get::<String>("Name").unwrap().get_value(), "Second");
+        assert_eq!(*reader.get::<i32>("Count").unwrap().get_value(), 1);
+        assert_eq!(*reader.get::<i32>("Car.Year").unwrap().get_value(), 2020);
+        assert_eq!(ObjectDataStructure::new_memory(fs::read(&file).unwrap()).get_all().unwrap().len(), 4);
+
+        // A tag is found without reading the tags before it.
+        let mut data = fs::read(&file).unwrap();
+        data[1..5].copy_from_slice(&i32::MAX.to_be_bytes());
+        fs::write(&file, &data).unwrap();
+        assert_eq!(*reader.get::<i32>("Count").unwrap().get_value(), 1);
+        assert!(matches!(reader.get::<i32>("[2]"), Err(OdsError::Truncated)));
+
+        // SAFETY: The file is not changed while it is mapped.
+        let mut mapped = unsafe { ObjectDataStructure::new_mapped_file(file.clone()) }.unwrap();
+        assert_eq!(*mapped.get::<i32>("Count").unwrap().get_value(), 1);
+        assert!(!mapped.find("Wheels").unwrap());
+        drop(mapped);
+
+        // A writer without the setting removes the index.
+        ods.save(anytag_vec!(IntTag::new("Count".to_string(), 2))).unwrap();
+        reader.append(IntTag::new("Size".to_string(), 3)).unwrap();
+        assert!(ObjectDataStructure::new_memory(fs::read(&file).unwrap()).get_all().unwrap().iter().all(|tag| tag.get_name() != FOOTER_NAME));
+        assert_eq!(*reader.get::<i32>("Size").unwrap().get_value(), 3);
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn append_in_place() {
+        use crate::internal::index::IndexMode;
+
+        let file = test_file("append_in_place");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.set_index_mode(IndexMode::TopLevel);
+        ods.append(StringTag::new("Name".to_string(), "Append".to_string())).unwrap();
+        let before = fs::read(&file).unwrap();
+        assert_eq!(ods.get::<String>("Name").unwrap().get_value(), "Append");
+
+        // Only the new tags are written, after the bytes that are already in the file.
+        // SAFETY: The appends do not fail, so the file is never cut back while it is mapped.
+        let mut mapped = unsafe { ObjectDataStructure::new_mapped_file(file.clone()) }.unwrap();
+        ods.set_sync_in_place(false);
+        ods.append_all(anytag_vec!(IntTag::new("Count".to_string(), 1), IntTag::new("Size".to_string(), 2))).unwrap();
+        ods.set_sync_in_place(true);
+        ods.append(IntTag::new("Total".to_string(), 3)).unwrap();
+        let after = fs::read(&file).unwrap();
+        assert_eq!(&after[..before.len()], before.as_slice());
+        assert_eq!(mapped.get_bytes(), before.as_slice());
+        assert!(!mapped.find("Count").unwrap());
+        mapped.remap().unwrap();
+        assert_eq!(*mapped.get::<i32>("Total").unwrap().get_value(), 3);
+        drop(mapped);
+
+        // The in memory index is kept, and sees the appended tags.
+        assert_eq!(*ods.get::<i32>("Size").unwrap().get_value(), 2);
+        assert_eq!(*ods.get::<i32>("[3]").unwrap().get_value(), 3);
+        assert_eq!(ods.get_all().unwrap().len(), 4);
+
+        // The whole file is written when a backup is kept.
+        ods.set_keep_backup(true);
+        ods.append(IntTag::new("Last".to_string(), 4)).unwrap();
+        let backup = crate::io::files::backup_path(&file);
+        assert_eq!(fs::read(&backup).unwrap(), after);
+        assert_eq!(*ods.get::<i32>("Last").unwrap().get_value(), 4);
+        fs::remove_file(backup).unwrap();
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn patch_in_place() {
+        use crate::internal::index::IndexMode;
+        use crate::tags::general::LongTag;
+        use std::io::{Read, Seek, SeekFrom};
+
+        let file = test_file("patch_in_place");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.append_all(anytag_vec!(
+            ObjectTag::from_vec("Car".to_string(), anytag_vec!(IntTag::new("Year".to_string(), 2019), StringTag::new("Model".to_string(), "Van".to_string()))).unwrap(),
+            LongTag::new("Seed".to_string(), 1),
+            CompressedObjectTag::from_vec("Compressed".to_string(), anytag_vec!(IntTag::new("Int".to_string(), 1))).unwrap()
+        )).unwrap();
+        let before = fs::read(&file).unwrap();
+
+        // A tag with the same size and name is written over the old one, so a handle to the file sees it.
+        let mut opened = fs::File::open(&file).unwrap();
+        let read_opened = |opened: &mut fs::File| {
+            let mut data = Vec::new();
+            opened.seek(SeekFrom::Start(0)).unwrap();
+            opened.read_to_end(&mut data).unwrap();
+            data
+        };
+        assert!(ods.replace_data("Car.Year", IntTag::new("Year".to_string(), 2020)).unwrap());
+        ods.set("Seed", Some(LongTag::new("ignored".to_string(), 42))).unwrap();
+        let after = fs::read(&file).unwrap();
+        assert_eq!(after.len(), before.len());
+        assert_eq!(before.iter().zip(&after).filter(|(old, new)| old != new).count(), 2);
+        assert_eq!(read_opened(&mut opened), after);
+        assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2020);
+        assert_eq!(*ods.get::<i64>("Seed").unwrap().get_value(), 42);
+
+        // Tags with another size and tags inside of a Compressed Object Tag are written by rewriting the file.
+        assert!(ods.replace_data("Car.Model", StringTag::new("Model".to_string(), "Truck".to_string())).unwrap());
+        assert!(ods.replace_data("Compressed.Int", IntTag::new("Int".to_string(), 2)).unwrap());
+        assert_eq!(read_opened(&mut opened), after);
+        assert_eq!(*ods.get::<i32>("Compressed.Int").unwrap().get_value(), 2);
+        assert_eq!(ods.get::<String>("Car.Model").unwrap().get_value(), "Truck");
+        drop(opened);
+
+        // The indexes stay correct after a patch.
+        ods.set_index_mode(IndexMode::Nested);
+        ods.set_footer_index(true);
+        ods.replace_data("Seed", LongTag::new("Seed".to_string(), 7)).unwrap();
+        assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2020);
+        let len = fs::metadata(&file).unwrap().len();
+        ods.replace_data("Car.Year", IntTag::new("Year".to_string(), 2021)).unwrap();
+        ods.set("Seed", Some(LongTag::new("Seed".to_string(), 8))).unwrap();
+        assert_eq!(fs::metadata(&file).unwrap().len(), len);
+        assert_eq!(*ods.get::<i32>("Car.Year").unwrap().get_value(), 2021);
+        assert_eq!(*ods.get::<i64>("Seed").unwrap().get_value(), 8);
+        let mut reader = ObjectDataStructure::new_file(file.clone());
+        assert_eq!(*reader.get::<i64>("Seed").unwrap().get_value(), 8);
+        assert!(reader.validate().unwrap().is_valid());
+
+        // The memory backend patches its bytes in place too.
+        let mut memory = ObjectDataStructure::new_memory(fs::read(&file).unwrap());
+        memory.replace_data("Car.Year", IntTag::new("Year".to_string(), 2022)).unwrap();
+        assert_eq!(memory.get_bytes().len(), len as usize);
+        assert_eq!(*memory.get::<i32>("Car.Year").unwrap().get_value(), 2022);
+
+        // A journaled file stores the change in its journal instead, even when it does not have a journal yet.
+        let journal = crate::internal::journal::journal_path(&file);
+        ods.set_footer_index(false);
+        ods.replace_data("Seed", LongTag::new("Seed".to_string(), 9)).unwrap();
+        let before = fs::read(&file).unwrap();
+        let mut journaled = ObjectDataStructure::new_journaled_file(file.clone()).unwrap();
+        assert!(journaled.replace_data("Car.Year", IntTag::new("Year".to_string(), 2023)).unwrap());
+        assert!(journaled.replace_data("Car.Year", IntTag::new("Year".to_string(), 2024)).unwrap());
+        assert_eq!(fs::read(&file).unwrap(), before);
+        assert!(journal.exists());
+        assert_eq!(*reader.get::<i32>("Car.Year").unwrap().get_value(), 2024);
+        journaled.checkpoint().unwrap();
+        assert!(!journal.exists());
+        assert_eq!(*reader.get::<i32>("Car.Year").unwrap().get_value(), 2024);
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn get_many() {
+        use crate::tags::value::OdsValue;
 
-        ods.append(tag![StringTag, "BOB", "Test".to_string()]);
+        let tags = || anytag_vec!(
+            ObjectTag::from_vec("Car".to_string(), anytag_vec!(
+                IntTag::new("Year".to_string(), 2019),
+                ObjectTag::from_vec("Owner".to_string(), anytag_vec!(StringTag::new("Name".to_string(), "Jeff".to_string()))).unwrap()
+            )).unwrap(),
+            IntTag::new("Count".to_string(), 1),
+            IntTag::new("Count".to_string(), 2),
+            CompressedObjectTag::from_vec("Compressed".to_string(), anytag_vec!(DoubleTag::new("Double".to_string(), 2.5))).unwrap()
+        );
+        let file = test_file("get_many");
+        let mut ods = ObjectDataStructure::new_file(file.clone());
+        ods.save(tags()).unwrap();
 
-        // ods.delete("Test_Tag".to_string());
+        let keys = ["Car.Year", "Car.Owner.Name", "Car[1]", "Count", "[2]", "Compressed.Double", "Car.Missing", "Count.x", "a..b"];
+        let found = ods.get_many(keys).unwrap();
+        assert_eq!(found.len(), keys.len());
+        assert_eq!(*found["Car.Year"].as_ref().unwrap().downcast_any_tag::<i32>().unwrap().get_value(), 2019);
+        assert_eq!(found["Car.Owner.Name"].as_ref().unwrap().downcast_any_tag::<String>().unwrap().get_value(), "Jeff");
+        assert_eq!(found["Car[1]"].as_ref().unwrap().get_name(), "Owner");
+        assert_eq!(*found["Count"].as_ref().unwrap().downcast_any_tag::<i32>().unwrap().get_value(), 1);
+        assert_eq!(*found["[2]"].as_ref().unwrap().downcast_any_tag::<i32>().unwrap().get_value(), 2);
+        assert_eq!(*found["Compressed.Double"].as_ref().unwrap().downcast_any_tag::<f64>().unwrap().get_value(), 2.5);
+        assert!(matches!(found["Car.Missing"], Err(OdsError::KeyNotFound(_))));
+        assert!(matches!(found["Count.x"], Err(OdsError::TypeMismatch { expected: 11, found: 2 })));
+        assert!(matches!(found["a..b"], Err(OdsError::InvalidKey(_))));
 
-        ods.replace_data("My_Cool_Tag".to_string(), StringTag::new("My_Cool_Tag".to_string(), "My Other Value!".to_string()));
+        // The typed variants convert every tag that is found.
+        let years = ods.get_many_as::<i32, _>(["Car.Year", "Car.Owner.Name"]).unwrap();
+        assert_eq!(*years["Car.Year"].as_ref().unwrap().get_value(), 2019);
+        assert!(matches!(years["Car.Owner.Name"], Err(OdsError::TypeMismatch { .. })));
+        let values = ods.get_many_values(["Car.Owner", "Compressed.Double"]).unwrap();
+        assert_eq!(values["Car.Owner"].as_ref().unwrap().get("Name").and_then(|name| name.as_str()), Some("Jeff"));
+        assert_eq!(values["Compressed.Double"].as_ref().unwrap(), &OdsValue::Double(2.5));
 
+        // Every backend finds the same tags.
+        let mut memory = ObjectDataStructure::new_memory(fs::read(&file).unwrap());
+        let mut stream = ObjectDataStructure::new_read_only_stream(std::io::Cursor::new(fs::read(&file).unwrap())).unwrap();
+        for other in [memory.get_many_values(keys).unwrap(), stream.get_many_values(keys).unwrap()] {
+            for key in keys {
+                assert_eq!(other[key].as_ref().ok(), ods.get_many_values([key]).unwrap()[key].as_ref().ok());
+            }
+        }
+        fs::remove_file(file).unwrap();
     }
-}
+}
\ No newline at end of file
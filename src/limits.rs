@@ -0,0 +1,104 @@
+use std::fmt;
+
+use crate::error::{OdsError, Result};
+
+/// Limits on the data that is read, so untrusted data cannot make ODS allocate too much memory or recurse too deep.
+///
+/// The limits are carried by the Stream that is read from, so every tag that is read from a Stream
+/// (and every container inside of it) is checked against the same limits.
+/// When a limit is exceeded `OdsError::LimitExceeded` is returned.
+///
+/// # Example
+/// ```rust
+/// use object_data_structure::limits::ParseLimits;
+///
+/// let limits = ParseLimits {
+///     max_total_size: 1024 * 1024,
+///     max_depth: 16,
+///     ..ParseLimits::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// The maximum size of a document in bytes. This includes the decompressed size of Compressed Object Tags.
+    pub max_total_size: usize,
+    /// The maximum data size of a single tag.
+    pub max_tag_size: usize,
+    /// The maximum number of containers (Ex: Object Tags) that can be nested inside of each other.
+    pub max_depth: usize,
+    /// The maximum length of the name of a tag.
+    pub max_name_length: usize,
+    /// The maximum number of tags in a document or in a single container.
+    pub max_elements: usize
+}
+
+impl ParseLimits {
+    /// Limits that allow anything that fits in the format, except for the nesting depth.
+    pub fn new() -> ParseLimits {
+        ParseLimits {
+            max_total_size: usize::MAX,
+            max_tag_size: i32::MAX as usize,
+            max_depth: 256,
+            max_name_length: i16::MAX as usize,
+            max_elements: usize::MAX
+        }
+    }
+
+    /// Check a value against one of the limits.
+    ///
+    /// # Returns
+    /// `OdsError::LimitExceeded` if the value is more than the limit.
+    pub fn check(&self, limit: Limit, value: usize) -> Result<()> {
+        let max = match limit {
+            Limit::TotalSize => self.max_total_size,
+            Limit::TagSize => self.max_tag_size,
+            Limit::Depth => self.max_depth,
+            Limit::NameLength => self.max_name_length,
+            Limit::Elements => self.max_elements
+        };
+        if value > max {
+            return Err(OdsError::LimitExceeded { limit, max });
+        }
+        Ok(())
+    }
+
+    /// Get the limits for the data inside of a container, which has one less level of nesting left.
+    ///
+    /// # Returns
+    /// `OdsError::LimitExceeded` if no more levels of nesting are allowed.
+    pub fn descend(&self) -> Result<ParseLimits> {
+        self.check(Limit::Depth, 1)?;
+        Ok(ParseLimits {
+            max_depth: self.max_depth - 1,
+            ..*self
+        })
+    }
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits::new()
+    }
+}
+
+/// One of the limits of `ParseLimits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    TotalSize,
+    TagSize,
+    Depth,
+    NameLength,
+    Elements
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::TotalSize => write!(f, "total size"),
+            Limit::TagSize => write!(f, "tag size"),
+            Limit::Depth => write!(f, "nesting depth"),
+            Limit::NameLength => write!(f, "name length"),
+            Limit::Elements => write!(f, "element count")
+        }
+    }
+}
\ No newline at end of file
@@ -1,7 +1,29 @@
+use crate::error::Result;
 use crate::internal::ODSInternal;
 use crate::tags::general::{Tag, Taggable, AnyTag};
+use crate::tags::value::OdsValue;
+use crate::tags::tag_ref::TagRef;
+use crate::key_path::IntoKeyPath;
+use crate::validate::ValidationReport;
+use crate::salvage::SalvageReport;
+use crate::limits::ParseLimits;
+use crate::internal::tag_builder::TagBuilder;
+use std::any::Any;
+use std::collections::HashMap;
 use std::path::PathBuf;
+use crate::compression::Compression;
+use crate::internal::compressed_file::ODSCompressedFile;
 use crate::internal::file::ODSFile;
+use crate::internal::memory::ODSMemory;
+use crate::internal::stream::ODSStream;
+#[cfg(any(feature = "mmap", test))]
+use crate::internal::mapped::ODSMappedFile;
+use crate::io::streams::{ReadOnly, StandardIO, Truncate};
+use std::io::{Read, Seek, Write};
+use crate::io::lock::LockMode;
+use crate::internal::index::IndexMode;
+#[cfg(any(feature = "serde", test))]
+pub use crate::serialization::{from_bytes, from_reader, to_bytes, to_writer};
 
 pub struct ObjectDataStructure<T: ODSInternal> {
     internal: T
@@ -14,39 +36,367 @@ impl ObjectDataStructure<ODSFile> {
         })
     }
 
-    pub fn get<T: Taggable<T>>(&mut self, key: String) -> Option<Tag<T>> {
-        self.internal.get::<T>(key)
+    /// Keep a backup of the previous version of the file (`<file>.bak`) every time the file is written.
+    ///
+    /// Writes are always atomic, the backup is only needed to undo the last change.
+    pub fn set_keep_backup(&mut self, keep_backup: bool) {
+        self.internal.set_keep_backup(keep_backup);
     }
 
-    pub fn get_all(&mut self) -> Option<Vec<AnyTag>> {
+    /// Set how the file waits for other processes that are using the same file. The default is `LockMode::Blocking`.
+    ///
+    /// Reads take a shared lock and changes take an exclusive lock on `<file>.lock`.
+    pub fn set_lock_mode(&mut self, lock_mode: LockMode) {
+        self.internal.set_lock_mode(lock_mode);
+    }
+
+    /// Index the tags of the file, so repeated lookups do not read the tags before the one they find.
+    /// The default is `IndexMode::Disabled`.
+    ///
+    /// The index is kept in memory and is built again after the file changes.
+    pub fn set_index_mode(&mut self, index_mode: IndexMode) {
+        self.internal.set_index_mode(index_mode);
+    }
+
+    /// Write a footer index at the end of the file every time it is written, so other readers can find a top level
+    /// tag without reading the tags before it. Files with a footer index can still be read by any version of ODS.
+    pub fn set_footer_index(&mut self, footer_index: bool) {
+        self.internal.set_footer_index(footer_index);
+    }
+
+    /// Set if tags that are written in place are synced to disk before returning. The default is true.
+    ///
+    /// Tags are appended to the end of the file without rewriting it, so a crash while appending can leave a part of
+    /// the tags at the end of the file. (See `salvage`.) A tag that is replaced by a tag of the same size and name
+    /// (Ex: An Int Tag with a new value) is written over the old one, so a power loss can leave it partly written.
+    pub fn set_sync_in_place(&mut self, sync_in_place: bool) {
+        self.internal.set_sync_in_place(sync_in_place);
+    }
+
+    /// Create an ObjectDataStructure for a file that stores its changes in a journal (`<file>.odsj`).
+    ///
+    /// Small changes (Ex: `replace_data`) only append a record to the journal instead of rewriting the whole file.
+    /// Any changes that are left in the journal (Ex: After a crash) are folded into the file when it is opened.
+    ///
+    /// # Params
+    /// file: `PathBuf` -> The file to use.
+    pub fn new_journaled_file(file: PathBuf) -> Result<Box<ObjectDataStructure<ODSFile>>> {
+        Ok(Box::new(ObjectDataStructure {
+            internal: ODSFile::new_journaled(file)?
+        }))
+    }
+
+    /// Fold the changes in the journal into the file and remove the journal.
+    pub fn checkpoint(&mut self) -> Result<()> {
+        self.internal.checkpoint()
+    }
+
+    /// Recover every tag that can still be read from a damaged file, instead of failing at the first problem.
+    ///
+    /// # Params
+    /// repair: bool -> If the file should be replaced with the recovered tags when it is damaged.
+    /// The damaged file is kept as a backup (`<file>.bak`).
+    ///
+    /// # Returns
+    /// The recovered tags and the byte ranges that had to be skipped.
+    pub fn salvage(&mut self, repair: bool) -> Result<SalvageReport> {
+        self.internal.salvage(repair)
+    }
+}
+
+impl ObjectDataStructure<ODSCompressedFile> {
+    /// Create an ObjectDataStructure for a file that is compressed as a whole.
+    /// This can read and write the compressed files that are created by the Java version of ODS.
+    ///
+    /// # Params
+    /// file: `PathBuf` -> The file to use.
+    /// compression: `Compression` -> The compression of the file.
+    pub fn new_compressed_file(file: PathBuf, compression: Compression) -> Box<ObjectDataStructure<ODSCompressedFile>> {
+        Box::new(ObjectDataStructure {
+            internal: ODSCompressedFile::new(file, compression)
+        })
+    }
+
+    /// Keep a backup of the previous version of the file (`<file>.bak`) every time the file is written.
+    pub fn set_keep_backup(&mut self, keep_backup: bool) {
+        self.internal.set_keep_backup(keep_backup);
+    }
+
+    /// Set how the file waits for other processes that are using the same file. The default is `LockMode::Blocking`.
+    ///
+    /// Reads take a shared lock and changes take an exclusive lock on `<file>.lock`.
+    pub fn set_lock_mode(&mut self, lock_mode: LockMode) {
+        self.internal.set_lock_mode(lock_mode);
+    }
+}
+
+impl ObjectDataStructure<ODSMemory> {
+    /// Create an ObjectDataStructure that is stored in memory.
+    ///
+    /// # Params
+    /// bytes: `Vec<u8>` -> The encoded tags to start with. Use an empty Vec for a new document.
+    pub fn new_memory(bytes: Vec<u8>) -> Box<ObjectDataStructure<ODSMemory>> {
+        Box::new(ObjectDataStructure {
+            internal: ODSMemory::new(bytes)
+        })
+    }
+
+    /// Index the tags of the document, so repeated lookups do not read the tags before the one they find.
+    /// The default is `IndexMode::Disabled`.
+    ///
+    /// Appended tags are added to the index, other changes remove it until the next lookup.
+    pub fn set_index_mode(&mut self, index_mode: IndexMode) {
+        self.internal.set_index_mode(index_mode);
+    }
+
+    /// Get the encoded bytes of the document.
+    pub fn get_bytes(&self) -> &[u8] {
+        self.internal.get_bytes()
+    }
+
+    /// Convert the ObjectDataStructure into the encoded bytes of the document.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.internal.into_bytes()
+    }
+
+    /// Borrow the tag with the specified key, without copying it. (See `TagRef`.)
+    ///
+    /// # Params
+    /// key: `impl IntoKeyPath` -> The key of the tag.
+    pub fn get_tag_ref(&self, key: impl IntoKeyPath) -> Result<TagRef<'_>> {
+        self.internal.get_tag_ref(&key.into_key_path()?)
+    }
+}
+
+#[cfg(any(feature = "mmap", test))]
+impl ObjectDataStructure<ODSMappedFile> {
+    /// Create an ObjectDataStructure for a file that is mapped into memory and can only be read. (Requires the `mmap` feature.)
+    ///
+    /// Opening the file does not read it, and every process that maps the same file shares its bytes.
+    /// Any change returns an `OdsError::Io` error with the kind `PermissionDenied`.
+    ///
+    /// Files that ODS replaces (Ex: With `save`) or appends to are seen after `remap`.
+    ///
+    /// # Params
+    /// file: `PathBuf` -> The file to map. It must exist.
+    ///
+    /// # Safety
+    /// The file must not be truncated or written over while the ObjectDataStructure exists, by any program including
+    /// ODS. ODS writes over a file with `replace_data` or `set` when the new tag has the same size as the old one, and
+    /// with an ObjectDataStructure that is stored in a stream over the file. See `ODSMappedFile::new` for the details.
+    pub unsafe fn new_mapped_file(file: PathBuf) -> Result<Box<ObjectDataStructure<ODSMappedFile>>> {
+        Ok(Box::new(ObjectDataStructure {
+            // SAFETY: The caller upholds the same guarantee.
+            internal: unsafe { ODSMappedFile::new(file)? }
+        }))
+    }
+
+    /// Map the file again, so changes that were written since it was mapped are seen.
+    pub fn remap(&mut self) -> Result<()> {
+        self.internal.remap()
+    }
+
+    /// Set how the file waits for other processes that are changing the same file. The default is `LockMode::Blocking`.
+    pub fn set_lock_mode(&mut self, lock_mode: LockMode) {
+        self.internal.set_lock_mode(lock_mode);
+    }
+
+    /// Index the tags of the file, so repeated lookups do not read the tags before the one they find.
+    /// The default is `IndexMode::Disabled`.
+    pub fn set_index_mode(&mut self, index_mode: IndexMode) {
+        self.internal.set_index_mode(index_mode);
+    }
+
+    /// Get the mapped bytes of the file, without copying them.
+    pub fn get_bytes(&self) -> &[u8] {
+        self.internal.get_bytes()
+    }
+}
+
+impl<S: Read + Write + Seek + Truncate> ObjectDataStructure<ODSStream<S>> {
+    /// Create an ObjectDataStructure that is stored in a stream. (Ex: A `File`, a `Cursor` or a custom virtual filesystem.)
+    ///
+    /// `get` and `find` only read the tags on the path of the key, so the whole stream is never copied into memory to find a tag.
+    /// Changes other than `append` write the whole document back to the stream.
+    ///
+    /// # Params
+    /// stream: `S` -> The stream that holds the encoded tags. It can be empty for a new document.
+    pub fn new_stream(stream: S) -> Result<Box<ObjectDataStructure<ODSStream<S>>>> {
+        Ok(Box::new(ObjectDataStructure {
+            internal: ODSStream::new(stream)?
+        }))
+    }
+
+    pub fn get_ref(&self) -> &S {
+        self.internal.get_ref()
+    }
+
+    /// Convert the ObjectDataStructure back into its stream.
+    pub fn into_inner(self) -> S {
+        self.internal.into_inner()
+    }
+}
+
+impl<R: Read + Seek> ObjectDataStructure<ODSStream<ReadOnly<R>>> {
+    /// Create an ObjectDataStructure over a stream that can only be read.
+    /// Any change returns an `OdsError::Io` error with the kind `PermissionDenied`.
+    ///
+    /// # Params
+    /// reader: `R` -> The stream that holds the encoded tags.
+    pub fn new_read_only_stream(reader: R) -> Result<Box<ObjectDataStructure<ODSStream<ReadOnly<R>>>>> {
+        ObjectDataStructure::new_stream(ReadOnly(reader))
+    }
+}
+
+impl<I: ODSInternal> ObjectDataStructure<I> {
+    /// Get the tag with the specified key.
+    ///
+    /// # Params
+    /// key: `impl IntoKeyPath` -> The key of the tag. This is a `KeyPath` or a string that is parsed as one. (Ex: `players[3].name`)
+    pub fn get<T: Taggable<T>>(&mut self, key: impl IntoKeyPath) -> Result<Tag<T>> {
+        self.internal.get::<T>(key.into_key_path()?)
+    }
+
+    /// Get the tags with many keys, reading the document only once.
+    ///
+    /// Calling `get` for every key reads the document from the start each time. Instead the tags are found in a single
+    /// pass, and only the containers that hold one of the keys are searched.
+    ///
+    /// # Params
+    /// keys: `impl IntoIterator<Item = K>` -> The keys of the tags. (Ex: `["Car.Owner.Name", "Car.Year"]`)
+    ///
+    /// # Returns
+    /// A map from every key to its tag, or to the error that `get` would return for it. (Ex: `OdsError::KeyNotFound`.)
+    /// An error that stops the document from being read is returned for the whole call.
+    pub fn get_many<K: IntoKeyPath + ToString>(&mut self, keys: impl IntoIterator<Item = K>) -> Result<HashMap<String, Result<AnyTag>>> {
+        self.get_many_with(keys, |current_builder| current_builder.process::<Box<dyn Any>>())
+    }
+
+    /// Get the tags with many keys that all have the same type, reading the document only once. (See `get_many`.)
+    ///
+    /// A tag of another type is `OdsError::TypeMismatch` in the map.
+    pub fn get_many_as<T: Taggable<T>, K: IntoKeyPath + ToString>(&mut self, keys: impl IntoIterator<Item = K>) -> Result<HashMap<String, Result<Tag<T>>>> {
+        self.get_many_with(keys, |current_builder| current_builder.process::<T>())
+    }
+
+    /// Get the values of the tags with many keys, reading the document only once. (See `get_many`.)
+    pub fn get_many_values<K: IntoKeyPath + ToString>(&mut self, keys: impl IntoIterator<Item = K>) -> Result<HashMap<String, Result<OdsValue>>> {
+        self.get_many_with(keys, |mut current_builder| {
+            let mut value_stream = current_builder.take_value_bytes()?;
+            let limits = value_stream.get_limits();
+            OdsValue::read_value(current_builder.data_type as u8, value_stream.bytes(), &limits)
+        })
+    }
+
+    /// Find the tags with many keys and convert every tag that is found.
+    /// A key that cannot be parsed is `OdsError::InvalidKey` in the map.
+    fn get_many_with<K: IntoKeyPath + ToString, V>(&mut self, keys: impl IntoIterator<Item = K>,
+                                                   convert: impl Fn(TagBuilder) -> Result<V>) -> Result<HashMap<String, Result<V>>> {
+        let mut output = HashMap::new();
+        let (mut names, mut paths) = (Vec::new(), Vec::new());
+        for key in keys {
+            let name = key.to_string();
+            match key.into_key_path() {
+                Ok(path) => {
+                    names.push(name);
+                    paths.push(path);
+                }
+                Err(err) => {
+                    output.insert(name, Err(err));
+                }
+            }
+        }
+
+        for (name, found) in names.into_iter().zip(self.internal.get_many(&paths)?) {
+            output.insert(name, found.and_then(&convert));
+        }
+        Ok(output)
+    }
+
+    pub fn get_all(&mut self) -> Result<Vec<AnyTag>> {
         self.internal.get_all()
     }
 
-    pub fn save(&mut self, tags: Vec<AnyTag>) {
-        unimplemented!("This method is not implemented at this time.");
+    /// Parse the whole document into an owned value tree.
+    ///
+    /// # Returns
+    /// An `OdsValue::Object` that holds every tag of the document, in order.
+    pub fn load_value(&mut self) -> Result<OdsValue> {
+        self.internal.load_value()
     }
 
-    pub fn append<T: Taggable<T>>(&mut self, tag: Tag<T>) {
-        self.internal.append(tag);
+    /// Replace the whole document with a list of tags.
+    ///
+    /// # Params
+    /// tags: `Vec<AnyTag>` -> The tags to save. (They can be of different types.)
+    pub fn save(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        self.internal.save(tags)
     }
 
-    pub fn append_all(&mut self, tags: Vec<AnyTag>){
-        unimplemented!("This method is not implemented at this time.");
+    /// Replace the whole document with a value tree. This is the opposite of `load_value`.
+    ///
+    /// # Params
+    /// value: `&OdsValue` -> The value to save. The children of an Object are saved as the tags of the document.
+    pub fn save_value(&mut self, value: &OdsValue) -> Result<()> {
+        self.internal.save_value(value)
     }
 
-    pub fn find(&mut self, key: String) -> bool {
-        self.internal.find(key)
+    pub fn append<T: Taggable<T>>(&mut self, tag: Tag<T>) -> Result<()> {
+        self.internal.append(tag)
     }
 
-    pub fn delete(&mut self, key: String) -> bool {
-        self.internal.delete(key)
+    /// Append a list of tags to the end of the document in one write.
+    ///
+    /// # Params
+    /// tags: `Vec<AnyTag>` -> The tags to append. (They can be of different types.)
+    pub fn append_all(&mut self, tags: Vec<AnyTag>) -> Result<()> {
+        self.internal.append_all(tags)
     }
 
-    pub fn replace_data<T: Taggable<T>>(&mut self, key: String, replacement: Tag<T>) -> bool {
-        self.internal.replace_data(key, replacement)
+    pub fn find(&mut self, key: impl IntoKeyPath) -> Result<bool> {
+        self.internal.find(key.into_key_path()?)
     }
 
-    pub fn set<T: Taggable<T>>(&mut self, key: String, value: Option<Tag<T>>) {
+    pub fn delete(&mut self, key: impl IntoKeyPath) -> Result<bool> {
+        self.internal.delete(key.into_key_path()?)
+    }
+
+    pub fn replace_data<T: Taggable<T>>(&mut self, key: impl IntoKeyPath, replacement: Tag<T>) -> Result<bool> {
+        self.internal.replace_data(key.into_key_path()?, replacement)
+    }
+
+    /// Set the tag with the specified key.
+    ///
+    /// If the tag exists it is replaced. Otherwise the tag is added, and any objects that are missing
+    /// along the key are created. (Ex: Setting `a.b.c` creates the Object Tags `a` and `b` if they do not exist.)
+    /// The name of the tag is set to the last section of the key, unless the last section is an index.
+    ///
+    /// # Params
+    /// key: `impl IntoKeyPath` -> The key of the tag. An empty key replaces the whole document with the tag.
+    /// value: `Option<Tag<T>>` -> The tag to set. If this is None, the tag with the key is deleted.
+    ///
+    /// # Returns
+    /// `OdsError::KeyNotFound` if the value is None and the key does not exist, or if an index section of the key
+    /// does not exist. `OdsError::TypeMismatch` if a section of the key exists but is not an object.
+    pub fn set<T: Taggable<T>>(&mut self, key: impl IntoKeyPath, value: Option<Tag<T>>) -> Result<()> {
+        self.internal.set(key.into_key_path()?, value)
+    }
+
+    /// Check the structure of the whole document, without stopping at the first problem.
+    ///
+    /// For a file this checks the bytes that are stored in the file. (The changes in a journal are not included.)
+    ///
+    /// # Returns
+    /// A report with the byte offset and the key of every problem. See `validate::validate` for what is checked.
+    pub fn validate(&mut self) -> Result<ValidationReport> {
+        self.internal.validate()
+    }
 
+    /// Set the limits that the document is read with. Use this when the data comes from a source that is not trusted.
+    ///
+    /// Every read that goes past a limit returns `OdsError::LimitExceeded` instead of allocating the memory.
+    /// (See `ParseLimits`.)
+    pub fn set_parse_limits(&mut self, limits: ParseLimits) {
+        self.internal.set_parse_limits(limits);
     }
 }
\ No newline at end of file
@@ -0,0 +1,261 @@
+//! Recover the tags that can still be read from a damaged document.
+//!
+//! The tags are read the same way as `get_all` does, but a tag that cannot be read is skipped instead of
+//! stopping the read. Containers (Ex: Object Tags) are read child by child, so the intact children of a damaged
+//! container are kept. When the size of a tag is wrong the next tag is found by looking for the next valid tag header.
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use crate::error::Result;
+use crate::internal::internal_utils::OBJECT_COMPRESSION;
+use crate::limits::{Limit, ParseLimits};
+use crate::tags::general::AnyTag;
+use crate::tags::value::OdsValue;
+use crate::validate::fixed_value_size;
+
+/// The size of the id, the data size and the name length of a tag.
+const HEADER_SIZE: usize = 7;
+
+/// The tags that were recovered from a document, and the bytes that had to be skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SalvageReport {
+    value: OdsValue,
+    skipped: Vec<SkippedRange>
+}
+
+impl SalvageReport {
+    /// Check if the whole document could be read.
+    pub fn is_intact(&self) -> bool {
+        self.skipped.is_empty()
+    }
+
+    /// Get the recovered tags as an `OdsValue::Object`.
+    pub fn value(&self) -> &OdsValue {
+        &self.value
+    }
+
+    pub fn into_value(self) -> OdsValue {
+        self.value
+    }
+
+    /// Get the recovered tags.
+    pub fn tags(&self) -> Result<Vec<AnyTag>> {
+        let mut output = Vec::new();
+        for (name, value) in self.value.entries().into_iter().flatten() {
+            output.push(value.to_any_tag(name.clone())?);
+        }
+        Ok(output)
+    }
+
+    /// Get the byte ranges that could not be recovered, in the order they appear in the document.
+    pub fn skipped(&self) -> &[SkippedRange] {
+        &self.skipped
+    }
+
+    /// Encode the recovered tags as a repaired document.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        self.value.to_bytes()
+    }
+}
+
+/// A range of bytes that could not be recovered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRange {
+    /// The bytes that were skipped. When the data ended in the middle of a tag, the range continues past
+    /// the end of the data up to where the tag should have ended.
+    ///
+    /// Inside of a Compressed Object Tag this is the range in its decompressed value.
+    pub range: Range<u64>,
+    /// The offset of the Compressed Object Tag that holds the range, if there is one.
+    pub compressed: Option<u64>
+}
+
+/// Recover every tag that can be fully read from a damaged document.
+///
+/// # Params
+/// data: `&[u8]` -> The encoded tags of the document.
+pub fn salvage(data: &[u8]) -> SalvageReport {
+    let mut skipped = Vec::new();
+    let entries = salvage_tags(data, 0, Option::None, &ParseLimits::default(), &mut skipped);
+    SalvageReport {
+        value: OdsValue::Object(entries),
+        skipped
+    }
+}
+
+/// Recover the tags in the data.
+///
+/// # Params
+/// data: `&[u8]` -> The tags.
+/// offset: u64 -> The offset of the data.
+/// compressed: `Option<u64>` -> The offset of the Compressed Object Tag that holds the data.
+/// limits: `&ParseLimits` -> The limits of the data. Containers that are nested too deep are skipped.
+/// skipped: `&mut Vec<SkippedRange>` -> The list to add the skipped ranges to.
+fn salvage_tags(data: &[u8], offset: u64, compressed: Option<u64>, limits: &ParseLimits, skipped: &mut Vec<SkippedRange>) -> Vec<(String, OdsValue)> {
+    let mut output = Vec::new();
+    let mut position = 0;
+    while position < data.len() {
+        let (id, size, name_length) = match read_header(data, position, limits) {
+            Some(header) => header,
+            None => {
+                // The data ends in the middle of a container, so it is read up to the end of the data.
+                if let Some((tag_end, entry)) = salvage_truncated(data, position, offset, compressed, limits, skipped) {
+                    output.push(entry);
+                    skip(skipped, offset + data.len() as u64..offset + tag_end as u64, compressed);
+                    break;
+                }
+                let next = find_next_tag(data, position + 1);
+                // When no tag follows, the data most likely ends in the middle of this tag.
+                let end = match declared_end(data, position) {
+                    Some(tag_end) if next == data.len() => tag_end.max(next),
+                    _ => next
+                };
+                skip(skipped, offset + position as u64..offset + end as u64, compressed);
+                position = next;
+                continue;
+            }
+        };
+
+        let name_end = position + HEADER_SIZE + name_length;
+        let tag_end = position + 5 + size;
+        let entry = salvage_tag(
+            id,
+            &data[position + HEADER_SIZE..name_end],
+            &data[name_end..tag_end],
+            (offset + position as u64, offset + name_end as u64),
+            compressed,
+            limits,
+            skipped
+        );
+        match entry {
+            Some(entry) => output.push(entry),
+            None => skip(skipped, offset + position as u64..offset + tag_end as u64, compressed)
+        }
+        position = tag_end;
+    }
+
+    output
+}
+
+/// Read the header of the tag at the position, straight from the data. (See `read_tag_header`.)
+///
+/// # Returns
+/// The id, the data size and the name length of the tag, or None if the tag does not fit inside of the data
+/// or the limits.
+fn read_header(data: &[u8], position: usize, limits: &ParseLimits) -> Option<(u8, usize, usize)> {
+    limits.check(Limit::TotalSize, data.len()).ok()?;
+    let header = data.get(position..position + HEADER_SIZE)?;
+    let size = usize::try_from(i32::from_be_bytes([header[1], header[2], header[3], header[4]])).ok()?;
+    if size < 2 || position + 5 + size > data.len() {
+        return Option::None;
+    }
+    limits.check(Limit::TagSize, size).ok()?;
+    let name_length = usize::try_from(i16::from_be_bytes([header[5], header[6]])).ok()?;
+    if name_length > size - 2 {
+        return Option::None;
+    }
+    limits.check(Limit::NameLength, name_length).ok()?;
+    Some((header[0], size, name_length))
+}
+
+/// Add a range to the skipped ranges.
+fn skip(skipped: &mut Vec<SkippedRange>, range: Range<u64>, compressed: Option<u64>) {
+    skipped.push(SkippedRange {
+        range,
+        compressed
+    });
+}
+
+/// Recover a tag whose header is valid.
+///
+/// # Params
+/// id: u8 -> The id of the tag.
+/// name: `&[u8]` -> The name of the tag.
+/// value: `&[u8]` -> The value of the tag.
+/// (tag_offset, value_offset): (u64, u64) -> The offset of the tag and of its value.
+/// compressed: `Option<u64>` -> The offset of the Compressed Object Tag that holds the tag.
+/// limits: `&ParseLimits` -> The limits of the data that holds the tag.
+/// skipped: `&mut Vec<SkippedRange>` -> The list to add the skipped ranges inside of the tag to.
+///
+/// # Returns
+/// The name and value of the tag, or None if it could not be read.
+fn salvage_tag(id: u8, name: &[u8], value: &[u8], (tag_offset, value_offset): (u64, u64), compressed: Option<u64>,
+               limits: &ParseLimits, skipped: &mut Vec<SkippedRange>) -> Option<(String, OdsValue)> {
+    let name = String::from_utf8(name.to_vec()).ok()?;
+    let value = match id {
+        9 => OdsValue::Vec(salvage_tags(value, value_offset, compressed, &limits.descend().ok()?, skipped).into_iter().map(|(_, value)| value).collect()),
+        10 => OdsValue::Map(salvage_tags(value, value_offset, compressed, &limits.descend().ok()?, skipped)),
+        11 => OdsValue::Object(salvage_tags(value, value_offset, compressed, &limits.descend().ok()?, skipped)),
+        12 => {
+            let inner_limits = limits.descend().ok()?;
+            let decompressed = OBJECT_COMPRESSION.decompress_limited(value, limits).ok()?;
+            OdsValue::CompressedObject(salvage_tags(&decompressed, 0, Some(tag_offset), &inner_limits, skipped))
+        }
+        _ => {
+            if fixed_value_size(id).is_some_and(|size| size != value.len()) {
+                return Option::None;
+            }
+            OdsValue::read_value(id, value.to_vec(), limits).ok()?
+        }
+    };
+    Some((name, value))
+}
+
+/// Recover a Vec, Map or Object Tag that continues past the end of the data.
+///
+/// # Returns
+/// Where the tag should have ended, and the name and the recovered children of the tag.
+/// None if the tag at the position is not a container that was cut off.
+fn salvage_truncated(data: &[u8], position: usize, offset: u64, compressed: Option<u64>, limits: &ParseLimits,
+                     skipped: &mut Vec<SkippedRange>) -> Option<(usize, (String, OdsValue))> {
+    let header = data.get(position..position + HEADER_SIZE)?;
+    let size = usize::try_from(i32::from_be_bytes([header[1], header[2], header[3], header[4]])).ok()?;
+    let name_length = usize::try_from(i16::from_be_bytes([header[5], header[6]])).ok()?;
+    let tag_end = position + 5 + size;
+    let name_end = position + HEADER_SIZE + name_length;
+    if !(9..=11).contains(&header[0]) || tag_end <= data.len() || name_end > data.len() || name_end > tag_end {
+        return Option::None;
+    }
+
+    let entry = salvage_tag(header[0], &data[position + HEADER_SIZE..name_end], &data[name_end..],
+                            (offset + position as u64, offset + name_end as u64), compressed, limits, skipped)?;
+    Some((tag_end, entry))
+}
+
+/// Get where the tag at the position should end according to its data size, or None if the data size cannot be read.
+fn declared_end(data: &[u8], position: usize) -> Option<usize> {
+    let header = data.get(position..position + 5)?;
+    let size = usize::try_from(i32::from_be_bytes([header[1], header[2], header[3], header[4]])).ok()?;
+    Some(position + 5 + size)
+}
+
+/// Find the next position that holds a valid tag header.
+///
+/// # Returns
+/// The position, or the length of the data if there is no valid tag after the position.
+fn find_next_tag(data: &[u8], from: usize) -> usize {
+    (from..data.len()).find(|&position| is_valid_header(data, position)).unwrap_or(data.len())
+}
+
+/// Check if a valid tag that fits inside of the data starts at the position.
+fn is_valid_header(data: &[u8], position: usize) -> bool {
+    let header = match data.get(position..position + HEADER_SIZE) {
+        Some(header) => header,
+        None => return false
+    };
+    let id = header[0];
+    let size = i32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+    let name_length = i16::from_be_bytes([header[5], header[6]]);
+    let (size, name_length) = match (usize::try_from(size), usize::try_from(name_length)) {
+        (Ok(size), Ok(name_length)) => (size, name_length),
+        _ => return false
+    };
+    if !(1..=12).contains(&id) || size < 2 || name_length > size - 2 || position + 5 + size > data.len() {
+        return false;
+    }
+
+    let name_end = position + HEADER_SIZE + name_length;
+    let value_size = size - 2 - name_length;
+    std::str::from_utf8(&data[position + HEADER_SIZE..name_end]).is_ok()
+        && fixed_value_size(id).is_none_or(|expected| expected == value_size)
+}
\ No newline at end of file
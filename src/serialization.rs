@@ -0,0 +1,609 @@
+/*!
+    Serialize and deserialize any serde type to and from the ODS format.
+
+    Values are converted to an `OdsValue` tree, which is then encoded as tags. The output can be read with
+    `ObjectDataStructure::get` like any other ODS data.
+
+    # Encoding
+    | Rust | ODS |
+    |------|-----|
+    | struct, map | Object Tag, with a tag for every field (or entry) |
+    | sequence, tuple, tuple struct | Vec Tag |
+    | `String`, `&str` | String Tag |
+    | `char` | Char Tag (a Char Tag is one byte, so only chars up to `U+00FF` can be serialized) |
+    | `bool`, `u8` | Byte Tag (`bool` is `0` or `1`) |
+    | `i8`, `i16` | Short Tag |
+    | `i32`, `u16` | Int Tag |
+    | `i64`, `u32`, `u64` | Long Tag (`u64` values above `i64::MAX` cannot be serialized) |
+    | `f32` | Float Tag |
+    | `f64` | Double Tag |
+    | byte arrays | Vec Tag of Byte Tags |
+    | `()`, unit struct | empty Object Tag |
+    | newtype struct | the inner value |
+    | `Some(value)` | the inner value |
+    | `None` | no tag: the field (or map entry) is left out |
+    | unit enum variant | String Tag containing the name of the variant |
+    | other enum variants | Object Tag with one tag named after the variant, holding the value of the variant |
+
+    Map keys are stored as the names of the tags, so they must be strings, chars or integers.
+    The top level value must be a struct or a map, its fields are stored as the tags of the document.
+    A `None` cannot be stored in a sequence.
+*/
+use std::fmt::Display;
+use std::io::{Read, Write};
+use std::vec;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+use serde::forward_to_deserialize_any;
+
+use crate::error::{OdsError, Result};
+use crate::io::streams::char_byte;
+use crate::tags::value::OdsValue;
+
+impl ser::Error for OdsError {
+    fn custom<T: Display>(msg: T) -> Self {
+        OdsError::Serde(msg.to_string())
+    }
+}
+
+impl de::Error for OdsError {
+    fn custom<T: Display>(msg: T) -> Self {
+        OdsError::Serde(msg.to_string())
+    }
+}
+
+/// Serialize a value into the encoded tags of an ODS document.
+///
+/// # Params
+/// value: `&T` -> The value to serialize. This must be a struct or a map.
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    match to_value(value)? {
+        value @ OdsValue::Object(_) => value.to_bytes(),
+        _ => Err(OdsError::Serde("the top level value must be a struct or a map".to_string()))
+    }
+}
+
+/// Serialize a value into a writer. See `to_bytes`.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(mut writer: W, value: &T) -> Result<()> {
+    writer.write_all(&to_bytes(value)?)?;
+    Ok(())
+}
+
+/// Serialize a value into an `OdsValue`.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<OdsValue> {
+    value.serialize(Serializer)?.ok_or_else(none_error)
+}
+
+/// Deserialize a value from the encoded tags of an ODS document.
+///
+/// # Params
+/// bytes: `&[u8]` -> The encoded tags. (Ex: The bytes of an ODS file.)
+pub fn from_bytes<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    from_value(OdsValue::from_bytes(bytes)?)
+}
+
+/// Deserialize a value from a reader. See `from_bytes`.
+pub fn from_reader<R: Read, T: DeserializeOwned>(mut reader: R) -> Result<T> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    from_bytes(&bytes)
+}
+
+/// Deserialize a value from an `OdsValue`.
+pub fn from_value<T: DeserializeOwned>(value: OdsValue) -> Result<T> {
+    T::deserialize(Deserializer::new(value))
+}
+
+fn none_error() -> OdsError {
+    OdsError::Serde("`None` can only be stored as a field or a map entry".to_string())
+}
+
+/// Serializes a value into an `OdsValue`.
+///
+/// The output is None when the value is `None`, so the field that holds it can be left out.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<OdsValue>;
+    type Error = OdsError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = SeqSerializer;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = MapSerializer;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Byte(v as u8)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Short(v as i16)))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Short(v)))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Int(v)))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Long(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Byte(v)))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Int(v as i32)))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Long(v as i64)))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        if v > i64::MAX as u64 {
+            return Err(OdsError::Serde(format!("{} is too large to be stored in a Long Tag", v)));
+        }
+        Ok(Some(OdsValue::Long(v as i64)))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Float(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Double(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        char_byte(v)?;
+        Ok(Some(OdsValue::Char(v)))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::String(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Vec(v.iter().map(|byte| OdsValue::Byte(*byte)).collect())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Object(Vec::new())))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::String(variant.to_string())))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _variant_index: u32, variant: &'static str, value: &T) -> Result<Self::Ok> {
+        Ok(Some(OdsValue::Object(vec![(variant.to_string(), to_value(value)?)])))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SeqSerializer {
+            variant: None,
+            values: Vec::with_capacity(len.unwrap_or(0))
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeTupleVariant> {
+        Ok(SeqSerializer {
+            variant: Some(variant),
+            values: Vec::with_capacity(len)
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(MapSerializer {
+            variant: None,
+            entries: Vec::with_capacity(len.unwrap_or(0)),
+            next_key: None
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _variant_index: u32, variant: &'static str, len: usize) -> Result<Self::SerializeStructVariant> {
+        Ok(MapSerializer {
+            variant: Some(variant),
+            entries: Vec::with_capacity(len),
+            next_key: None
+        })
+    }
+}
+
+/// Wrap the value of an enum variant in an Object Tag named after the variant.
+fn wrap_variant(variant: Option<&'static str>, value: OdsValue) -> Option<OdsValue> {
+    match variant {
+        Some(variant) => Some(OdsValue::Object(vec![(variant.to_string(), value)])),
+        None => Some(value)
+    }
+}
+
+/// Serializes sequences, tuples and tuple variants into a Vec.
+pub struct SeqSerializer {
+    variant: Option<&'static str>,
+    values: Vec<OdsValue>
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Option<OdsValue>;
+    type Error = OdsError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.values.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(wrap_variant(self.variant, OdsValue::Vec(self.values)))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Option<OdsValue>;
+    type Error = OdsError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Option<OdsValue>;
+    type Error = OdsError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer {
+    type Ok = Option<OdsValue>;
+    type Error = OdsError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializes maps, structs and struct variants into an Object.
+pub struct MapSerializer {
+    variant: Option<&'static str>,
+    entries: Vec<(String, OdsValue)>,
+    next_key: Option<String>
+}
+
+impl MapSerializer {
+    fn add_entry<T: Serialize + ?Sized>(&mut self, key: String, value: &T) -> Result<()> {
+        if let Some(value) = value.serialize(Serializer)? {
+            self.entries.push((key, value));
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Option<OdsValue>;
+    type Error = OdsError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.next_key = Some(match to_value(key)? {
+            OdsValue::String(key) => key,
+            OdsValue::Char(key) => key.to_string(),
+            key @ (OdsValue::Byte(_) | OdsValue::Short(_) | OdsValue::Int(_) | OdsValue::Long(_)) => key.as_i64().unwrap_or_default().to_string(),
+            _ => return Err(OdsError::Serde("map keys must be strings, chars or integers".to_string()))
+        });
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.next_key.take().ok_or_else(|| OdsError::Serde("a map value was serialized before its key".to_string()))?;
+        self.add_entry(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(wrap_variant(self.variant, OdsValue::Object(self.entries)))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Option<OdsValue>;
+    type Error = OdsError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.add_entry(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for MapSerializer {
+    type Ok = Option<OdsValue>;
+    type Error = OdsError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.add_entry(key.to_string(), value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+/// Deserializes a value from an `OdsValue`.
+pub struct Deserializer {
+    value: OdsValue
+}
+
+impl Deserializer {
+    pub fn new(value: OdsValue) -> Self {
+        Deserializer {
+            value
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = OdsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            OdsValue::String(value) => visitor.visit_string(value),
+            OdsValue::Int(value) => visitor.visit_i32(value),
+            OdsValue::Float(value) => visitor.visit_f32(value),
+            OdsValue::Double(value) => visitor.visit_f64(value),
+            OdsValue::Short(value) => visitor.visit_i16(value),
+            OdsValue::Long(value) => visitor.visit_i64(value),
+            OdsValue::Char(value) => visitor.visit_char(value),
+            OdsValue::Byte(value) => visitor.visit_u8(value),
+            OdsValue::Vec(values) => visitor.visit_seq(SeqDeserializer {
+                values: values.into_iter()
+            }),
+            OdsValue::Map(entries) | OdsValue::Object(entries) | OdsValue::CompressedObject(entries) => visitor.visit_map(MapDeserializer {
+                entries: entries.into_iter(),
+                next_value: None
+            })
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            OdsValue::Byte(value) => visitor.visit_bool(value != 0),
+            _ => self.deserialize_any(visitor)
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if let OdsValue::Vec(values) = &self.value {
+            let bytes: Option<Vec<u8>> = values.iter().map(|value| match value {
+                OdsValue::Byte(byte) => Some(*byte),
+                _ => None
+            }).collect();
+            if let Some(bytes) = bytes {
+                return visitor.visit_byte_buf(bytes);
+            }
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // A None is never stored, so any value that exists is Some.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        match self.value {
+            OdsValue::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            OdsValue::Object(mut entries) if entries.len() == 1 => {
+                let (variant, value) = entries.remove(0);
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value
+                })
+            }
+            _ => Err(OdsError::Serde("an enum must be a String Tag or an Object Tag with one tag".to_string()))
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        seq tuple tuple_struct map struct identifier
+    }
+}
+
+struct SeqDeserializer {
+    values: vec::IntoIter<OdsValue>
+}
+
+impl<'de> de::SeqAccess<'de> for SeqDeserializer {
+    type Error = OdsError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.values.next() {
+            Some(value) => seed.deserialize(Deserializer::new(value)).map(Some),
+            None => Ok(None)
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
+    }
+}
+
+struct MapDeserializer {
+    entries: vec::IntoIter<(String, OdsValue)>,
+    next_value: Option<OdsValue>
+}
+
+impl<'de> de::MapAccess<'de> for MapDeserializer {
+    type Error = OdsError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.next_value = Some(value);
+                seed.deserialize(KeyDeserializer {
+                    key
+                }).map(Some)
+            }
+            None => Ok(None)
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let value = self.next_value.take().ok_or_else(|| OdsError::Serde("a map value was read before its key".to_string()))?;
+        seed.deserialize(Deserializer::new(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// Deserializes the name of a tag as a map key. Integer keys are parsed from the name.
+struct KeyDeserializer {
+    key: String
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                match self.key.parse() {
+                    Ok(key) => visitor.$visit(key),
+                    Err(_) => visitor.visit_string(self.key)
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = OdsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.key)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_i8 => visit_i8, deserialize_i16 => visit_i16, deserialize_i32 => visit_i32, deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8, deserialize_u16 => visit_u16, deserialize_u32 => visit_u32, deserialize_u64 => visit_u64
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        visitor.visit_enum(self.key.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        bool i128 u128 f32 f64 char str string bytes byte_buf option unit unit_struct
+        seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct EnumDeserializer {
+    variant: String,
+    value: OdsValue
+}
+
+impl<'de> de::EnumAccess<'de> for EnumDeserializer {
+    type Error = OdsError;
+    type Variant = Deserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(de::value::StringDeserializer::<OdsError>::new(self.variant))?;
+        Ok((variant, Deserializer::new(self.value)))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for Deserializer {
+    type Error = OdsError;
+
+    fn unit_variant(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
\ No newline at end of file
@@ -1,8 +1,10 @@
-use crate::tags::general::{Container, ObjectContainer, VectorContainer};
+use crate::tags::general::{Container, ObjectContainer, VectorContainer, MapContainer, CompressedObjectContainer};
 use std::any::Any;
 
 pub type Object = Container<ObjectContainer>;
 pub type Vector = Container<VectorContainer>;
+pub type Map<V> = Container<MapContainer<V>>;
+pub type CompressedObject = Container<CompressedObjectContainer>;
 
 pub type Short = i16;
 pub type Integer = i32;
@@ -1,11 +1,17 @@
-use crate::io::streams::{ReadStream, WriteStream, Stream, IOWrite, StandardIO, IORead};
+use crate::error::{OdsError, Result};
+use crate::io::streams::{WriteStream, Stream, IOWrite, StandardIO, IORead};
 use std::any::Any;
-use std::fmt::Debug;
+use std::collections::HashMap;
 use crate::internal::tag_builder::TagBuilder;
-use crate::internal::internal_utils::{get_list_data, get_sub_object_data, get_sub_object_by_index, get_data_type_by_index, scout_index_data, delete_sub_object_data, get_object_count, get_sub_object_type, scout_object_data, find_sub_object_data};
+use crate::internal::internal_utils::{get_sub_object_data, get_sub_object_by_index, get_data_type_by_index, scout_index_data, delete_sub_object_data, get_object_count, get_sub_object_type, find_sub_object_data, OBJECT_COMPRESSION, get_sub_object_by_name, get_object_names, scout_name_data};
 use crate::util::write_any_tag;
 use std::marker::PhantomData;
 use crate::internal::keyscout::KeyScout;
+use crate::internal::memory::ODSMemory;
+use crate::internal::ODSInternal;
+use crate::key_path::IntoKeyPath;
+use crate::limits::Limit;
+use std::convert::TryFrom;
 
 pub type StringTag = Tag<String>;
 pub type IntTag = Tag<i32>;
@@ -16,7 +22,9 @@ pub type LongTag = Tag<i64>;
 pub type CharTag = Tag<char>;
 pub type ByteTag = Tag<u8>;
 pub type VecTag = Tag<Container<VectorContainer>>;
+pub type MapTag<V> = Tag<Container<MapContainer<V>>>;
 pub type ObjectTag = Tag<Container<ObjectContainer>>;
+pub type CompressedObjectTag = Tag<Container<CompressedObjectContainer>>;
 pub type AnyTag = Tag<Box<dyn Any>>;
 
 /*
@@ -26,12 +34,78 @@ pub type AnyTag = Tag<Box<dyn Any>>;
  */
 
 pub trait Taggable<T> {
-    fn as_tag(&self, name: String) -> Tag<T>;
-    fn process(tag_builder: TagBuilder) -> Option<Tag<T>>;
-    fn write_data(tag: Tag<T>, write_stream: &mut Stream);
+    fn as_tag(&self, name: String) -> Result<Tag<T>>;
+    fn process(tag_builder: TagBuilder) -> Result<Tag<T>>;
+    fn write_data(tag: Tag<T>, write_stream: &mut Stream) -> Result<()>;
     fn get_id() -> u8;
 }
 
+/// Check that the tag builder describes a tag with the specified id.
+fn check_data_type(tag_builder: &TagBuilder, id: u8) -> Result<()> {
+    if tag_builder.data_type != id as i32 {
+        return Err(OdsError::TypeMismatch {
+            expected: id,
+            found: tag_builder.data_type as u8
+        });
+    }
+    Ok(())
+}
+
+/// Edit the tags of a container as an in-memory document.
+fn edit_container<C, R, F>(container: &mut Container<C>, edit: F) -> Result<R>
+    where F: FnOnce(&mut ODSMemory) -> Result<R> {
+    let mut memory = ODSMemory::new(container.data.bytes());
+    memory.set_parse_limits(container.data.get_limits());
+    let output = edit(&mut memory)?;
+
+    container.data.clear();
+    container.data.write_vec(memory.into_bytes())?;
+    Ok(output)
+}
+
+/// Read the value of a container tag from a stream.
+/// The data of the container keeps the limits of the stream, with one less level of nesting left.
+///
+/// # Params
+/// read_stream: `&mut Stream` -> The stream to read from. (At the start of the value.)
+/// size: i32 -> The size of the value.
+/// compressed: bool -> If the value has to be decompressed. (For Compressed Object Tags.)
+///
+/// # Returns
+/// `OdsError::Truncated` if the value does not fit in the stream, or `OdsError::LimitExceeded` if the value
+/// is larger than the tag size limit or is nested too deep. This is checked before anything is allocated.
+fn read_container<T>(read_stream: &mut Stream, size: i32, compressed: bool) -> Result<Container<T>> {
+    let limits = read_stream.get_limits();
+    let inner_limits = limits.descend()?;
+    let size = usize::try_from(size).map_err(|_| OdsError::Truncated)?;
+    limits.check(Limit::TagSize, size)?;
+    if read_stream.position() as usize + size > read_stream.size() {
+        return Err(OdsError::Truncated);
+    }
+
+    let mut value = vec![0; size];
+    read_stream.read_vec(&mut value)?;
+    if compressed {
+        value = OBJECT_COMPRESSION.decompress_limited(&value, &limits)?;
+    }
+
+    let mut container = Container::new_with_data(value);
+    container.data.set_limits(inner_limits);
+    Ok(container)
+}
+
+/// Write the name of a tag (and its length) to a stream.
+///
+/// # Returns
+/// `OdsError::InvalidKey` if the name is too long to be stored.
+fn write_tag_name(temp_stream: &mut WriteStream, name: String) -> Result<()> {
+    if name.len() > i16::MAX as usize {
+        return Err(OdsError::InvalidKey(name));
+    }
+    temp_stream.write_i16(name.len() as i16)?;
+    temp_stream.write_string(name)
+}
+
 #[derive(Clone, Debug)]
 pub struct Container<T> {
     data: Stream,
@@ -58,6 +132,12 @@ impl<T> Container<T> {
     }
 }
 
+impl<T> Default for Container<T> {
+    fn default() -> Self {
+        Container::new()
+    }
+}
+
 // trait CloneableAny: Any + DynClone + Downcast {}
 // clone_trait_object!(CloneableAny);
 // downcast!(CloneableAny);
@@ -101,11 +181,11 @@ impl<T: Taggable<T>> Tag<T>{
     =============================
  */
 impl Tag<String> {
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> StringTag {
-        let string = read_stream.read_string(size as u64);
+    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> Result<StringTag> {
+        let string = read_stream.read_string(size as u64)?;
         self.value = string;
 
-        self
+        Ok(self)
     }
 
     pub fn get_id(&self) -> u8 {
@@ -114,27 +194,24 @@ impl Tag<String> {
 }
 
 impl Taggable<String> for String {
-    fn as_tag(&self, name: String) -> StringTag {
-        StringTag::new(name, self.clone())
+    fn as_tag(&self, name: String) -> Result<StringTag> {
+        Ok(StringTag::new(name, self.clone()))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<StringTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 1 {
-            return Some(StringTag::new(name, String::new()).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<StringTag> {
+        check_data_type(&tag_builder, 1)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        StringTag::new(tag_builder.name, String::new()).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<String>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<String>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name);
-        temp_stream.write_string(tag.value);
+        write_tag_name(&mut temp_stream, tag.name)?;
+        temp_stream.write_string(tag.value)?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -148,10 +225,10 @@ impl Taggable<String> for String {
     =============================
  */
 impl Tag<i32> {
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> IntTag {
-        let int = read_stream.read_i32();
+    pub fn create_from_data(mut self, mut read_stream: Stream, _size: i32) -> Result<IntTag> {
+        let int = read_stream.read_i32()?;
         self.value = int;
-        self
+        Ok(self)
     }
 
     pub fn get_id(&self) -> u8 {
@@ -160,27 +237,24 @@ impl Tag<i32> {
 }
 
 impl Taggable<i32> for i32 {
-    fn as_tag(&self, name: String) -> IntTag {
-        IntTag::new(name, self.clone())
+    fn as_tag(&self, name: String) -> Result<IntTag> {
+        Ok(IntTag::new(name, *self))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<IntTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 2 {
-            return Some(IntTag::new(name, -1).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<IntTag> {
+        check_data_type(&tag_builder, 2)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        IntTag::new(tag_builder.name, -1).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<i32>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<i32>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name);
-        temp_stream.write_i32(tag.value);
+        write_tag_name(&mut temp_stream, tag.name)?;
+        temp_stream.write_i32(tag.value)?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -194,10 +268,10 @@ impl Taggable<i32> for i32 {
     =============================
  */
 impl Tag<f32> {
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> FloatTag {
-        let float = read_stream.read_f32();
+    pub fn create_from_data(mut self, mut read_stream: Stream, _size: i32) -> Result<FloatTag> {
+        let float = read_stream.read_f32()?;
         self.value = float;
-        self
+        Ok(self)
     }
 
     pub fn get_id(&self) -> u8 {
@@ -206,31 +280,28 @@ impl Tag<f32> {
 }
 
 impl Taggable<f32> for f32 {
-    fn as_tag(&self, name: String) -> FloatTag {
-        FloatTag::new(name, self.clone())
+    fn as_tag(&self, name: String) -> Result<FloatTag> {
+        Ok(FloatTag::new(name, *self))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<FloatTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 3 {
-            return Some(FloatTag::new(name, -1.0).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<FloatTag> {
+        check_data_type(&tag_builder, 3)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        FloatTag::new(tag_builder.name, -1.0).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<f32>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<f32>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name);
-        temp_stream.write_f32(tag.value);
+        write_tag_name(&mut temp_stream, tag.name)?;
+        temp_stream.write_f32(tag.value)?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
-        4
+        3
     }
 }
 
@@ -240,10 +311,10 @@ impl Taggable<f32> for f32 {
     =============================
  */
 impl Tag<f64> {
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> DoubleTag {
-        let double = read_stream.read_f64();
+    pub fn create_from_data(mut self, mut read_stream: Stream, _size: i32) -> Result<DoubleTag> {
+        let double = read_stream.read_f64()?;
         self.value = double;
-        self
+        Ok(self)
     }
 
     pub fn get_id(&self) -> u8 {
@@ -252,27 +323,24 @@ impl Tag<f64> {
 }
 
 impl Taggable<f64> for f64 {
-    fn as_tag(&self, name: String) -> DoubleTag {
-        DoubleTag::new(name, self.clone())
+    fn as_tag(&self, name: String) -> Result<DoubleTag> {
+        Ok(DoubleTag::new(name, *self))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<DoubleTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 4 {
-            return Some(DoubleTag::new(name, -1.0).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<DoubleTag> {
+        check_data_type(&tag_builder, 4)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        DoubleTag::new(tag_builder.name, -1.0).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<f64>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<f64>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name);
-        temp_stream.write_f64(tag.value);
+        write_tag_name(&mut temp_stream, tag.name)?;
+        temp_stream.write_f64(tag.value)?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -286,10 +354,10 @@ impl Taggable<f64> for f64 {
     =============================
  */
 impl Tag<i16> {
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> ShortTag {
-        let short = read_stream.read_i16();
+    pub fn create_from_data(mut self, mut read_stream: Stream, _size: i32) -> Result<ShortTag> {
+        let short = read_stream.read_i16()?;
         self.value = short;
-        self
+        Ok(self)
     }
 
     pub fn get_id(&self) -> u8 {
@@ -298,27 +366,24 @@ impl Tag<i16> {
 }
 
 impl Taggable<i16> for i16 {
-    fn as_tag(&self, name: String) -> ShortTag {
-        ShortTag::new(name, self.clone())
+    fn as_tag(&self, name: String) -> Result<ShortTag> {
+        Ok(ShortTag::new(name, *self))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<ShortTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 5 {
-            return Some(ShortTag::new(name, -1).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<ShortTag> {
+        check_data_type(&tag_builder, 5)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        ShortTag::new(tag_builder.name, -1).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<i16>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<i16>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name);
-        temp_stream.write_i16(tag.value);
+        write_tag_name(&mut temp_stream, tag.name)?;
+        temp_stream.write_i16(tag.value)?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -332,10 +397,10 @@ impl Taggable<i16> for i16 {
     =============================
  */
 impl Tag<i64> {
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> LongTag {
-        let long = read_stream.read_i64();
+    pub fn create_from_data(mut self, mut read_stream: Stream, _size: i32) -> Result<LongTag> {
+        let long = read_stream.read_i64()?;
         self.value = long;
-        self
+        Ok(self)
     }
 
     pub fn get_id(&self) -> u8 {
@@ -344,27 +409,24 @@ impl Tag<i64> {
 }
 
 impl Taggable<i64> for i64 {
-    fn as_tag(&self, name: String) -> LongTag {
-        LongTag::new(name, self.clone())
+    fn as_tag(&self, name: String) -> Result<LongTag> {
+        Ok(LongTag::new(name, *self))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<LongTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 6 {
-            return Some(LongTag::new(name, -1).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<LongTag> {
+        check_data_type(&tag_builder, 6)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        LongTag::new(tag_builder.name, -1).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<i64>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<i64>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name);
-        temp_stream.write_i64(tag.value);
+        write_tag_name(&mut temp_stream, tag.name)?;
+        temp_stream.write_i64(tag.value)?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -378,10 +440,10 @@ impl Taggable<i64> for i64 {
     =============================
  */
 impl Tag<char> {
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> CharTag {
-        let character = read_stream.read_char();
+    pub fn create_from_data(mut self, mut read_stream: Stream, _size: i32) -> Result<CharTag> {
+        let character = read_stream.read_char()?;
         self.value = character;
-        self
+        Ok(self)
     }
 
     pub fn get_id(&self) -> u8 {
@@ -390,27 +452,24 @@ impl Tag<char> {
 }
 
 impl Taggable<char> for char {
-    fn as_tag(&self, name: String) -> CharTag {
-        CharTag::new(name, self.clone())
+    fn as_tag(&self, name: String) -> Result<CharTag> {
+        Ok(CharTag::new(name, *self))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<CharTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 7 {
-            return Some(CharTag::new(name, ' ').create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<CharTag> {
+        check_data_type(&tag_builder, 7)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        CharTag::new(tag_builder.name, ' ').create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<char>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<char>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name);
-        temp_stream.write_char(tag.value);
+        write_tag_name(&mut temp_stream, tag.name)?;
+        temp_stream.write_char(tag.value)?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -424,10 +483,10 @@ impl Taggable<char> for char {
     =============================
  */
 impl Tag<u8> {
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> ByteTag {
-        let byte = read_stream.read();
+    pub fn create_from_data(mut self, mut read_stream: Stream, _size: i32) -> Result<ByteTag> {
+        let byte = read_stream.read()?;
         self.value = byte;
-        self
+        Ok(self)
     }
 
     pub fn get_id(&self) -> u8 {
@@ -436,27 +495,24 @@ impl Tag<u8> {
 }
 
 impl Taggable<u8> for u8 {
-    fn as_tag(&self, name: String) -> ByteTag {
-        ByteTag::new(name, self.clone())
+    fn as_tag(&self, name: String) -> Result<ByteTag> {
+        Ok(ByteTag::new(name, *self))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<ByteTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 8 {
-            return Some(ByteTag::new(name, 0).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<ByteTag> {
+        check_data_type(&tag_builder, 8)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        ByteTag::new(tag_builder.name, 0).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<u8>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<u8>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name);
-        temp_stream.write(tag.value);
+        write_tag_name(&mut temp_stream, tag.name)?;
+        temp_stream.write(tag.value)?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -474,73 +530,78 @@ pub struct VectorContainer;
 
 impl Tag<Container<VectorContainer>> {
 
-    pub fn from_vec(name: String, tags: &mut Vec<AnyTag>) -> VecTag {
+    pub fn from_vec(name: String, tags: &mut Vec<AnyTag>) -> Result<VecTag> {
         let mut write_stream = Stream::new_empty();
         for tag in tags.iter_mut() {
             tag.set_name("".to_string());
-            write_any_tag(tag, &mut write_stream);
+            write_any_tag(tag, &mut write_stream)?;
         }
 
         let container : Container<VectorContainer> = Container::new_with_data(write_stream.bytes());
 
-        VecTag {
+        Ok(VecTag {
             name,
             value: container,
             id: 9
-        }
+        })
     }
 
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> VecTag {
-        let mut vec = vec![0;size as usize];
-        read_stream.read_vec(&mut vec);
-        let data = Container::new_with_data(vec);
-
-        self.value = data;
-        self
+    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> Result<VecTag> {
+        self.value = read_container(&mut read_stream, size, false)?;
+        Ok(self)
     }
 
-    pub fn get<T: Taggable<T>>(&mut self, index: i32) -> Option<Tag<T>>{
+    pub fn get<T: Taggable<T>>(&mut self, index: i32) -> Result<Tag<T>>{
         self.value.data.set_position(0);
-        get_sub_object_by_index::<T>(self.value.clone().data, index)
+        get_sub_object_by_index::<T>(&mut self.value.data, index)
     }
 
-    pub fn add<T: Taggable<T>>(&mut self, mut tag: Tag<T>) {
+    pub fn add<T: Taggable<T>>(&mut self, mut tag: Tag<T>) -> Result<()> {
         self.value.data.goto_end();
         tag.set_name("".to_string());
-        T::write_data(tag, &mut self.value.data);
+        T::write_data(tag, &mut self.value.data)
     }
 
-    pub fn is_type<T: Taggable<T>>(&mut self, index: i32) -> bool {
+    pub fn is_type<T: Taggable<T>>(&mut self, index: i32) -> Result<bool> {
         self.value.data.set_position(0);
-        let id = get_data_type_by_index::<T>(self.value.clone().data, index);
+        let id = get_data_type_by_index::<T>(&mut self.value.data, index)?;
 
-        T::get_id() == id as u8
+        Ok(T::get_id() == id as u8)
     }
 
-    pub fn remove(&mut self, index: i32) {
+    /// Remove the tag at the specified index.
+    ///
+    /// # Returns
+    /// If a tag was removed.
+    pub fn remove(&mut self, index: i32) -> Result<bool> {
         self.value.data.set_position(0);
         let mut key_scout = KeyScout::new();
-        scout_index_data(&mut self.value.data, index, &mut key_scout);
+        scout_index_data(&mut self.value.data, index, &mut key_scout)?;
 
         if key_scout.get_end().is_none() {
-            return;
+            return Ok(false);
         }
 
         let mut bytes = self.value.data.bytes();
-        delete_sub_object_data(&mut bytes, &mut key_scout);
+        delete_sub_object_data(&mut bytes, &mut key_scout)?;
 
         self.value.data.clear();
-        self.value.data.write_vec(bytes);
+        self.value.data.write_vec(bytes)?;
+        Ok(true)
     }
 
     pub fn remove_all(&mut self) {
         self.value.data.clear();
     }
 
-    pub fn len(&mut self) -> usize {
+    pub fn len(&mut self) -> Result<usize> {
         self.value.data.set_position(0);
 
-        get_object_count(self.value.clone().data)
+        get_object_count(&mut self.value.data)
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.value.data.size() == 0
     }
 
     pub fn get_id(&self) -> u8 {
@@ -549,29 +610,25 @@ impl Tag<Container<VectorContainer>> {
 }
 
 impl Taggable<Container<VectorContainer>> for Container<VectorContainer> {
-    fn as_tag(&self, name: String) -> VecTag {
-        // VecTag::new(name, self.clone())
-        panic!("Cannot create a VecTag.");
+    fn as_tag(&self, name: String) -> Result<VecTag> {
+        Ok(VecTag::new(name, self.clone()))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<VecTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 9 {
-            return Some(VecTag::new(name, Container::new()).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<VecTag> {
+        check_data_type(&tag_builder, 9)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        VecTag::new(tag_builder.name, Container::new()).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<Container<VectorContainer>>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<Container<VectorContainer>>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name.clone());
+        write_tag_name(&mut temp_stream, tag.name.clone())?;
 
-        temp_stream.write_vec(tag.get_value().clone().get_data().bytes());
+        temp_stream.write_vec(tag.get_value().clone().get_data().bytes())?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -579,6 +636,165 @@ impl Taggable<Container<VectorContainer>> for Container<VectorContainer> {
     }
 }
 
+/*
+    =============================
+             Map Tag
+    =============================
+ */
+/// The value of a Map Tag. Every value of the map is stored as a tag with the type `V`, the name
+/// of the tag is the key of the entry.
+///
+/// A Map Tag that is read as an AnyTag has the value type `Box<dyn Any>`.
+/// Use `MapTag::convert` to turn it into a Map Tag of a specific type.
+#[derive(Debug)]
+pub struct MapContainer<V> {
+    phantom: PhantomData<V>
+}
+
+// Implemented by hand so the value type does not need to be Clone. (Ex: `Box<dyn Any>`.)
+impl<V> Clone for MapContainer<V> {
+    fn clone(&self) -> Self {
+        MapContainer {
+            phantom: PhantomData
+        }
+    }
+}
+
+impl<V: Taggable<V>> Tag<Container<MapContainer<V>>> {
+
+    /// Create a Map Tag from a HashMap.
+    pub fn from_map(name: String, map: HashMap<String, V>) -> Result<MapTag<V>> {
+        let mut write_stream = Stream::new_empty();
+        for (key, value) in map {
+            V::write_data(Tag::new(key, value), &mut write_stream)?;
+        }
+
+        let container : Container<MapContainer<V>> = Container::new_with_data(write_stream.bytes());
+
+        Ok(MapTag {
+            name,
+            value: container,
+            id: 10
+        })
+    }
+
+    /// Convert the Map Tag into a HashMap.
+    pub fn to_map(&mut self) -> Result<HashMap<String, V>> {
+        let mut output = HashMap::new();
+        for key in self.keys()? {
+            let tag = self.get(key.clone())?;
+            output.insert(key, tag.value);
+        }
+
+        Ok(output)
+    }
+
+    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> Result<MapTag<V>> {
+        self.value = read_container(&mut read_stream, size, false)?;
+        Ok(self)
+    }
+
+    /// Get the value of an entry as a tag. The name of the tag is the key.
+    /// **Note:** The key is not split on dots, it is the exact key of the entry.
+    pub fn get(&mut self, key: String) -> Result<Tag<V>> {
+        self.value.data.set_position(0);
+        get_sub_object_by_name::<V>(&mut self.value.data, key)
+    }
+
+    /// Insert an entry into the map. An existing entry with the same key is replaced.
+    pub fn insert(&mut self, key: String, value: V) -> Result<()> {
+        self.remove(key.clone())?;
+        self.value.data.goto_end();
+        V::write_data(Tag::new(key, value), &mut self.value.data)
+    }
+
+    /// Remove the entry with the specified key.
+    ///
+    /// # Returns
+    /// If an entry was removed.
+    pub fn remove(&mut self, key: String) -> Result<bool> {
+        self.value.data.set_position(0);
+        let mut key_scout = KeyScout::new();
+        scout_name_data(&mut self.value.data, key, &mut key_scout)?;
+
+        if key_scout.get_end().is_none() {
+            return Ok(false);
+        }
+
+        let mut bytes = self.value.data.bytes();
+        delete_sub_object_data(&mut bytes, &mut key_scout)?;
+
+        self.value.data.clear();
+        self.value.data.write_vec(bytes)?;
+        Ok(true)
+    }
+
+    pub fn contains_key(&mut self, key: String) -> Result<bool> {
+        Ok(self.keys()?.contains(&key))
+    }
+
+    /// Get the keys of all of the entries in the map.
+    pub fn keys(&mut self) -> Result<Vec<String>> {
+        self.value.data.set_position(0);
+        get_object_names(&mut self.value.data)
+    }
+
+    /// Convert the Map Tag into a Map Tag with a different value type.
+    /// The entries are not checked, using `get` on an entry with the wrong type returns an `OdsError::TypeMismatch`.
+    pub fn convert<U: Taggable<U>>(self) -> MapTag<U> {
+        MapTag {
+            name: self.name,
+            value: Container::new_with_data(self.value.data.clone().bytes()),
+            id: 10
+        }
+    }
+
+    pub fn remove_all(&mut self) {
+        self.value.data.clear();
+    }
+
+    pub fn len(&mut self) -> Result<usize> {
+        self.value.data.set_position(0);
+
+        get_object_count(&mut self.value.data)
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.value.data.size() == 0
+    }
+
+    pub fn get_id(&self) -> u8 {
+        10
+    }
+}
+
+impl<V: Taggable<V>> Taggable<Container<MapContainer<V>>> for Container<MapContainer<V>> {
+    fn as_tag(&self, name: String) -> Result<MapTag<V>> {
+        Ok(MapTag::new(name, self.clone()))
+    }
+
+    fn process(mut tag_builder: TagBuilder) -> Result<MapTag<V>> {
+        check_data_type(&tag_builder, 10)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        MapTag::new(tag_builder.name, Container::new()).create_from_data(value_bytes, tag_builder.value_length)
+    }
+
+    fn write_data(tag: Tag<Container<MapContainer<V>>>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
+        let mut temp_stream = WriteStream::new();
+        write_tag_name(&mut temp_stream, tag.name.clone())?;
+
+        temp_stream.write_vec(tag.get_value().clone().get_data().bytes())?;
+
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
+    }
+
+    fn get_id() -> u8 {
+        10
+    }
+}
+
 /*
     =============================
              Object Tag
@@ -589,75 +805,86 @@ pub struct ObjectContainer;
 
 impl Tag<Container<ObjectContainer>> {
 
-    pub fn from_vec(name: String, tags: Vec<AnyTag>) -> ObjectTag {
+    pub fn from_vec(name: String, tags: Vec<AnyTag>) -> Result<ObjectTag> {
         let mut write_stream = Stream::new_empty();
         for tag in tags.iter() {
-            write_any_tag(tag, &mut write_stream);
+            write_any_tag(tag, &mut write_stream)?;
         }
 
         let container : Container<ObjectContainer> = Container::new_with_data(write_stream.bytes());
 
-        ObjectTag {
+        Ok(ObjectTag {
             name,
             value: container,
-            id: 9
-        }
+            id: 11
+        })
     }
 
-    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> ObjectTag {
-        let mut vec = vec![0;size as usize];
-        read_stream.read_vec(&mut vec);
-        let data = Container::new_with_data(vec);
-
-        self.value = data;
-        self
+    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> Result<ObjectTag> {
+        self.value = read_container(&mut read_stream, size, false)?;
+        Ok(self)
     }
 
-    pub fn get<T: Taggable<T>>(&mut self, key: String) -> Option<Tag<T>>{
+    /// Get the tag with the specified key. See `ObjectDataStructure::get`.
+    pub fn get<T: Taggable<T>>(&mut self, key: impl IntoKeyPath) -> Result<Tag<T>>{
         self.value.data.set_position(0);
-        get_sub_object_data::<T>(self.value.clone().data, key)
+        get_sub_object_data::<T>(&mut self.value.data, &key.into_key_path()?)
     }
 
-    pub fn add<T: Taggable<T>>(&mut self, mut tag: Tag<T>) {
+    pub fn add<T: Taggable<T>>(&mut self, tag: Tag<T>) -> Result<()> {
         self.value.data.goto_end();
-        T::write_data(tag, &mut self.value.data);
+        T::write_data(tag, &mut self.value.data)
     }
 
-    pub fn is_type<T: Taggable<T>>(&mut self, key: String) -> bool {
+    pub fn is_type<T: Taggable<T>>(&mut self, key: impl IntoKeyPath) -> Result<bool> {
         self.value.data.set_position(0);
-        let id = get_sub_object_type::<T>(self.value.clone().data, key);
+        let id = get_sub_object_type::<T>(&mut self.value.data, &key.into_key_path()?)?;
 
-        T::get_id() == id as u8
+        Ok(T::get_id() == id as u8)
     }
 
-    pub fn has_tag(&mut self, key: String) -> bool {
-        find_sub_object_data(self.value.clone().data, key)
-    }
-
-    pub fn remove(&mut self, key: String) {
+    pub fn has_tag(&mut self, key: impl IntoKeyPath) -> Result<bool> {
         self.value.data.set_position(0);
-        let mut key_scout = KeyScout::new();
-        scout_object_data(&mut self.value.data, key, &mut key_scout);
+        find_sub_object_data(&mut self.value.data, &key.into_key_path()?)
+    }
 
-        if key_scout.get_end().is_none() {
-            return;
-        }
+    /// Remove the tag with the specified key.
+    ///
+    /// # Returns
+    /// If a tag was removed.
+    pub fn remove(&mut self, key: impl IntoKeyPath) -> Result<bool> {
+        let key = key.into_key_path()?;
+        edit_container(&mut self.value, |memory| memory.delete(key))
+    }
 
-        let mut bytes = self.value.data.bytes();
-        delete_sub_object_data(&mut bytes, &mut key_scout);
+    /// Replace the tag with the specified key. See `ObjectDataStructure::replace_data`.
+    ///
+    /// # Returns
+    /// If the tag was found.
+    pub fn replace_data<T: Taggable<T>>(&mut self, key: impl IntoKeyPath, replacement: Tag<T>) -> Result<bool> {
+        let key = key.into_key_path()?;
+        edit_container(&mut self.value, |memory| memory.replace_data(key, replacement))
+    }
 
-        self.value.data.clear();
-        self.value.data.write_vec(bytes);
+    /// Set the tag with the specified key, creating any objects that are missing along the key.
+    /// See `ObjectDataStructure::set`.
+    pub fn set<T: Taggable<T>>(&mut self, key: impl IntoKeyPath, value: Option<Tag<T>>) -> Result<()> {
+        let key = key.into_key_path()?;
+        edit_container(&mut self.value, |memory| memory.set(key, value))
     }
 
     pub fn remove_all(&mut self) {
         self.value.data.clear();
     }
 
-    pub fn len(&mut self) -> usize {
+    pub fn len(&mut self) -> Result<usize> {
         self.value.data.set_position(0);
 
-        get_object_count(self.value.clone().data)
+        get_object_count(&mut self.value.data)
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.value.data.size() == 0
     }
 
     pub fn get_id(&self) -> u8 {
@@ -666,28 +893,25 @@ impl Tag<Container<ObjectContainer>> {
 }
 
 impl Taggable<Container<ObjectContainer>> for Container<ObjectContainer> {
-    fn as_tag(&self, name: String) -> ObjectTag {
-        panic!("Cannot create an Object Tag.");
+    fn as_tag(&self, name: String) -> Result<ObjectTag> {
+        Ok(ObjectTag::new(name, self.clone()))
     }
 
-    fn process(tag_builder: TagBuilder) -> Option<ObjectTag> {
-        let name = tag_builder.name;
-        if tag_builder.data_type == 11 {
-            return Some(ObjectTag::new(name, Container::new()).create_from_data(tag_builder.value_bytes.clone().unwrap(), tag_builder.value_length));
-        }
-        Option::None
+    fn process(mut tag_builder: TagBuilder) -> Result<ObjectTag> {
+        check_data_type(&tag_builder, 11)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        ObjectTag::new(tag_builder.name, Container::new()).create_from_data(value_bytes, tag_builder.value_length)
     }
 
-    fn write_data(tag: Tag<Container<ObjectContainer>>, write_stream: &mut Stream) {
-        write_stream.write(tag.get_id());
+    fn write_data(tag: Tag<Container<ObjectContainer>>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
         let mut temp_stream = WriteStream::new();
-        temp_stream.write_i16(tag.name.len() as i16);
-        temp_stream.write_string(tag.name.clone());
+        write_tag_name(&mut temp_stream, tag.name.clone())?;
 
-        temp_stream.write_vec(tag.get_value().clone().get_data().bytes());
+        temp_stream.write_vec(tag.get_value().clone().get_data().bytes())?;
 
-        write_stream.write_i32(temp_stream.size() as i32);
-        write_stream.write_vec(temp_stream.bytes());
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
     }
 
     fn get_id() -> u8 {
@@ -698,14 +922,136 @@ impl Taggable<Container<ObjectContainer>> for Container<ObjectContainer> {
 
 /*
     =============================
-               Any Tag
+        Compressed Object Tag
     =============================
  */
-impl Tag<Box<dyn Any>> {
-    pub fn create_from_data(mut self, mut read_stream: ReadStream, size: i32) -> StringTag {
-        panic!("This tag cannot be created.");
+/// The value of a Compressed Object Tag is a block of tags that is compressed using GZIP.
+/// The tags are kept decompressed in memory, they are only compressed when the tag is written.
+#[derive(Clone, Debug)]
+pub struct CompressedObjectContainer;
+
+impl Tag<Container<CompressedObjectContainer>> {
+
+    pub fn from_vec(name: String, tags: Vec<AnyTag>) -> Result<CompressedObjectTag> {
+        let mut write_stream = Stream::new_empty();
+        for tag in tags.iter() {
+            write_any_tag(tag, &mut write_stream)?;
+        }
+
+        let container : Container<CompressedObjectContainer> = Container::new_with_data(write_stream.bytes());
+
+        Ok(CompressedObjectTag {
+            name,
+            value: container,
+            id: 12
+        })
+    }
+
+    pub fn create_from_data(mut self, mut read_stream: Stream, size: i32) -> Result<CompressedObjectTag> {
+        self.value = read_container(&mut read_stream, size, true)?;
+        Ok(self)
+    }
+
+    /// Get the tag with the specified key. See `ObjectDataStructure::get`.
+    pub fn get<T: Taggable<T>>(&mut self, key: impl IntoKeyPath) -> Result<Tag<T>>{
+        self.value.data.set_position(0);
+        get_sub_object_data::<T>(&mut self.value.data, &key.into_key_path()?)
+    }
+
+    pub fn add<T: Taggable<T>>(&mut self, tag: Tag<T>) -> Result<()> {
+        self.value.data.goto_end();
+        T::write_data(tag, &mut self.value.data)
+    }
+
+    pub fn is_type<T: Taggable<T>>(&mut self, key: impl IntoKeyPath) -> Result<bool> {
+        self.value.data.set_position(0);
+        let id = get_sub_object_type::<T>(&mut self.value.data, &key.into_key_path()?)?;
+
+        Ok(T::get_id() == id as u8)
+    }
+
+    pub fn has_tag(&mut self, key: impl IntoKeyPath) -> Result<bool> {
+        self.value.data.set_position(0);
+        find_sub_object_data(&mut self.value.data, &key.into_key_path()?)
+    }
+
+    /// Remove the tag with the specified key.
+    ///
+    /// # Returns
+    /// If a tag was removed.
+    pub fn remove(&mut self, key: impl IntoKeyPath) -> Result<bool> {
+        let key = key.into_key_path()?;
+        edit_container(&mut self.value, |memory| memory.delete(key))
+    }
+
+    /// Replace the tag with the specified key. See `ObjectDataStructure::replace_data`.
+    ///
+    /// # Returns
+    /// If the tag was found.
+    pub fn replace_data<T: Taggable<T>>(&mut self, key: impl IntoKeyPath, replacement: Tag<T>) -> Result<bool> {
+        let key = key.into_key_path()?;
+        edit_container(&mut self.value, |memory| memory.replace_data(key, replacement))
     }
 
+    /// Set the tag with the specified key, creating any objects that are missing along the key.
+    /// See `ObjectDataStructure::set`.
+    pub fn set<T: Taggable<T>>(&mut self, key: impl IntoKeyPath, value: Option<Tag<T>>) -> Result<()> {
+        let key = key.into_key_path()?;
+        edit_container(&mut self.value, |memory| memory.set(key, value))
+    }
+
+    pub fn remove_all(&mut self) {
+        self.value.data.clear();
+    }
+
+    pub fn len(&mut self) -> Result<usize> {
+        self.value.data.set_position(0);
+
+        get_object_count(&mut self.value.data)
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.value.data.size() == 0
+    }
+
+    pub fn get_id(&self) -> u8 {
+        12
+    }
+}
+
+impl Taggable<Container<CompressedObjectContainer>> for Container<CompressedObjectContainer> {
+    fn as_tag(&self, name: String) -> Result<CompressedObjectTag> {
+        Ok(CompressedObjectTag::new(name, self.clone()))
+    }
+
+    fn process(mut tag_builder: TagBuilder) -> Result<CompressedObjectTag> {
+        check_data_type(&tag_builder, 12)?;
+        let value_bytes = tag_builder.take_value_bytes()?;
+        CompressedObjectTag::new(tag_builder.name, Container::new()).create_from_data(value_bytes, tag_builder.value_length)
+    }
+
+    fn write_data(tag: Tag<Container<CompressedObjectContainer>>, write_stream: &mut Stream) -> Result<()> {
+        write_stream.write(tag.get_id())?;
+        let mut temp_stream = WriteStream::new();
+        write_tag_name(&mut temp_stream, tag.name.clone())?;
+
+        temp_stream.write_vec(OBJECT_COMPRESSION.compress(&tag.get_value().clone().get_data().bytes())?)?;
+
+        write_stream.write_i32(temp_stream.size() as i32)?;
+        write_stream.write_vec(temp_stream.bytes())
+    }
+
+    fn get_id() -> u8 {
+        12
+    }
+}
+
+/*
+    =============================
+               Any Tag
+    =============================
+ */
+impl Tag<Box<dyn Any>> {
     pub fn from_tag<T: 'static + Taggable<T>>(tag: Tag<T>) -> AnyTag {
         Self {
             name: tag.name,
@@ -714,14 +1060,48 @@ impl Tag<Box<dyn Any>> {
         }
     }
 
-    pub fn downcast_any_tag<T: 'static + Clone + Taggable<T>>(&self) -> Tag<T> {
-        Tag::<T>::new(self.name.clone(), (*self.value.downcast_ref::<T>().unwrap()).clone())
+    /// Convert the AnyTag back into a tag of the specified type.
+    ///
+    /// # Returns
+    /// `OdsError::TypeMismatch` if the AnyTag does not hold a value of type T.
+    pub fn downcast_any_tag<T: 'static + Clone + Taggable<T>>(&self) -> Result<Tag<T>> {
+        match self.value.downcast_ref::<T>() {
+            Some(value) => Ok(Tag::<T>::new(self.name.clone(), value.clone())),
+            None => Err(OdsError::TypeMismatch {
+                expected: T::get_id(),
+                found: self.id
+            })
+        }
     }
 
     // pub fn downcast_any_tag_noclone<T: 'static + Taggable<T>>(&self) -> Tag<T> {
     //     Tag::<T>::new(self.name.clone(), self.value.downcast::<T>().unwrap().to_owned())
     // }
 
+    /// Convert an AnyTag that holds a Map Tag into a Map Tag with the value type `Box<dyn Any>`.
+    /// This works for Map Tags with any of the built in value types.
+    ///
+    /// # Returns
+    /// `OdsError::TypeMismatch` if the AnyTag does not hold a Map Tag.
+    pub fn downcast_any_map_tag(&self) -> Result<MapTag<Box<dyn Any>>> {
+        macro_rules! downcast_map {
+            ($($value_type:ty),*) => {
+                $(
+                    if let Some(value) = self.value.downcast_ref::<Container<MapContainer<$value_type>>>() {
+                        return Ok(MapTag::new(self.name.clone(), Container::new_with_data(value.data.clone().bytes())));
+                    }
+                )*
+            }
+        }
+        downcast_map!(Box<dyn Any>, String, i32, f32, f64, i16, i64, char, u8, Container<VectorContainer>,
+            Container<ObjectContainer>, Container<CompressedObjectContainer>);
+
+        Err(OdsError::TypeMismatch {
+            expected: 10,
+            found: self.id
+        })
+    }
+
     pub fn is_tag<T: 'static + Taggable<T>>(&self) -> bool {
         self.value.is::<T>()
     }
@@ -732,47 +1112,45 @@ impl Tag<Box<dyn Any>> {
 }
 
 impl Taggable<Box<dyn Any>> for Box<dyn Any> {
-    fn as_tag(&self, name: String) -> AnyTag {
-        panic!("This operation is not supported for AnyTags!");
-    }
-
-    fn process(tag_builder: TagBuilder) -> Option<AnyTag> {
-        if tag_builder.data_type == 1 {
-            return Some(AnyTag::from_tag::<String>(String::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 2 {
-            return Some(AnyTag::from_tag::<i32>(i32::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 3 {
-            return Some(AnyTag::from_tag::<f32>(f32::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 4 {
-            return Some(AnyTag::from_tag::<f64>(f64::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 5 {
-            return Some(AnyTag::from_tag::<i16>(i16::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 6 {
-            return Some(AnyTag::from_tag::<i64>(i64::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 7 {
-            return Some(AnyTag::from_tag::<char>(char::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 8 {
-            return Some(AnyTag::from_tag::<u8>(u8::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 9 {
-            type T = Container<VectorContainer>;
-            return Some(AnyTag::from_tag::<Container<VectorContainer>>(T::process(tag_builder.clone()).unwrap()));
-        }
-        else if tag_builder.data_type == 11 {
-            type T = Container<ObjectContainer>;
-            return Some(AnyTag::from_tag::<Container<ObjectContainer>>(T::process(tag_builder.clone()).unwrap()));
+    /// An AnyTag cannot be made from a `Box<dyn Any>`, since the value cannot be copied.
+    ///
+    /// # Returns
+    /// Always `OdsError::Unsupported`.
+    fn as_tag(&self, _name: String) -> Result<AnyTag> {
+        Err(OdsError::Unsupported("as_tag is not supported for AnyTags".to_string()))
+    }
+
+    fn process(tag_builder: TagBuilder) -> Result<AnyTag> {
+        match tag_builder.data_type {
+            1 => Ok(AnyTag::from_tag::<String>(String::process(tag_builder)?)),
+            2 => Ok(AnyTag::from_tag::<i32>(i32::process(tag_builder)?)),
+            3 => Ok(AnyTag::from_tag::<f32>(f32::process(tag_builder)?)),
+            4 => Ok(AnyTag::from_tag::<f64>(f64::process(tag_builder)?)),
+            5 => Ok(AnyTag::from_tag::<i16>(i16::process(tag_builder)?)),
+            6 => Ok(AnyTag::from_tag::<i64>(i64::process(tag_builder)?)),
+            7 => Ok(AnyTag::from_tag::<char>(char::process(tag_builder)?)),
+            8 => Ok(AnyTag::from_tag::<u8>(u8::process(tag_builder)?)),
+            9 => {
+                type T = Container<VectorContainer>;
+                Ok(AnyTag::from_tag::<Container<VectorContainer>>(T::process(tag_builder)?))
+            },
+            10 => {
+                type T = Container<MapContainer<Box<dyn Any>>>;
+                Ok(AnyTag::from_tag::<Container<MapContainer<Box<dyn Any>>>>(T::process(tag_builder)?))
+            },
+            11 => {
+                type T = Container<ObjectContainer>;
+                Ok(AnyTag::from_tag::<Container<ObjectContainer>>(T::process(tag_builder)?))
+            },
+            12 => {
+                type T = Container<CompressedObjectContainer>;
+                Ok(AnyTag::from_tag::<Container<CompressedObjectContainer>>(T::process(tag_builder)?))
+            },
+            id => Err(OdsError::UnknownTag(id as u8))
         }
-        Option::None
     }
 
-    fn write_data(tag: Tag<Box<dyn Any>>, write_stream: &mut Stream) {
+    fn write_data(tag: Tag<Box<dyn Any>>, write_stream: &mut Stream) -> Result<()> {
         write_any_tag(&tag, write_stream)
     }
 
@@ -1,4 +1,6 @@
 pub mod general;
+pub mod value;
+pub mod tag_ref;
 
 #[cfg(any(feature = "container_types", test))]
 pub mod container_types;
\ No newline at end of file
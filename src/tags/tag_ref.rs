@@ -0,0 +1,285 @@
+//! Read tags straight from encoded bytes without copying them.
+//!
+//! A `TagRef` borrows its name and value from the bytes it was read from. Numbers are decoded when they are asked for
+//! and the children of containers are read one at a time, so reading a few fields of a large document does not allocate.
+//!
+//! # Example
+//! ```rust
+//! use object_data_structure::ods::ObjectDataStructure;
+//! use object_data_structure::tags::general::{AnyTag, IntTag, ObjectTag};
+//! use object_data_structure::tags::tag_ref::TagRef;
+//!
+//! let mut ods = ObjectDataStructure::new_memory(Vec::new());
+//! let year = AnyTag::from_tag(IntTag::new("Year".to_string(), 2019));
+//! ods.append(ObjectTag::from_vec("Car".to_string(), vec![year])?)?;
+//!
+//! let year = TagRef::iter(ods.get_bytes()).get("Car.Year")?.unwrap();
+//! assert_eq!(year.name(), "Year");
+//! assert_eq!(year.as_i32()?, 2019);
+//! # Ok::<(), object_data_structure::error::OdsError>(())
+//! ```
+use std::convert::TryFrom;
+use std::io;
+
+use crate::error::{OdsError, Result};
+use crate::internal::internal_utils::OBJECT_COMPRESSION;
+use crate::internal::tag_builder::TagBuilder;
+use crate::io::streams::{StandardIO, Stream};
+use crate::key_path::{IntoKeyPath, KeySegment};
+use crate::limits::{Limit, ParseLimits};
+use crate::tags::general::{Tag, Taggable};
+use crate::tags::value::OdsValue;
+
+/// The size of the id, the data size and the name length of a tag.
+const HEADER_SIZE: usize = 7;
+
+/// A tag that is borrowed from encoded bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TagRef<'a> {
+    id: u8,
+    name: &'a str,
+    value: &'a [u8],
+    limits: ParseLimits
+}
+
+impl<'a> TagRef<'a> {
+    /// Iterate over the tags in encoded bytes. (Ex: The bytes of an ODS file.)
+    pub fn iter(data: &'a [u8]) -> TagIter<'a> {
+        TagIter::new(data, ParseLimits::default())
+    }
+
+    /// Get the id of the tag type.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Get the encoded value of the tag. (Without the id, size and name.)
+    pub fn value_bytes(&self) -> &'a [u8] {
+        self.value
+    }
+
+    /// Get the value of a String Tag.
+    pub fn as_str(&self) -> Result<&'a str> {
+        self.check_id(1)?;
+        std::str::from_utf8(self.value).map_err(|err| OdsError::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
+    }
+
+    /// Get the value of an Int Tag.
+    pub fn as_i32(&self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.fixed_value(2)?))
+    }
+
+    /// Get the value of a Float Tag.
+    pub fn as_f32(&self) -> Result<f32> {
+        Ok(f32::from_be_bytes(self.fixed_value(3)?))
+    }
+
+    /// Get the value of a Double Tag.
+    pub fn as_f64(&self) -> Result<f64> {
+        Ok(f64::from_be_bytes(self.fixed_value(4)?))
+    }
+
+    /// Get the value of a Short Tag.
+    pub fn as_i16(&self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.fixed_value(5)?))
+    }
+
+    /// Get the value of a Long Tag.
+    pub fn as_i64(&self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.fixed_value(6)?))
+    }
+
+    /// Get the value of a Char Tag.
+    pub fn as_char(&self) -> Result<char> {
+        Ok(self.fixed_value::<1>(7)?[0] as char)
+    }
+
+    /// Get the value of a Byte Tag.
+    pub fn as_u8(&self) -> Result<u8> {
+        Ok(self.fixed_value::<1>(8)?[0])
+    }
+
+    /// Iterate over the children of a Vec, Map or Object Tag.
+    ///
+    /// # Returns
+    /// `OdsError::TypeMismatch` if the tag cannot hold other tags. The value of a Compressed Object Tag has to be
+    /// decompressed first, which cannot be borrowed. (See `decompress`.)
+    pub fn children(&self) -> Result<TagIter<'a>> {
+        if !(9..=11).contains(&self.id) {
+            return Err(OdsError::TypeMismatch {
+                expected: 11,
+                found: self.id
+            });
+        }
+        Ok(TagIter::new(self.value, self.limits.descend()?))
+    }
+
+    /// Get a child of the tag using a key. (See `TagIter::get`.)
+    pub fn get(&self, key: impl IntoKeyPath) -> Result<Option<TagRef<'a>>> {
+        self.children()?.get(key)
+    }
+
+    /// Decompress the value of a Compressed Object Tag. The children can then be read with `TagRef::iter`.
+    pub fn decompress(&self) -> Result<Vec<u8>> {
+        self.check_id(12)?;
+        OBJECT_COMPRESSION.decompress_limited(self.value, &self.limits)
+    }
+
+    /// Copy the tag into an owned `OdsValue`.
+    pub fn to_value(&self) -> Result<OdsValue> {
+        OdsValue::read_value(self.id, self.value.to_vec(), &self.limits)
+    }
+
+    /// Copy the tag into an owned Tag.
+    ///
+    /// # Returns
+    /// `OdsError::TypeMismatch` if the tag is not of type `T`.
+    pub fn to_tag<T: Taggable<T>>(&self) -> Result<Tag<T>> {
+        let mut value_stream = Stream::new_with_data(self.value.to_vec());
+        value_stream.set_limits(self.limits);
+        value_stream.set_position(0);
+
+        let mut current_builder = TagBuilder::new();
+        current_builder.set_data_type(self.id as i32);
+        current_builder.set_name(self.name.to_string());
+        current_builder.set_value_length(self.value.len() as i32);
+        current_builder.set_value_bytes(value_stream);
+        current_builder.process::<T>()
+    }
+
+    /// Check that the tag has the specified id.
+    fn check_id(&self, id: u8) -> Result<()> {
+        if self.id != id {
+            return Err(OdsError::TypeMismatch {
+                expected: id,
+                found: self.id
+            });
+        }
+        Ok(())
+    }
+
+    /// Get the value of a tag whose value always has the same size.
+    fn fixed_value<const N: usize>(&self, id: u8) -> Result<[u8; N]> {
+        self.check_id(id)?;
+        <[u8; N]>::try_from(self.value).map_err(|_| OdsError::Truncated)
+    }
+}
+
+/// An iterator over encoded tags, that reads one tag at a time.
+///
+/// The iterator ends after the first tag that cannot be read.
+#[derive(Debug, Clone)]
+pub struct TagIter<'a> {
+    data: &'a [u8],
+    limits: ParseLimits,
+    count: usize
+}
+
+impl<'a> TagIter<'a> {
+    /// Iterate over encoded tags that are checked against limits. (See `ParseLimits`.)
+    pub fn new(data: &'a [u8], limits: ParseLimits) -> TagIter<'a> {
+        TagIter {
+            data,
+            limits,
+            count: 0
+        }
+    }
+
+    /// Find a tag using a key. Names select the children of Vec, Map or Object Tags, and indexes select them by position.
+    ///
+    /// # Returns
+    /// The tag, or None if it does not exist.
+    /// `OdsError::TypeMismatch` if the key continues inside of a tag that cannot hold other tags (or a Compressed Object Tag).
+    pub fn get(self, key: impl IntoKeyPath) -> Result<Option<TagRef<'a>>> {
+        let key = key.into_key_path()?;
+        if key.is_empty() {
+            return Err(OdsError::InvalidKey(String::new()));
+        }
+
+        let mut tags = self;
+        let mut found: Option<TagRef<'a>> = Option::None;
+        for segment in key.segments() {
+            if let Some(parent) = found {
+                tags = parent.children()?;
+            }
+            found = match segment {
+                KeySegment::Name(name) => tags.find_name(name)?,
+                KeySegment::Index(index) => tags.nth_tag(*index)?
+            };
+            if found.is_none() {
+                return Ok(Option::None);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Find the next tag with the specified name.
+    fn find_name(&mut self, name: &str) -> Result<Option<TagRef<'a>>> {
+        for tag in self {
+            let tag = tag?;
+            if tag.name == name {
+                return Ok(Some(tag));
+            }
+        }
+        Ok(Option::None)
+    }
+
+    /// Get the tag that is `index` tags after the next one.
+    fn nth_tag(&mut self, index: usize) -> Result<Option<TagRef<'a>>> {
+        self.nth(index).transpose()
+    }
+
+    /// Read the tag at the start of the data.
+    fn read_tag(&mut self) -> Result<TagRef<'a>> {
+        if self.count == 0 {
+            self.limits.check(Limit::TotalSize, self.data.len())?;
+        }
+        self.count += 1;
+        self.limits.check(Limit::Elements, self.count)?;
+        if self.data.len() < HEADER_SIZE {
+            return Err(OdsError::Truncated);
+        }
+
+        let data = self.data;
+        let size = usize::try_from(i32::from_be_bytes([data[1], data[2], data[3], data[4]])).map_err(|_| OdsError::Truncated)?;
+        if size < 2 || 5 + size > data.len() {
+            return Err(OdsError::Truncated);
+        }
+        self.limits.check(Limit::TagSize, size)?;
+        let name_length = usize::try_from(i16::from_be_bytes([data[5], data[6]])).map_err(|_| OdsError::Truncated)?;
+        if name_length > size - 2 {
+            return Err(OdsError::Truncated);
+        }
+        self.limits.check(Limit::NameLength, name_length)?;
+
+        let name_end = HEADER_SIZE + name_length;
+        let name = std::str::from_utf8(&data[HEADER_SIZE..name_end])
+            .map_err(|err| OdsError::Io(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+        self.data = &data[5 + size..];
+        Ok(TagRef {
+            id: data[0],
+            name,
+            value: &data[name_end..5 + size],
+            limits: self.limits
+        })
+    }
+}
+
+impl<'a> Iterator for TagIter<'a> {
+    type Item = Result<TagRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return Option::None;
+        }
+        let tag = self.read_tag();
+        if tag.is_err() {
+            self.data = &[];
+        }
+        Some(tag)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,373 @@
+use std::any::Any;
+
+use crate::error::{OdsError, Result};
+use crate::internal::internal_utils::{get_list_data, read_tag_header, OBJECT_COMPRESSION};
+use crate::io::streams::{IORead, StandardIO, Stream};
+use crate::key_path::{IntoKeyPath, KeyPath, KeySegment};
+use crate::limits::{Limit, ParseLimits};
+use crate::tags::general::{AnyTag, CompressedObjectTag, Container, MapContainer, MapTag, ObjectContainer, ObjectTag, Tag, Taggable, VecTag, VectorContainer, CompressedObjectContainer};
+use crate::util::write_any_tag;
+
+/// An owned value of any tag type.
+///
+/// Unlike an `AnyTag`, the type of the value does not need to be known to use it.
+/// Containers hold their children as OdsValues, so a whole document can be walked, changed and
+/// written back. The children of objects and maps are kept in the order they were read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OdsValue {
+    String(String),
+    Int(i32),
+    Float(f32),
+    Double(f64),
+    Short(i16),
+    Long(i64),
+    Char(char),
+    Byte(u8),
+    Vec(Vec<OdsValue>),
+    Map(Vec<(String, OdsValue)>),
+    Object(Vec<(String, OdsValue)>),
+    CompressedObject(Vec<(String, OdsValue)>)
+}
+
+impl OdsValue {
+    /// Parse encoded tags into an `OdsValue::Object` that holds every tag.
+    ///
+    /// # Params
+    /// data: `&[u8]` -> The encoded tags. (Ex: The bytes of an ODS file.)
+    pub fn from_bytes(data: &[u8]) -> Result<OdsValue> {
+        OdsValue::from_bytes_with_limits(data, &ParseLimits::default())
+    }
+
+    /// Parse encoded tags that may not be trusted. See `from_bytes` and `ParseLimits`.
+    ///
+    /// # Params
+    /// data: `&[u8]` -> The encoded tags.
+    /// limits: `&ParseLimits` -> The limits to check the tags against.
+    pub fn from_bytes_with_limits(data: &[u8], limits: &ParseLimits) -> Result<OdsValue> {
+        Ok(OdsValue::Object(read_values(data, limits)?))
+    }
+
+    /// Encode the value.
+    ///
+    /// Objects (and Compressed Objects and Maps) are written as the list of their children, which is the
+    /// format of an ODS file. This is the opposite of `from_bytes`.
+    /// Any other value is written as a single tag without a name.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        match self {
+            OdsValue::Object(entries) | OdsValue::CompressedObject(entries) | OdsValue::Map(entries) => write_values(entries),
+            _ => {
+                let mut write_stream = Stream::new_empty();
+                self.write_tag(String::new(), &mut write_stream)?;
+                Ok(write_stream.bytes())
+            }
+        }
+    }
+
+    /// Convert an AnyTag into its name and value.
+    pub fn from_any_tag(tag: &AnyTag) -> Result<(String, OdsValue)> {
+        let mut write_stream = Stream::new_empty();
+        write_any_tag(tag, &mut write_stream)?;
+        read_values(&write_stream.bytes(), &ParseLimits::default())?.pop().ok_or(OdsError::Truncated)
+    }
+
+    /// Convert the value into an AnyTag with the specified name.
+    pub fn to_any_tag(&self, name: String) -> Result<AnyTag> {
+        let mut write_stream = Stream::new_empty();
+        self.write_tag(name, &mut write_stream)?;
+        let size = write_stream.size() as i32;
+        write_stream.set_position(0);
+        get_list_data(write_stream, size)?.pop().ok_or(OdsError::Truncated)
+    }
+
+    /// Get the id of the tag type of the value.
+    pub fn get_id(&self) -> u8 {
+        match self {
+            OdsValue::String(_) => 1,
+            OdsValue::Int(_) => 2,
+            OdsValue::Float(_) => 3,
+            OdsValue::Double(_) => 4,
+            OdsValue::Short(_) => 5,
+            OdsValue::Long(_) => 6,
+            OdsValue::Char(_) => 7,
+            OdsValue::Byte(_) => 8,
+            OdsValue::Vec(_) => 9,
+            OdsValue::Map(_) => 10,
+            OdsValue::Object(_) => 11,
+            OdsValue::CompressedObject(_) => 12
+        }
+    }
+
+    /// Get a value using a key. Names select the children of an Object, Compressed Object or Map, and indexes
+    /// select the elements of a Vec. (Ex: `players[3].name`)
+    ///
+    /// # Returns
+    /// The value, or None if it does not exist or the key is not valid.
+    pub fn get(&self, key: impl IntoKeyPath) -> Option<&OdsValue> {
+        let key = key.into_key_path().ok()?;
+        if key.is_empty() {
+            return None;
+        }
+
+        let mut value = self;
+        for segment in key.segments() {
+            value = value.child(segment)?;
+        }
+        Some(value)
+    }
+
+    /// Get a mutable value using a key. See `get`.
+    pub fn get_mut(&mut self, key: impl IntoKeyPath) -> Option<&mut OdsValue> {
+        let key = key.into_key_path().ok()?;
+        if key.is_empty() {
+            return None;
+        }
+
+        let mut value = self;
+        for segment in key.segments() {
+            value = value.child_mut(segment)?;
+        }
+        Some(value)
+    }
+
+    /// Set a value using a key. An existing value is replaced, otherwise the value is added
+    /// and any Objects that are missing along the key are created.
+    ///
+    /// # Returns
+    /// `OdsError::TypeMismatch` if a section of the key exists but cannot hold other values, or
+    /// `OdsError::KeyNotFound` if an index section of the key does not exist. (Only names can be created.)
+    pub fn set(&mut self, key: impl IntoKeyPath, value: OdsValue) -> Result<()> {
+        let key = key.into_key_path()?;
+        if key.is_empty() {
+            return Err(OdsError::InvalidKey(String::new()));
+        }
+
+        let mut current = self;
+        for segment in key.segments() {
+            current = current.child_or_insert(segment, &key)?;
+        }
+        *current = value;
+        Ok(())
+    }
+
+    /// Remove a value using a key. See `get`.
+    ///
+    /// # Returns
+    /// The removed value, or None if it does not exist.
+    pub fn remove(&mut self, key: impl IntoKeyPath) -> Option<OdsValue> {
+        let key = key.into_key_path().ok()?;
+        let (last, parents) = key.segments().split_last()?;
+
+        let mut parent = self;
+        for segment in parents {
+            parent = parent.child_mut(segment)?;
+        }
+
+        match (last, parent) {
+            (KeySegment::Index(index), OdsValue::Vec(values)) => {
+                if *index >= values.len() {
+                    return None;
+                }
+                Some(values.remove(*index))
+            }
+            (segment, parent) => {
+                let entries = parent.entries_mut()?;
+                let index = match segment {
+                    KeySegment::Name(name) => entries.iter().position(|(entry_name, _)| entry_name == name)?,
+                    KeySegment::Index(index) => Some(*index).filter(|index| *index < entries.len())?
+                };
+                Some(entries.remove(index).1)
+            }
+        }
+    }
+
+    /// Get the child that a segment of a key points to.
+    fn child(&self, segment: &KeySegment) -> Option<&OdsValue> {
+        match (segment, self) {
+            (KeySegment::Index(index), OdsValue::Vec(values)) => values.get(*index),
+            (KeySegment::Index(index), value) => value.entries()?.get(*index).map(|(_, value)| value),
+            (KeySegment::Name(name), value) => value.entries()?.iter().find(|(entry_name, _)| entry_name == name).map(|(_, value)| value)
+        }
+    }
+
+    /// Get the mutable child that a segment of a key points to.
+    fn child_mut(&mut self, segment: &KeySegment) -> Option<&mut OdsValue> {
+        match (segment, self) {
+            (KeySegment::Index(index), OdsValue::Vec(values)) => values.get_mut(*index),
+            (KeySegment::Index(index), value) => value.entries_mut()?.get_mut(*index).map(|(_, value)| value),
+            (KeySegment::Name(name), value) => value.entries_mut()?.iter_mut().find(|(entry_name, _)| entry_name == name).map(|(_, value)| value)
+        }
+    }
+
+    /// Get the mutable child that a segment of a key points to. A missing name is added as an empty Object.
+    fn child_or_insert(&mut self, segment: &KeySegment, key: &KeyPath) -> Result<&mut OdsValue> {
+        let id = self.get_id();
+        let type_mismatch = OdsError::TypeMismatch {
+            expected: 11,
+            found: id
+        };
+
+        match (segment, self) {
+            (KeySegment::Index(index), OdsValue::Vec(values)) => values.get_mut(*index).ok_or_else(|| OdsError::KeyNotFound(key.to_string())),
+            (KeySegment::Index(index), value) => {
+                let entries = value.entries_mut().ok_or(type_mismatch)?;
+                entries.get_mut(*index).map(|(_, value)| value).ok_or_else(|| OdsError::KeyNotFound(key.to_string()))
+            }
+            (KeySegment::Name(name), value) => {
+                let entries = value.entries_mut().ok_or(type_mismatch)?;
+                let index = match entries.iter().position(|(entry_name, _)| entry_name == name) {
+                    Some(index) => index,
+                    None => {
+                        entries.push((name.clone(), OdsValue::Object(Vec::new())));
+                        entries.len() - 1
+                    }
+                };
+                Ok(&mut entries[index].1)
+            }
+        }
+    }
+
+    /// Get the named children of an Object, Compressed Object or Map.
+    pub fn entries(&self) -> Option<&Vec<(String, OdsValue)>> {
+        match self {
+            OdsValue::Object(entries) | OdsValue::CompressedObject(entries) | OdsValue::Map(entries) => Some(entries),
+            _ => None
+        }
+    }
+
+    /// Get the mutable named children of an Object, Compressed Object or Map.
+    pub fn entries_mut(&mut self) -> Option<&mut Vec<(String, OdsValue)>> {
+        match self {
+            OdsValue::Object(entries) | OdsValue::CompressedObject(entries) | OdsValue::Map(entries) => Some(entries),
+            _ => None
+        }
+    }
+
+    /// Get the elements of a Vec.
+    pub fn as_vec(&self) -> Option<&Vec<OdsValue>> {
+        match self {
+            OdsValue::Vec(values) => Some(values),
+            _ => None
+        }
+    }
+
+    /// Get the value of a String.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            OdsValue::String(value) => Some(value),
+            _ => None
+        }
+    }
+
+    /// Get the value of any integer type (Byte, Short, Int or Long).
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            OdsValue::Byte(value) => Some(*value as i64),
+            OdsValue::Short(value) => Some(*value as i64),
+            OdsValue::Int(value) => Some(*value as i64),
+            OdsValue::Long(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    /// Get the value of any floating point type (Float or Double).
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            OdsValue::Float(value) => Some(*value as f64),
+            OdsValue::Double(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    /// Write the value as a tag with the specified name.
+    pub fn write_tag(&self, name: String, write_stream: &mut Stream) -> Result<()> {
+        match self {
+            OdsValue::String(value) => String::write_data(Tag::new(name, value.clone()), write_stream),
+            OdsValue::Int(value) => i32::write_data(Tag::new(name, *value), write_stream),
+            OdsValue::Float(value) => f32::write_data(Tag::new(name, *value), write_stream),
+            OdsValue::Double(value) => f64::write_data(Tag::new(name, *value), write_stream),
+            OdsValue::Short(value) => i16::write_data(Tag::new(name, *value), write_stream),
+            OdsValue::Long(value) => i64::write_data(Tag::new(name, *value), write_stream),
+            OdsValue::Char(value) => char::write_data(Tag::new(name, *value), write_stream),
+            OdsValue::Byte(value) => u8::write_data(Tag::new(name, *value), write_stream),
+            OdsValue::Vec(values) => {
+                let mut value_stream = Stream::new_empty();
+                for value in values.iter() {
+                    value.write_tag(String::new(), &mut value_stream)?;
+                }
+                Container::<VectorContainer>::write_data(VecTag::new(name, Container::new_with_data(value_stream.bytes())), write_stream)
+            }
+            OdsValue::Map(entries) => {
+                let tag: MapTag<Box<dyn Any>> = MapTag::new(name, Container::new_with_data(write_values(entries)?));
+                Container::<MapContainer<Box<dyn Any>>>::write_data(tag, write_stream)
+            }
+            OdsValue::Object(entries) => {
+                Container::<ObjectContainer>::write_data(ObjectTag::new(name, Container::new_with_data(write_values(entries)?)), write_stream)
+            }
+            OdsValue::CompressedObject(entries) => {
+                let tag = CompressedObjectTag::new(name, Container::new_with_data(write_values(entries)?));
+                Container::<CompressedObjectContainer>::write_data(tag, write_stream)
+            }
+        }
+    }
+
+    /// Read the value of a tag.
+    ///
+    /// # Params
+    /// data_type: u8 -> The id of the tag.
+    /// value: `Vec<u8>` -> The encoded value of the tag. (Without the id, size and name.)
+    /// limits: `&ParseLimits` -> The limits of the data that holds the tag.
+    pub(crate) fn read_value(data_type: u8, value: Vec<u8>, limits: &ParseLimits) -> Result<OdsValue> {
+        let mut read_stream = Stream::new_with_data(value);
+        read_stream.set_position(0);
+        let size = read_stream.size() as u64;
+        Ok(match data_type {
+            1 => OdsValue::String(read_stream.read_string(size)?),
+            2 => OdsValue::Int(read_stream.read_i32()?),
+            3 => OdsValue::Float(read_stream.read_f32()?),
+            4 => OdsValue::Double(read_stream.read_f64()?),
+            5 => OdsValue::Short(read_stream.read_i16()?),
+            6 => OdsValue::Long(read_stream.read_i64()?),
+            7 => OdsValue::Char(read_stream.read_char()?),
+            8 => OdsValue::Byte(read_stream.read()?),
+            9 => OdsValue::Vec(read_values(&read_stream.bytes(), &limits.descend()?)?.into_iter().map(|(_, value)| value).collect()),
+            10 => OdsValue::Map(read_values(&read_stream.bytes(), &limits.descend()?)?),
+            11 => OdsValue::Object(read_values(&read_stream.bytes(), &limits.descend()?)?),
+            12 => {
+                let decompressed = OBJECT_COMPRESSION.decompress_limited(&read_stream.bytes(), limits)?;
+                OdsValue::CompressedObject(read_values(&decompressed, &limits.descend()?)?)
+            }
+            id => return Err(OdsError::UnknownTag(id))
+        })
+    }
+}
+
+/// Read every tag in the data as a name and a value.
+fn read_values(data: &[u8], limits: &ParseLimits) -> Result<Vec<(String, OdsValue)>> {
+    let mut read_stream = Stream::new_with_data(data.to_vec());
+    read_stream.set_limits(*limits);
+    read_stream.set_position(0);
+
+    let mut output = Vec::new();
+    while read_stream.can_read_more() {
+        limits.check(Limit::Elements, output.len() + 1)?;
+        let current_builder = read_tag_header(&mut read_stream)?;
+        let name = read_stream.read_string(current_builder.name_size as u64)?;
+
+        let value_length = current_builder.starting_index + current_builder.data_size as i64 - read_stream.position() as i64;
+        let mut value = vec![0; value_length as usize];
+        read_stream.read_vec(&mut value)?;
+
+        output.push((name, OdsValue::read_value(current_builder.data_type as u8, value, limits)?));
+    }
+
+    Ok(output)
+}
+
+/// Write a list of named values as tags.
+fn write_values(entries: &[(String, OdsValue)]) -> Result<Vec<u8>> {
+    let mut write_stream = Stream::new_empty();
+    for (name, value) in entries.iter() {
+        value.write_tag(name.clone(), &mut write_stream)?;
+    }
+    Ok(write_stream.bytes())
+}
\ No newline at end of file
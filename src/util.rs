@@ -1,43 +1,67 @@
-use crate::tags::general::{AnyTag, VecTag, Container, VectorContainer, ObjectContainer};
-use crate::io::streams::{WriteStream, Stream};
+use crate::error::{OdsError, Result};
+use std::any::Any;
+
+use crate::tags::general::{AnyTag, Container, VectorContainer, MapContainer, ObjectContainer, CompressedObjectContainer};
+use crate::io::streams::{StandardIO, Stream};
 use crate::tags::general::Taggable;
 
-pub(crate) fn write_any_tag<'a>(tag: &'a AnyTag, write_stream: &'a mut Stream) {
+pub(crate) fn write_any_tag(tag: &AnyTag, write_stream: &mut Stream) -> Result<()> {
     match tag.get_id() {
-        1 => String::write_data(tag.downcast_any_tag::<String>(), write_stream),
-        2 => i32::write_data(tag.downcast_any_tag::<i32>(), write_stream),
-        3 => f32::write_data(tag.downcast_any_tag::<f32>(), write_stream),
-        4 => f64::write_data(tag.downcast_any_tag::<f64>(), write_stream),
-        5 => i16::write_data(tag.downcast_any_tag::<i16>(), write_stream),
-        6 => i64::write_data(tag.downcast_any_tag::<i64>(), write_stream),
-        7 => char::write_data(tag.downcast_any_tag::<char>(), write_stream),
-        8 => u8::write_data(tag.downcast_any_tag::<u8>(), write_stream),
+        1 => String::write_data(tag.downcast_any_tag::<String>()?, write_stream),
+        2 => i32::write_data(tag.downcast_any_tag::<i32>()?, write_stream),
+        3 => f32::write_data(tag.downcast_any_tag::<f32>()?, write_stream),
+        4 => f64::write_data(tag.downcast_any_tag::<f64>()?, write_stream),
+        5 => i16::write_data(tag.downcast_any_tag::<i16>()?, write_stream),
+        6 => i64::write_data(tag.downcast_any_tag::<i64>()?, write_stream),
+        7 => char::write_data(tag.downcast_any_tag::<char>()?, write_stream),
+        8 => u8::write_data(tag.downcast_any_tag::<u8>()?, write_stream),
         9 => {
             type T = Container<VectorContainer>;
-            T::write_data(tag.downcast_any_tag::<Container<VectorContainer>>(), write_stream);
+            T::write_data(tag.downcast_any_tag::<Container<VectorContainer>>()?, write_stream)
+        },
+        10 => {
+            type T = Container<MapContainer<Box<dyn Any>>>;
+            T::write_data(tag.downcast_any_map_tag()?, write_stream)
         },
         11 => {
             type T = Container<ObjectContainer>;
-            T::write_data(tag.downcast_any_tag::<Container<ObjectContainer>>(), write_stream);
+            T::write_data(tag.downcast_any_tag::<Container<ObjectContainer>>()?, write_stream)
+        },
+        12 => {
+            type T = Container<CompressedObjectContainer>;
+            T::write_data(tag.downcast_any_tag::<Container<CompressedObjectContainer>>()?, write_stream)
         },
-        _ => {
-            panic!("Unknown type!");
-        }
+        id => Err(OdsError::UnknownTag(id))
+    }
+}
+
+/// Encode a list of AnyTags.
+///
+/// # Returns
+/// The bytes of all of the tags, in order.
+pub(crate) fn write_any_tags(tags: &[AnyTag]) -> Result<Vec<u8>> {
+    let mut write_stream = Stream::new_empty();
+    for tag in tags.iter() {
+        write_any_tag(tag, &mut write_stream)?;
     }
+    Ok(write_stream.bytes())
 }
 
+#[allow(unused_macros)]
 macro_rules! tag (
     ($type:tt, $name:expr, $value:expr) => [
         $type::new($name.to_string(), $value)
     ]
 );
 
+#[allow(unused_macros)]
 macro_rules! anytag_vec {
     ($($x:expr),*) => {
         vec![$(as_any_tag![$x]),*]
     }
 }
 
+#[allow(unused_macros)]
 macro_rules! as_any_tag {
     ($x:expr) => {
         crate::tags::general::AnyTag::from_tag($x)
@@ -0,0 +1,292 @@
+//! Check the structure of an encoded ODS document without loading it.
+//!
+//! `validate` walks the whole tag tree and reports every problem it finds, instead of stopping at the first one
+//! like the other methods do. A problem with the header of a tag (Ex: A size that does not fit inside of its parent)
+//! means the tags after it cannot be found, so the rest of that parent is skipped.
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::internal::internal_utils::OBJECT_COMPRESSION;
+use crate::key_path::KeyPath;
+use crate::limits::ParseLimits;
+
+/// The size of the id and the data size of a tag.
+const ID_AND_SIZE: usize = 5;
+/// The size of the name length of a tag.
+const NAME_LENGTH: usize = 2;
+
+/// The problems that were found in a document.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationReport {
+    problems: Vec<Problem>,
+    tag_count: usize
+}
+
+impl ValidationReport {
+    /// Check if no problems were found.
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Get the problems, in the order they appear in the document.
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
+    /// Get the number of tags that were checked.
+    pub fn tag_count(&self) -> usize {
+        self.tag_count
+    }
+
+    fn add(&mut self, offset: u64, compressed: Option<u64>, key: &KeyPath, kind: ProblemKind) {
+        self.problems.push(Problem {
+            offset,
+            compressed,
+            key: key.clone(),
+            kind
+        });
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_valid() {
+            return write!(f, "no problems found in {} tags", self.tag_count);
+        }
+        write!(f, "{} problems found in {} tags:", self.problems.len(), self.tag_count)?;
+        for problem in self.problems.iter() {
+            write!(f, "\n  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// A problem that was found in a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// The byte offset of the tag (or the value) with the problem.
+    ///
+    /// Inside of a Compressed Object Tag this is the offset in its decompressed value.
+    pub offset: u64,
+    /// The offset of the Compressed Object Tag that holds the problem, if there is one.
+    pub compressed: Option<u64>,
+    /// The key of the tag with the problem. If the name of the tag could not be read, this is the key of its parent.
+    pub key: KeyPath,
+    /// What is wrong.
+    pub kind: ProblemKind
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "offset {}", self.offset)?;
+        if let Some(compressed) = self.compressed {
+            write!(f, " (in the compressed object at {})", compressed)?;
+        }
+        if !self.key.is_empty() {
+            write!(f, " `{}`", self.key)?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// The kind of a problem that was found in a document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProblemKind {
+    /// The tag id does not belong to any known tag type.
+    UnknownTag(u8),
+    /// The data size of the tag does not fit inside of its parent.
+    InvalidSize {
+        size: i32,
+        available: u64
+    },
+    /// The name length of the tag does not fit inside of the tag.
+    InvalidNameLength {
+        length: i16,
+        available: u64
+    },
+    /// The parent ends in the middle of the header of a tag, so its children do not exactly fill it.
+    TrailingBytes(u64),
+    /// The name of the tag is not valid UTF-8.
+    InvalidName,
+    /// The value of a String Tag is not valid UTF-8.
+    InvalidString,
+    /// The value of a number tag has the wrong size.
+    InvalidValueSize {
+        expected: u64,
+        found: u64
+    },
+    /// The value of a Compressed Object Tag could not be decompressed.
+    InvalidCompression(String),
+    /// The containers are nested deeper than `ParseLimits::default` allows, so the tags inside of this one are not checked.
+    TooDeep
+}
+
+impl fmt::Display for ProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProblemKind::UnknownTag(id) => write!(f, "unknown tag id {}", id),
+            ProblemKind::InvalidSize { size, available } => write!(f, "the data size {} does not fit in the {} bytes of the parent", size, available),
+            ProblemKind::InvalidNameLength { length, available } => write!(f, "the name length {} does not fit in the {} bytes of the tag", length, available),
+            ProblemKind::TrailingBytes(count) => write!(f, "{} bytes are left that are too short for a tag", count),
+            ProblemKind::InvalidName => write!(f, "the name is not valid UTF-8"),
+            ProblemKind::InvalidString => write!(f, "the string is not valid UTF-8"),
+            ProblemKind::InvalidValueSize { expected, found } => write!(f, "expected a value of {} bytes, found {} bytes", expected, found),
+            ProblemKind::InvalidCompression(err) => write!(f, "the value could not be decompressed: {}", err),
+            ProblemKind::TooDeep => write!(f, "the containers are nested too deep")
+        }
+    }
+}
+
+/// Check the structure of an encoded document.
+///
+/// Every tag id must be known, every data size and name length must fit inside of the parent,
+/// the children of every container must exactly fill it, names and strings must be valid UTF-8
+/// and number values must have the right size.
+///
+/// # Params
+/// data: `&[u8]` -> The encoded tags of the document.
+pub fn validate(data: &[u8]) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    validate_tags(data, &Location::default(), false, &mut report);
+    report
+}
+
+/// Where the data that is checked is in the document.
+#[derive(Default)]
+struct Location {
+    /// The offset of the data.
+    offset: u64,
+    /// The offset of the Compressed Object Tag that holds the data.
+    compressed: Option<u64>,
+    /// The key of the tag that holds the data.
+    key: KeyPath,
+    /// The limits of the data, which limit how deep the check can go.
+    limits: ParseLimits
+}
+
+/// Check a list of tags that fill the data.
+///
+/// # Params
+/// data: `&[u8]` -> The tags.
+/// location: `&Location` -> Where the data is.
+/// indexed: bool -> If the tags are the elements of a Vec Tag, so they are found by index instead of by name.
+/// report: `&mut ValidationReport` -> The report to add the problems to.
+fn validate_tags(data: &[u8], location: &Location, indexed: bool, report: &mut ValidationReport) {
+    let mut position = 0;
+    let mut index = 0;
+    while position < data.len() {
+        let offset = location.offset + position as u64;
+        let remaining = data.len() - position;
+        if remaining < ID_AND_SIZE + NAME_LENGTH {
+            report.add(offset, location.compressed, &location.key, ProblemKind::TrailingBytes(remaining as u64));
+            return;
+        }
+
+        let id = data[position];
+        let size = i32::from_be_bytes([data[position + 1], data[position + 2], data[position + 3], data[position + 4]]);
+        let available = remaining - ID_AND_SIZE;
+        let size_bytes = match usize::try_from(size) {
+            Ok(size_bytes) if size_bytes >= NAME_LENGTH && size_bytes <= available => size_bytes,
+            _ => {
+                report.add(offset, location.compressed, &location.key, ProblemKind::InvalidSize { size, available: available as u64 });
+                return;
+            }
+        };
+
+        let name_start = position + ID_AND_SIZE + NAME_LENGTH;
+        let length = i16::from_be_bytes([data[position + ID_AND_SIZE], data[position + ID_AND_SIZE + 1]]);
+        let name_available = size_bytes - NAME_LENGTH;
+        let name_end = match usize::try_from(length) {
+            Ok(name_length) if name_length <= name_available => name_start + name_length,
+            _ => {
+                report.add(offset, location.compressed, &location.key, ProblemKind::InvalidNameLength { length, available: name_available as u64 });
+                return;
+            }
+        };
+
+        let name = &data[name_start..name_end];
+        let mut key = location.key.clone();
+        if indexed {
+            key.push_index(index);
+        } else {
+            key.push_name(String::from_utf8_lossy(name).into_owned());
+        }
+        if std::str::from_utf8(name).is_err() {
+            report.add(offset, location.compressed, &key, ProblemKind::InvalidName);
+        }
+
+        report.tag_count += 1;
+        let tag_end = position + ID_AND_SIZE + size_bytes;
+        let value = Location {
+            offset: location.offset + name_end as u64,
+            compressed: location.compressed,
+            key,
+            limits: location.limits
+        };
+        validate_value(id, &data[name_end..tag_end], offset, &value, report);
+
+        position = tag_end;
+        index += 1;
+    }
+}
+
+/// Check the value of a tag.
+///
+/// # Params
+/// id: u8 -> The id of the tag.
+/// data: `&[u8]` -> The value of the tag.
+/// offset: u64 -> The offset of the tag.
+/// location: `&Location` -> Where the value is.
+/// report: `&mut ValidationReport` -> The report to add the problems to.
+fn validate_value(id: u8, data: &[u8], offset: u64, location: &Location, report: &mut ValidationReport) {
+    if let Some(expected) = fixed_value_size(id) {
+        if data.len() != expected {
+            report.add(location.offset, location.compressed, &location.key, ProblemKind::InvalidValueSize { expected: expected as u64, found: data.len() as u64 });
+        }
+        return;
+    }
+
+    match id {
+        1 => {
+            if std::str::from_utf8(data).is_err() {
+                report.add(location.offset, location.compressed, &location.key, ProblemKind::InvalidString);
+            }
+        }
+        9..=12 => {
+            let limits = match location.limits.descend() {
+                Ok(limits) => limits,
+                Err(_) => return report.add(offset, location.compressed, &location.key, ProblemKind::TooDeep)
+            };
+            let mut inner = Location {
+                offset: location.offset,
+                compressed: location.compressed,
+                key: location.key.clone(),
+                limits
+            };
+            if id != 12 {
+                return validate_tags(data, &inner, id == 9, report);
+            }
+
+            match OBJECT_COMPRESSION.decompress_limited(data, &location.limits) {
+                Ok(decompressed) => {
+                    inner.offset = 0;
+                    inner.compressed = Some(offset);
+                    validate_tags(&decompressed, &inner, false, report);
+                }
+                Err(err) => report.add(location.offset, location.compressed, &location.key, ProblemKind::InvalidCompression(err.to_string()))
+            }
+        }
+        _ => report.add(offset, location.compressed, &location.key, ProblemKind::UnknownTag(id))
+    }
+}
+
+/// Get the size of the value of a number tag (Or a Char or Byte Tag), or None if the size of the value can change.
+pub(crate) fn fixed_value_size(id: u8) -> Option<usize> {
+    match id {
+        2 | 3 => Some(4),
+        4 | 6 => Some(8),
+        5 => Some(2),
+        7 | 8 => Some(1),
+        _ => Option::None
+    }
+}
\ No newline at end of file